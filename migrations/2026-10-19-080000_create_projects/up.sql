@@ -0,0 +1,10 @@
+-- Your SQL goes here
+CREATE TABLE projects (
+    id varchar not null primary key,
+    name varchar not null,
+    created_at timestamp not null,
+    updated_at timestamp
+);
+
+ALTER TABLE tasks
+ADD COLUMN project_id varchar REFERENCES projects(id) ON DELETE SET NULL;
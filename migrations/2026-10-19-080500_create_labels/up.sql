@@ -0,0 +1,20 @@
+-- Your SQL goes here
+CREATE TABLE labels (
+    id varchar not null primary key,
+    name varchar not null,
+    color varchar not null default '#9e9e9e',
+    project_id varchar REFERENCES projects(id) ON DELETE CASCADE,
+    created_at timestamp not null,
+    updated_at timestamp
+);
+
+-- Имя метки уникально в пределах проекта (или среди глобальных меток)
+CREATE UNIQUE INDEX labels_scope_name_idx ON labels (COALESCE(project_id, ''), lower(name));
+
+CREATE TABLE task_labels (
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    label_id varchar not null REFERENCES labels(id) ON DELETE CASCADE,
+    PRIMARY KEY (task_id, label_id)
+);
+
+CREATE INDEX task_labels_label_id_idx ON task_labels (label_id);
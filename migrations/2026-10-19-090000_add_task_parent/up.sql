@@ -0,0 +1,5 @@
+-- Your SQL goes here
+ALTER TABLE tasks
+ADD COLUMN parent_id varchar REFERENCES tasks(id);
+
+CREATE INDEX tasks_parent_id_idx ON tasks (parent_id);
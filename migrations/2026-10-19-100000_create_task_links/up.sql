@@ -0,0 +1,12 @@
+-- Your SQL goes here
+CREATE TABLE task_links (
+    id varchar not null primary key,
+    source_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    target_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    link_type varchar not null CHECK (link_type IN ('blocks', 'relates_to', 'duplicates')),
+    created_at timestamp not null,
+    UNIQUE (source_id, target_id, link_type),
+    CHECK (source_id <> target_id)
+);
+
+CREATE INDEX task_links_target_id_idx ON task_links (target_id);
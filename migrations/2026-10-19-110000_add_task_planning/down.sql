@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE tasks
+DROP COLUMN due_at,
+DROP COLUMN estimate_minutes;
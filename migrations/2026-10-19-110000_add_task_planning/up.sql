@@ -0,0 +1,4 @@
+-- Your SQL goes here
+ALTER TABLE tasks
+ADD COLUMN due_at timestamp,
+ADD COLUMN estimate_minutes int CHECK (estimate_minutes >= 0);
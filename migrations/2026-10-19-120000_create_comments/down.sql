@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE comment_mentions;
+DROP TABLE comment_revisions;
+DROP TABLE comments;
@@ -0,0 +1,28 @@
+-- Your SQL goes here
+CREATE TABLE comments (
+    id varchar not null primary key,
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    user_id varchar not null REFERENCES users(id) ON DELETE CASCADE,
+    body varchar not null,
+    created_at timestamp not null,
+    updated_at timestamp
+);
+
+CREATE INDEX comments_task_id_idx ON comments (task_id, created_at);
+
+-- Предыдущие версии текста комментария
+CREATE TABLE comment_revisions (
+    id varchar not null primary key,
+    comment_id varchar not null REFERENCES comments(id) ON DELETE CASCADE,
+    body varchar not null,
+    edited_at timestamp not null
+);
+
+CREATE INDEX comment_revisions_comment_id_idx ON comment_revisions (comment_id, edited_at);
+
+-- Пользователи, упомянутые в комментарии через @user_name
+CREATE TABLE comment_mentions (
+    comment_id varchar not null REFERENCES comments(id) ON DELETE CASCADE,
+    user_id varchar not null REFERENCES users(id) ON DELETE CASCADE,
+    PRIMARY KEY (comment_id, user_id)
+);
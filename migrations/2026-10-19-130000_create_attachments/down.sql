@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE projects
+DROP COLUMN storage_quota;
+
+DROP TABLE attachments;
@@ -0,0 +1,18 @@
+-- Your SQL goes here
+CREATE TABLE attachments (
+    id varchar not null primary key,
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    file_name varchar not null,
+    size bigint not null,
+    content_type varchar not null,
+    checksum varchar not null,
+    storage_key varchar not null unique,
+    uploaded_by varchar REFERENCES users(id) ON DELETE SET NULL,
+    created_at timestamp not null
+);
+
+CREATE INDEX attachments_task_id_idx ON attachments (task_id);
+
+-- Квота на суммарный размер вложений проекта в байтах. NULL - квота по умолчанию
+ALTER TABLE projects
+ADD COLUMN storage_quota bigint;
@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE attachment_thumbnails;
+
+ALTER TABLE attachments
+DROP COLUMN preview_status;
@@ -0,0 +1,17 @@
+-- Your SQL goes here
+-- Состояние генерации превью: pending, ready, failed. NULL - файл не является изображением
+ALTER TABLE attachments
+ADD COLUMN preview_status varchar;
+
+CREATE TABLE attachment_thumbnails (
+    id varchar not null primary key,
+    attachment_id varchar not null REFERENCES attachments(id) ON DELETE CASCADE,
+    size_name varchar not null,
+    width int not null,
+    height int not null,
+    content_type varchar not null,
+    size bigint not null,
+    storage_key varchar not null unique,
+    created_at timestamp not null,
+    UNIQUE (attachment_id, size_name)
+);
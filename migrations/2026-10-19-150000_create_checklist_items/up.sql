@@ -0,0 +1,13 @@
+-- Your SQL goes here
+CREATE TABLE checklist_items (
+    id varchar not null primary key,
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    position int4 not null,
+    text varchar not null,
+    checked boolean not null default false,
+    checked_by varchar REFERENCES users(id) ON DELETE SET NULL,
+    checked_at timestamp,
+    created_at timestamp not null
+);
+
+CREATE INDEX checklist_items_task_id_idx ON checklist_items (task_id, position);
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+-- Правило повторения задачи (RRULE из iCalendar). Время начала хранится в UTC,
+-- повторения вычисляются в часовом поясе серии.
+CREATE TABLE task_series (
+    id varchar not null primary key,
+    rrule varchar not null,
+    timezone varchar not null,
+    dtstart timestamp not null,
+    created_at timestamp not null
+);
+
+ALTER TABLE tasks ADD COLUMN series_id varchar REFERENCES task_series(id) ON DELETE SET NULL;
+
+CREATE INDEX tasks_series_id_idx ON tasks (series_id, due_at);
@@ -0,0 +1,16 @@
+-- Your SQL goes here
+-- Записи о затраченном времени. Запись без длительности - запущенный таймер.
+CREATE TABLE worklogs (
+    id varchar not null primary key,
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    user_id varchar not null REFERENCES users(id) ON DELETE CASCADE,
+    started_at timestamp not null,
+    duration_seconds int4 CHECK (duration_seconds >= 0),
+    note varchar not null default '',
+    created_at timestamp not null
+);
+
+CREATE INDEX worklogs_task_id_idx ON worklogs (task_id, started_at);
+CREATE INDEX worklogs_user_id_idx ON worklogs (user_id, started_at);
+-- У пользователя может быть только один запущенный таймер
+CREATE UNIQUE INDEX worklogs_running_timer_idx ON worklogs (user_id) WHERE duration_seconds IS NULL;
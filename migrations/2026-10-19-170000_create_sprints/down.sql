@@ -0,0 +1,5 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE tasks
+DROP COLUMN sprint_id,
+DROP COLUMN estimate_points;
+DROP TABLE sprints;
@@ -0,0 +1,24 @@
+-- Your SQL goes here
+-- Спринт проекта. При закрытии сохраняются запланированные и выполненные story points для истории скорости.
+CREATE TABLE sprints (
+    id varchar not null primary key,
+    project_id varchar not null REFERENCES projects(id) ON DELETE CASCADE,
+    name varchar not null,
+    start_date date not null,
+    end_date date not null,
+    goal varchar,
+    closed_at timestamp,
+    committed_points int,
+    completed_points int,
+    created_at timestamp not null,
+    updated_at timestamp,
+    CHECK (end_date >= start_date)
+);
+
+CREATE INDEX sprints_project_id_idx ON sprints (project_id, start_date);
+
+ALTER TABLE tasks
+ADD COLUMN estimate_points int CHECK (estimate_points >= 0),
+ADD COLUMN sprint_id varchar REFERENCES sprints(id) ON DELETE SET NULL;
+
+CREATE INDEX tasks_sprint_id_idx ON tasks (sprint_id);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE task_status_changes;
+ALTER TABLE tasks DROP COLUMN status;
@@ -0,0 +1,20 @@
+-- Your SQL goes here
+ALTER TABLE tasks ADD COLUMN status varchar not null default 'todo';
+UPDATE tasks SET status = 'done' WHERE done;
+
+-- История смены статусов задач для отчётов о ходе работ
+CREATE TABLE task_status_changes (
+    id varchar not null primary key,
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    from_status varchar,
+    to_status varchar not null,
+    changed_at timestamp not null
+);
+
+CREATE INDEX task_status_changes_task_id_idx ON task_status_changes (task_id, changed_at);
+
+-- Для существующих задач история восстанавливается по времени создания и последнего изменения
+INSERT INTO task_status_changes (id, task_id, from_status, to_status, changed_at)
+SELECT gen_random_uuid()::varchar, id, NULL, 'todo', created_at FROM tasks;
+INSERT INTO task_status_changes (id, task_id, from_status, to_status, changed_at)
+SELECT gen_random_uuid()::varchar, id, 'todo', 'done', COALESCE(updated_at, created_at) FROM tasks WHERE done;
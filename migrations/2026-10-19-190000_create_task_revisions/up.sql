@@ -0,0 +1,17 @@
+-- Your SQL goes here
+-- История изменений задач. Ссылки на задачу нет, чтобы история удалённой задачи сохранялась.
+CREATE TABLE task_revisions (
+    id varchar not null primary key,
+    task_id varchar not null,
+    action varchar not null,
+    actor_id varchar,
+    changes jsonb not null,
+    snapshot jsonb not null,
+    changed_at timestamp not null
+);
+
+CREATE INDEX task_revisions_task_id_idx ON task_revisions (task_id, changed_at);
+
+-- Для существующих задач известно только текущее состояние
+INSERT INTO task_revisions (id, task_id, action, actor_id, changes, snapshot, changed_at)
+SELECT gen_random_uuid()::varchar, t.id, 'create', NULL, '{}'::jsonb, to_jsonb(t), t.created_at FROM tasks t;
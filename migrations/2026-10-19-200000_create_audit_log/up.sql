@@ -0,0 +1,31 @@
+-- Your SQL goes here
+-- Журнал аудита. Каждая запись содержит хэш предыдущей, поэтому изменение или удаление
+-- записи нарушает цепочку. Порядок цепочки задаётся номером `seq`.
+CREATE TABLE audit_log (
+    id varchar not null primary key,
+    seq bigint not null unique,
+    action varchar not null,
+    actor_id varchar,
+    target_type varchar,
+    target_id varchar,
+    details jsonb not null,
+    ip varchar,
+    user_agent varchar,
+    request_id varchar,
+    created_at timestamp not null,
+    prev_hash varchar not null,
+    hash varchar not null
+);
+
+CREATE INDEX audit_log_action_idx ON audit_log (action, created_at);
+CREATE INDEX audit_log_actor_id_idx ON audit_log (actor_id, created_at);
+
+-- Журнал только дополняется
+CREATE FUNCTION audit_log_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'audit_log is append-only';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER audit_log_append_only BEFORE UPDATE OR DELETE ON audit_log
+    FOR EACH ROW EXECUTE FUNCTION audit_log_append_only();
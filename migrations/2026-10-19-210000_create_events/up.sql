@@ -0,0 +1,15 @@
+-- Your SQL goes here
+CREATE TABLE events (
+    id bigserial primary key,
+    kind varchar not null,
+    entity_type varchar not null,
+    entity_id varchar not null,
+    project_id varchar,
+    sprint_id varchar,
+    user_id varchar,
+    actor_id varchar,
+    payload jsonb not null,
+    created_at timestamp not null
+);
+
+CREATE INDEX events_created_at_idx ON events (created_at);
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TRIGGER events_notify ON events;
+DROP FUNCTION events_notify();
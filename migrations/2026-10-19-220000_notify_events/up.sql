@@ -0,0 +1,12 @@
+-- Your SQL goes here
+-- События изменения задач и пользователей записываются в той же транзакции, что и само изменение.
+-- Уведомление доставляется слушателям всех экземпляров сервера после завершения транзакции.
+CREATE FUNCTION events_notify() RETURNS trigger AS $$
+BEGIN
+    PERFORM pg_notify('events', NEW.id::text);
+    RETURN NULL;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER events_notify AFTER INSERT ON events
+    FOR EACH ROW EXECUTE FUNCTION events_notify();
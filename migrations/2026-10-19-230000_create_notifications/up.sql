@@ -0,0 +1,23 @@
+-- Your SQL goes here
+CREATE TABLE notifications (
+    id varchar not null primary key,
+    user_id varchar not null REFERENCES users(id) ON DELETE CASCADE,
+    kind varchar not null,
+    task_id varchar,
+    comment_id varchar,
+    actor_id varchar,
+    details jsonb not null,
+    read_at timestamp,
+    created_at timestamp not null
+);
+
+CREATE INDEX notifications_user_idx ON notifications (user_id, created_at DESC);
+CREATE INDEX notifications_unread_idx ON notifications (user_id) WHERE read_at IS NULL;
+
+CREATE TABLE notification_preferences (
+    user_id varchar not null primary key REFERENCES users(id) ON DELETE CASCADE,
+    assigned boolean not null default true,
+    mentioned boolean not null default true,
+    task_changed boolean not null default true,
+    updated_at timestamp not null
+);
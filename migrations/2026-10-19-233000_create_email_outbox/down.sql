@@ -0,0 +1,8 @@
+-- This file should undo anything in `up.sql`
+ALTER TABLE notification_preferences
+    DROP COLUMN email_mode,
+    DROP COLUMN locale,
+    DROP COLUMN due_soon,
+    DROP COLUMN digest_sent_at;
+
+DROP TABLE email_outbox;
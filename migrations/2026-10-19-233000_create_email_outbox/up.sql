@@ -0,0 +1,24 @@
+-- Your SQL goes here
+CREATE TABLE email_outbox (
+    id varchar not null primary key,
+    user_id varchar REFERENCES users(id) ON DELETE CASCADE,
+    recipient varchar not null,
+    kind varchar not null,
+    subject varchar not null,
+    body text not null,
+    dedupe_key varchar unique,
+    status varchar not null default 'pending',
+    attempts int not null default 0,
+    next_attempt_at timestamp not null,
+    last_error varchar,
+    created_at timestamp not null,
+    sent_at timestamp
+);
+
+CREATE INDEX email_outbox_pending_idx ON email_outbox (next_attempt_at) WHERE status IN ('pending', 'sending');
+
+ALTER TABLE notification_preferences
+    ADD COLUMN email_mode varchar not null default 'instant',
+    ADD COLUMN locale varchar not null default 'ru',
+    ADD COLUMN due_soon boolean not null default true,
+    ADD COLUMN digest_sent_at timestamp;
@@ -0,0 +1,26 @@
+-- Your SQL goes here
+CREATE TABLE task_watchers (
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    user_id varchar not null REFERENCES users(id) ON DELETE CASCADE,
+    created_at timestamp not null,
+    PRIMARY KEY (task_id, user_id)
+);
+
+CREATE INDEX task_watchers_user_idx ON task_watchers (user_id);
+
+-- Существующие задачи отслеживают их авторы, текущие и прежние исполнители и комментаторы
+INSERT INTO task_watchers (task_id, user_id, created_at)
+SELECT watcher.task_id, watcher.user_id, now() FROM (
+    SELECT r.task_id, r.actor_id AS user_id FROM task_revisions r
+    JOIN tasks t ON t.id = r.task_id
+    JOIN users u ON u.id = r.actor_id
+    WHERE r.action = 'create'
+    UNION
+    SELECT r.task_id, u.id FROM task_revisions r
+    JOIN tasks t ON t.id = r.task_id
+    JOIN users u ON u.id = r.snapshot->>'user_id'
+    UNION
+    SELECT id, user_id FROM tasks WHERE user_id IS NOT NULL
+    UNION
+    SELECT task_id, user_id FROM comments
+) watcher;
@@ -0,0 +1,14 @@
+-- Your SQL goes here
+CREATE TABLE task_assignees (
+    task_id varchar not null REFERENCES tasks(id) ON DELETE CASCADE,
+    user_id varchar not null REFERENCES users(id) ON DELETE CASCADE,
+    role varchar not null default 'contributor' CHECK (role IN ('owner', 'reviewer', 'contributor')),
+    created_at timestamp not null,
+    PRIMARY KEY (task_id, user_id)
+);
+
+CREATE INDEX task_assignees_user_idx ON task_assignees (user_id, role);
+
+-- Исполнитель задачи становится её основным ответственным
+INSERT INTO task_assignees (task_id, user_id, role, created_at)
+SELECT id, user_id, 'owner', now() FROM tasks WHERE user_id IS NOT NULL;
@@ -0,0 +1,33 @@
+-- Your SQL goes here
+CREATE TABLE webhooks (
+    id varchar not null primary key,
+    project_id varchar not null REFERENCES projects(id) ON DELETE CASCADE,
+    url varchar not null,
+    secret varchar not null,
+    event_types text[] not null,
+    active boolean not null default true,
+    created_at timestamp not null,
+    updated_at timestamp
+);
+
+CREATE INDEX webhooks_project_idx ON webhooks (project_id);
+
+CREATE TABLE webhook_deliveries (
+    id varchar not null primary key,
+    webhook_id varchar not null REFERENCES webhooks(id) ON DELETE CASCADE,
+    event_id bigint not null,
+    event_type varchar not null,
+    payload jsonb not null,
+    status varchar not null default 'pending',
+    attempts int not null default 0,
+    next_attempt_at timestamp not null,
+    response_status int,
+    response_body text,
+    last_error varchar,
+    redelivery_of varchar,
+    created_at timestamp not null,
+    delivered_at timestamp
+);
+
+CREATE INDEX webhook_deliveries_webhook_idx ON webhook_deliveries (webhook_id, created_at DESC);
+CREATE INDEX webhook_deliveries_pending_idx ON webhook_deliveries (next_attempt_at) WHERE status IN ('pending', 'sending');
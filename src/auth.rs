@@ -0,0 +1,188 @@
+use actix_web::{dev::Payload, Error, FromRequest, HttpRequest};
+use hmac::{Hmac, Mac};
+use rand::RngCore;
+use sha2::Sha256;
+use std::future::{ready, Ready};
+use std::sync::OnceLock;
+
+/// Схема заголовка Authorization, в котором клиент передаёт токен, выданный при входе
+const BEARER_PREFIX: &str = "Bearer ";
+
+/// Срок действия токена по умолчанию в часах
+const DEFAULT_TOKEN_TTL_HOURS: i64 = 24;
+
+/// Метод, возвращающий ключ подписи токенов. Задаётся переменной окружения AUTH_SECRET.
+/// Если переменная не задана, ключ генерируется при запуске, и токены перестают действовать после перезапуска.
+fn secret() -> &'static [u8]{
+    static SECRET: OnceLock<Vec<u8>> = OnceLock::new();
+    SECRET.get_or_init(|| match std::env::var("AUTH_SECRET") {
+        Ok(secret) if !secret.is_empty() => secret.into_bytes(),
+        _ => {
+            log::warn!("AUTH_SECRET is not set, tokens are signed with a random key and expire on restart");
+            let mut secret = vec![0u8; 32];
+            rand::thread_rng().fill_bytes(&mut secret);
+            secret
+        },
+    })
+}
+
+/// Метод, возвращающий срок действия токена. Задаётся переменной окружения AUTH_TOKEN_TTL_HOURS.
+fn token_ttl() -> chrono::Duration{
+    let hours = std::env::var("AUTH_TOKEN_TTL_HOURS")
+        .ok()
+        .and_then(|hours| hours.parse::<i64>().ok())
+        .filter(|hours| *hours > 0)
+        .unwrap_or(DEFAULT_TOKEN_TTL_HOURS);
+    chrono::Duration::hours(hours)
+}
+
+/// Метод, вычисляющий подпись данных токена.
+fn signature(key: &[u8], payload: &str) -> Hmac<Sha256>{
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(payload.as_bytes());
+    mac
+}
+
+/// Метод, выдающий токен пользователю при входе.
+/// Токен имеет вид `<user_id>.<expires>.<signature>`, где `expires` - время окончания действия в секундах Unix,
+/// а `signature` - HMAC-SHA256 от `<user_id>.<expires>` в шестнадцатеричном виде.
+/// # Arguments
+///
+/// * `user_id`     - идентификатор пользователя.
+///
+/// # Return
+///
+/// Возвращает токен и время окончания его действия.
+pub fn issue_token(user_id: &str) -> (String, chrono::NaiveDateTime){
+    let expires_at = chrono::Utc::now().naive_utc() + token_ttl();
+    let payload = format!("{}.{}", user_id, expires_at.and_utc().timestamp());
+    let signed = hex::encode(signature(secret(), &payload).finalize().into_bytes());
+    (format!("{}.{}", payload, signed), expires_at)
+}
+
+/// Метод, проверяющий подпись и срок действия токена.
+/// # Arguments
+///
+/// * `token`       - токен, выданный при входе.
+///
+/// # Return
+///
+/// Возвращает идентификатор пользователя, если токен действителен.
+pub fn verify_token(token: &str) -> Option<String>{
+    verify_token_with(secret(), token, chrono::Utc::now().timestamp())
+}
+
+/// Метод проверки токена указанным ключом на указанный момент времени.
+fn verify_token_with(key: &[u8], token: &str, now: i64) -> Option<String>{
+    let (payload, signed) = token.rsplit_once('.')?;
+    let (user_id, expires) = payload.rsplit_once('.')?;
+    // Сравнение подписи выполняется за постоянное время
+    signature(key, payload).verify_slice(&hex::decode(signed).ok()?).ok()?;
+    if user_id.is_empty() || expires.parse::<i64>().ok()? <= now {
+        return None;
+    }
+    Some(user_id.to_string())
+}
+
+/// Пользователь, от имени которого выполняется запрос.
+/// Извлекается фреймворком Actix из токена в заголовке `Authorization: Bearer <token>`.
+#[derive(Debug, Clone)]
+pub struct Actor{
+    pub user_id: String,
+}
+
+impl FromRequest for Actor{
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future{
+        let token = req
+            .headers()
+            .get(actix_web::http::header::AUTHORIZATION)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().strip_prefix(BEARER_PREFIX))
+            .map(|value| value.trim().to_string());
+        ready(match token {
+            Some(token) => verify_token(&token)
+                .map(|user_id| Actor{ user_id })
+                .ok_or_else(|| actix_web::error::ErrorUnauthorized("Invalid or expired token")),
+            None => Err(actix_web::error::ErrorUnauthorized("Missing bearer token")),
+        })
+    }
+}
+
+/// Заголовок, в котором клиент или прокси передаёт идентификатор запроса
+pub const REQUEST_ID_HEADER: &str = "X-Request-Id";
+
+/// Сведения о запросе, записываемые в журнал аудита.
+/// Если клиент не передал заголовок X-Request-Id, идентификатор запроса генерируется.
+#[derive(Debug, Clone)]
+pub struct RequestMeta{
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: String,
+}
+
+impl FromRequest for RequestMeta{
+    type Error = Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future{
+        let header = |name: &str| req
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty());
+        ready(Ok(RequestMeta{
+            ip: req.connection_info().realip_remote_addr().map(|ip| ip.to_string()),
+            user_agent: header(actix_web::http::header::USER_AGENT.as_str()),
+            request_id: header(REQUEST_ID_HEADER).unwrap_or_else(|| uuid::Uuid::new_v4().to_string()),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn issued_token_is_verified(){
+        let (token, expires_at) = issue_token("user-1");
+
+        assert_eq!(verify_token(&token).as_deref(), Some("user-1"));
+        assert!(expires_at > chrono::Utc::now().naive_utc());
+    }
+
+    #[test]
+    fn user_id_may_contain_dots(){
+        let (token, _) = issue_token("user.with.dots");
+
+        assert_eq!(verify_token(&token).as_deref(), Some("user.with.dots"));
+    }
+
+    #[test]
+    fn tampered_token_is_rejected(){
+        let (token, _) = issue_token("user-1");
+        let (_, rest) = token.split_once('.').unwrap();
+
+        assert_eq!(verify_token(&format!("admin.{}", rest)), None);
+        assert_eq!(verify_token_with(b"another key", &token, chrono::Utc::now().timestamp()), None);
+    }
+
+    #[test]
+    fn expired_token_is_rejected(){
+        let (token, expires_at) = issue_token("user-1");
+        let expires = expires_at.and_utc().timestamp();
+
+        assert_eq!(verify_token_with(secret(), &token, expires - 1).as_deref(), Some("user-1"));
+        assert_eq!(verify_token_with(secret(), &token, expires), None);
+    }
+
+    #[test]
+    fn malformed_token_is_rejected(){
+        for token in ["", "user-1", "user-1.123", "user-1.123.not-hex", ".123.00"] {
+            assert_eq!(verify_token(token), None);
+        }
+    }
+}
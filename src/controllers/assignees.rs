@@ -0,0 +1,247 @@
+use diesel::{prelude::*};
+use std::collections::HashMap;
+
+use crate::models::{AssigneeView, NewAssignee, Task, TaskAssignee};
+use crate::schema::{task_assignees, tasks, users};
+use super::{history, notifications, tasks as task_views, watchers, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Ответственный за задачу
+pub const ROLE_OWNER: &str = "owner";
+/// Проверяющий результат задачи
+pub const ROLE_REVIEWER: &str = "reviewer";
+/// Участник работы над задачей
+pub const ROLE_CONTRIBUTOR: &str = "contributor";
+/// Допустимые роли исполнителей
+pub const ROLES: [&str; 3] = [ROLE_OWNER, ROLE_REVIEWER, ROLE_CONTRIBUTOR];
+
+/// Метод проверки роли исполнителя.
+pub fn validate_role(role: &str) -> Result<(), ClientError>{
+    if ROLES.contains(&role) {
+        Ok(())
+    } else {
+        Err(ClientError::BadRequest(format!("Unknown assignee role {}, expected one of {}", role, ROLES.join(", "))))
+    }
+}
+
+/// Метод, возвращающий исполнителей задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор исполнителей задачи.
+/// Если задача не найдена, возвращается None.
+pub fn get_assignees(task: &Uuid, conn: &PgConnection) -> Result<Option<Vec<AssigneeView>>, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task.to_string()))))
+        .get_result::<bool>(conn)?;
+    if !exists {
+        return Ok(None);
+    }
+    let mut assignees = task_assignees_by_task(&[task.to_string()], conn)?;
+    Ok(Some(assignees.remove(&task.to_string()).unwrap_or_default()))
+}
+
+/// Метод, возвращающий исполнителей нескольких задач
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `ids`         - идентификаторы задач.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо исполнителей по идентификатору задачи.
+/// Основной исполнитель идёт первым, остальные - в порядке добавления.
+pub fn task_assignees_by_task(ids: &[String], conn: &PgConnection) -> Result<HashMap<String, Vec<AssigneeView>>, DbError>{
+    let rows = task_assignees::table
+        .inner_join(users::table)
+        .inner_join(tasks::table)
+        .filter(task_assignees::task_id.eq_any(ids))
+        .order((task_assignees::created_at.asc(), users::user_name.asc()))
+        .select((task_assignees::all_columns, users::user_name, tasks::user_id))
+        .load::<(TaskAssignee, String, Option<String>)>(conn)?;
+
+    let mut assignees: HashMap<String, Vec<AssigneeView>> = HashMap::new();
+    for (assignee, user_name, primary) in rows {
+        let view = AssigneeView{
+            primary: primary.as_ref() == Some(&assignee.user_id),
+            user_id: assignee.user_id,
+            user_name,
+            role: assignee.role,
+            created_at: assignee.created_at,
+        };
+        assignees.entry(assignee.task_id).or_default().push(view);
+    }
+    for list in assignees.values_mut() {
+        list.sort_by_key(|assignee| !assignee.primary);
+    }
+    Ok(assignees)
+}
+
+/// Метод, добавляющий исполнителя задачи или изменяющий его роль.
+/// Первый исполнитель с ролью `owner` становится основным исполнителем задачи, а если основной
+/// исполнитель получает другую роль, основным становится следующий исполнитель с ролью `owner`.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `assignee`    - указатель на десериализованный объект структуры NewAssignee.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор исполнителей задачи.
+/// Если задача не найдена, возвращается None, если роль неизвестна или пользователь не найден - ошибка 400.
+pub fn add_assignee(task: &Uuid, assignee: &NewAssignee, actor: Option<&str>, conn: &PgConnection) -> Result<Option<Vec<AssigneeView>>, DbError>{
+    let role = assignee.role.as_deref().unwrap_or(ROLE_CONTRIBUTOR);
+    validate_role(role)?;
+    conn.transaction::<_, DbError, _>(|| {
+        let current = match lock_task(task, conn)? {
+            Some(current) => current,
+            None => return Ok(None),
+        };
+        let user_exists = diesel::select(diesel::dsl::exists(users::table.filter(users::id.eq(&assignee.user_id))))
+            .get_result::<bool>(conn)?;
+        if !user_exists {
+            return Err(Box::new(ClientError::BadRequest(format!("User {} not found", assignee.user_id))));
+        }
+
+        let previous_role = task_assignees::table
+            .find((&current.id, &assignee.user_id))
+            .select(task_assignees::role)
+            .first::<String>(conn)
+            .optional()?;
+        diesel::insert_into(task_assignees::table)
+            .values(&TaskAssignee{
+                task_id: current.id.clone(),
+                user_id: assignee.user_id.clone(),
+                role: role.to_string(),
+                created_at: chrono::Utc::now().naive_utc(),
+            })
+            .on_conflict((task_assignees::task_id, task_assignees::user_id))
+            .do_update()
+            .set(task_assignees::role.eq(role))
+            .execute(conn)?;
+
+        let primary = match &current.user_id {
+            None if role == ROLE_OWNER => Some(assignee.user_id.clone()),
+            Some(primary) if *primary == assignee.user_id && role != ROLE_OWNER => next_owner(&current.id, conn)?,
+            primary => primary.clone(),
+        };
+        // О назначении основным исполнителем уведомляет история задачи
+        if previous_role.is_none() && primary.as_ref() != Some(&assignee.user_id) {
+            notifications::notify_assignment(&current, &assignee.user_id, role, actor, conn)?;
+            watchers::add_watchers(&current.id, &[assignee.user_id.as_str()], conn)?;
+        }
+        set_primary(&current, primary, actor, conn)?;
+        get_assignees(task, conn)
+    })
+}
+
+/// Метод, удаляющий исполнителя задачи. Если удаляется основной исполнитель,
+/// основным становится следующий исполнитель с ролью `owner`.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `user`        - уникальный идентификатор пользователя.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+/// Флаг равен false, если пользователь не является исполнителем задачи.
+pub fn remove_assignee(task: &Uuid, user: &Uuid, actor: Option<&str>, conn: &PgConnection) -> Result<bool, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let current = match lock_task(task, conn)? {
+            Some(current) => current,
+            None => return Ok(false),
+        };
+        let deleted = diesel::delete(task_assignees::table.find((&current.id, user.to_string()))).execute(conn)?;
+        if current.user_id.as_deref() == Some(user.to_string().as_str()) {
+            let primary = next_owner(&current.id, conn)?;
+            set_primary(&current, primary, actor, conn)?;
+        }
+        Ok(deleted > 0)
+    })
+}
+
+/// Метод, приводящий исполнителей задачи в соответствие с основным исполнителем после изменения задачи.
+/// Новый основной исполнитель получает роль `owner`, а прежний, если он был ответственным, перестаёт быть исполнителем,
+/// так изменение `user_id` задачи по-прежнему переназначает задачу.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `before`      - состояние задачи до изменения. None для новой задачи.
+/// * `after`       - состояние задачи после изменения. None для удалённой задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если исполнителей не удалось сохранить.
+pub fn sync_primary(before: Option<&Task>, after: Option<&Task>, conn: &PgConnection) -> Result<(), DbError>{
+    let after = match after {
+        Some(after) => after,
+        None => return Ok(()),
+    };
+    let previous = before.and_then(|before| before.user_id.as_ref());
+    if previous == after.user_id.as_ref() && before.is_some() {
+        return Ok(());
+    }
+    if let Some(previous) = previous {
+        diesel::delete(task_assignees::table
+            .find((&after.id, previous))
+            .filter(task_assignees::role.eq(ROLE_OWNER)))
+            .execute(conn)?;
+    }
+    if let Some(primary) = &after.user_id {
+        diesel::insert_into(task_assignees::table)
+            .values(&TaskAssignee{
+                task_id: after.id.clone(),
+                user_id: primary.clone(),
+                role: ROLE_OWNER.to_string(),
+                created_at: chrono::Utc::now().naive_utc(),
+            })
+            .on_conflict((task_assignees::task_id, task_assignees::user_id))
+            .do_update()
+            .set(task_assignees::role.eq(ROLE_OWNER))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Метод, загружающий задачу с блокировкой до конца транзакции.
+fn lock_task(task: &Uuid, conn: &PgConnection) -> Result<Option<Task>, DbError>{
+    let current = tasks::table
+        .find(task.to_string())
+        .for_update()
+        .first::<Task>(conn)
+        .optional()?;
+    Ok(current)
+}
+
+/// Метод, возвращающий исполнителя с ролью `owner`, добавленного к задаче раньше остальных.
+fn next_owner(task: &str, conn: &PgConnection) -> Result<Option<String>, DbError>{
+    let owner = task_assignees::table
+        .filter(task_assignees::task_id.eq(task))
+        .filter(task_assignees::role.eq(ROLE_OWNER))
+        .order((task_assignees::created_at.asc(), task_assignees::user_id.asc()))
+        .select(task_assignees::user_id)
+        .first::<String>(conn)
+        .optional()?;
+    Ok(owner)
+}
+
+/// Метод, назначающий основного исполнителя задачи и записывающий изменение в историю.
+fn set_primary(task: &Task, primary: Option<String>, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    if task.user_id == primary {
+        return Ok(());
+    }
+    let updated = diesel::update(tasks::table.find(&task.id))
+        .set((tasks::user_id.eq(primary), tasks::updated_at.eq(chrono::Utc::now().naive_utc())))
+        .get_result::<Task>(conn)?;
+    task_views::on_task_changed(history::ACTION_UPDATE, Some(task), Some(&updated), actor, conn)
+}
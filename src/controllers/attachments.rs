@@ -0,0 +1,275 @@
+use diesel::{prelude::*};
+
+use crate::models::{Attachment, AttachmentView, UploadedFile};
+use crate::schema::{attachment_thumbnails, attachments, projects, tasks, users};
+use crate::storage::Storage;
+use super::{thumbnails, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Максимальный размер одного файла по умолчанию (10 МиБ)
+const DEFAULT_MAX_FILE_SIZE: i64 = 10 * 1024 * 1024;
+
+/// Квота проекта на вложения по умолчанию (100 МиБ)
+const DEFAULT_PROJECT_QUOTA: i64 = 100 * 1024 * 1024;
+
+/// Метод, возвращающий максимальный размер одного файла в байтах.
+/// Задаётся переменной окружения ATTACHMENT_MAX_SIZE.
+pub fn max_file_size() -> i64{
+    std::env::var("ATTACHMENT_MAX_SIZE")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_MAX_FILE_SIZE)
+}
+
+/// Метод, возвращающий квоту проекта по умолчанию в байтах.
+/// Задаётся переменной окружения PROJECT_STORAGE_QUOTA.
+fn default_project_quota() -> i64{
+    std::env::var("PROJECT_STORAGE_QUOTA")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_PROJECT_QUOTA)
+}
+
+/// Метод, возвращающий вложения задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор вложений с уменьшенными копиями.
+/// Если задача не найдена, возвращается None.
+pub fn get_attachments(task: &Uuid, conn: &PgConnection) -> Result<Option<Vec<AttachmentView>>, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task.to_string()))))
+        .get_result::<bool>(conn)?;
+    if !exists {
+        return Ok(None);
+    }
+    let attachments_list = attachments::table
+        .filter(attachments::task_id.eq(task.to_string()))
+        .order(attachments::created_at.asc())
+        .load::<Attachment>(conn)?;
+
+    Ok(Some(to_views(attachments_list, conn)?))
+}
+
+/// Метод, возвращающий вложение задачи вместе с уменьшенными копиями
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `attachment`  - уникальный идентификатор объекта вложения.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо представление вложения.
+pub fn get_attachment_view(task: &Uuid, attachment: &Uuid, conn: &PgConnection) -> Result<Option<AttachmentView>, DbError>{
+    let attachment = match get_attachment(task, attachment, conn)? {
+        Some(attachment) => attachment,
+        None => return Ok(None),
+    };
+
+    Ok(to_views(vec![attachment], conn)?.pop())
+}
+
+/// Метод, дополняющий вложения их уменьшенными копиями.
+fn to_views(attachments_list: Vec<Attachment>, conn: &PgConnection) -> Result<Vec<AttachmentView>, DbError>{
+    let ids: Vec<String> = attachments_list.iter().map(|attachment| attachment.id.clone()).collect();
+    let mut by_attachment = thumbnails::get_thumbnails(&ids, conn)?;
+
+    Ok(attachments_list
+        .into_iter()
+        .map(|attachment| {
+            let thumbnails = by_attachment.remove(&attachment.id).unwrap_or_default();
+            AttachmentView{ attachment, thumbnails }
+        })
+        .collect())
+}
+
+/// Метод, возвращающий вложение задачи по идентификатору
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `attachment`  - уникальный идентификатор объекта вложения.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект вложения.
+pub fn get_attachment(task: &Uuid, attachment: &Uuid, conn: &PgConnection) -> Result<Option<Attachment>, DbError>{
+    let attachment = attachments::table
+        .filter(attachments::id.eq(attachment.to_string()))
+        .filter(attachments::task_id.eq(task.to_string()))
+        .first::<Attachment>(conn)
+        .optional()?;
+
+    Ok(attachment)
+}
+
+/// Метод, сохраняющий загруженный файл в хранилище и создающий запись о вложении.
+/// Проверяет ограничение размера файла и квоту проекта задачи.
+/// Содержимое сохраняется до записи о вложении, чтобы долгая загрузка в хранилище не держала транзакцию
+/// и блокировку квоты проекта. Если запись не удалось сохранить, содержимое удаляется из хранилища.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `uploader`    - идентификатор пользователя, загрузившего файл.
+/// * `file`        - загруженный во временный файл файл.
+/// * `storage`     - хранилище содержимого файлов.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект вложения.
+/// Если задача не найдена, возвращается None.
+pub fn create_attachment(
+    task: &Uuid,
+    uploader: &str,
+    file: &UploadedFile,
+    storage: &dyn Storage,
+    conn: &PgConnection
+) -> Result<Option<Attachment>, DbError>{
+    if file.size > max_file_size() {
+        return Err(Box::new(ClientError::PayloadTooLarge(format!(
+            "File is larger than {} bytes", max_file_size()
+        ))));
+    }
+    // Предварительная проверка, чтобы не загружать в хранилище заведомо отклоняемый файл
+    let task_project = match get_task_project(task, conn)? {
+        Some(task_project) => task_project,
+        None => return Ok(None),
+    };
+    let uploader_exists = diesel::select(diesel::dsl::exists(users::table.filter(users::id.eq(uploader))))
+        .get_result::<bool>(conn)?;
+    if !uploader_exists {
+        return Err(Box::new(ClientError::BadRequest(format!("User {} not found", uploader))));
+    }
+    if let Some(project) = &task_project {
+        check_quota(project, file.size, conn)?;
+    }
+
+    let storage_key = format!("attachments/{}/{}", task, Uuid::new_v4());
+    storage.put(&storage_key, &file.path, file.size as u64, &file.checksum)?;
+    let result = conn.transaction::<_, DbError, _>(|| {
+        // Задача могла быть удалена, а квота занята параллельной загрузкой, пока файл сохранялся
+        let task_project = match get_task_project(task, conn)? {
+            Some(task_project) => task_project,
+            None => return Ok(None),
+        };
+        if let Some(project) = task_project {
+            check_quota(&project, file.size, conn)?;
+        }
+        let new = Attachment{
+            id: Uuid::new_v4().to_string(),
+            task_id: task.to_string(),
+            file_name: file.file_name.clone(),
+            size: file.size,
+            content_type: file.content_type.clone(),
+            checksum: file.checksum.clone(),
+            storage_key: storage_key.clone(),
+            uploaded_by: Some(uploader.to_string()),
+            created_at: chrono::Utc::now().naive_utc(),
+            preview_status: if thumbnails::supports_preview(&file.content_type) {
+                Some(thumbnails::PREVIEW_PENDING.to_string())
+            } else {
+                None
+            },
+        };
+        diesel::insert_into(attachments::table).values(&new).execute(conn)?;
+        Ok(Some(new))
+    });
+    if !matches!(result, Ok(Some(_))) {
+        if let Err(err) = storage.delete(&storage_key) {
+            log::error!("Failed to delete unreferenced attachment content {}: {}", storage_key, err);
+        }
+    }
+    result
+}
+
+/// Метод, возвращающий проект задачи. Если задача не найдена, возвращается None.
+fn get_task_project(task: &Uuid, conn: &PgConnection) -> Result<Option<Option<String>>, DbError>{
+    let task_project = tasks::table
+        .filter(tasks::id.eq(task.to_string()))
+        .select(tasks::project_id)
+        .first::<Option<String>>(conn)
+        .optional()?;
+    Ok(task_project)
+}
+
+/// Метод, удаляющий вложение, его содержимое и уменьшенные копии
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `attachment`  - уникальный идентификатор объекта вложения.
+/// * `storage`     - хранилище содержимого файлов.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_attachment(task: &Uuid, attachment: &Uuid, storage: &dyn Storage, conn: &PgConnection) -> Result<bool, DbError>{
+    let attachment = match get_attachment(task, attachment, conn)? {
+        Some(attachment) => attachment,
+        None => return Ok(false),
+    };
+    let thumbnail_keys: Vec<String> = attachment_thumbnails::table
+        .filter(attachment_thumbnails::attachment_id.eq(&attachment.id))
+        .select(attachment_thumbnails::storage_key)
+        .load(conn)?;
+    diesel::delete(attachments::table.filter(attachments::id.eq(&attachment.id))).execute(conn)?;
+    storage.delete(&attachment.storage_key)?;
+    for key in thumbnail_keys {
+        storage.delete(&key)?;
+    }
+    Ok(true)
+}
+
+/// Метод, возвращающий ключи хранилища вложений задач и их уменьшенных копий.
+/// Используется перед удалением задач, так как строки вложений удаляются каскадно, а содержимое остаётся в хранилище.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task_ids`    - идентификаторы задач.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор ключей хранилища.
+pub fn get_storage_keys(task_ids: &[String], conn: &PgConnection) -> Result<Vec<String>, DbError>{
+    let mut keys: Vec<String> = attachments::table
+        .filter(attachments::task_id.eq_any(task_ids))
+        .select(attachments::storage_key)
+        .load(conn)?;
+    let thumbnail_keys: Vec<String> = attachment_thumbnails::table
+        .inner_join(attachments::table)
+        .filter(attachments::task_id.eq_any(task_ids))
+        .select(attachment_thumbnails::storage_key)
+        .load(conn)?;
+    keys.extend(thumbnail_keys);
+    Ok(keys)
+}
+
+/// Метод проверки квоты проекта. Строка проекта блокируется до конца транзакции,
+/// чтобы параллельные загрузки не превысили квоту.
+fn check_quota(project: &str, size: i64, conn: &PgConnection) -> Result<(), DbError>{
+    let quota = projects::table
+        .filter(projects::id.eq(project))
+        .select(projects::storage_quota)
+        .for_update()
+        .first::<Option<i64>>(conn)?
+        .unwrap_or_else(default_project_quota);
+    let sizes: Vec<i64> = attachments::table
+        .inner_join(tasks::table)
+        .filter(tasks::project_id.eq(project))
+        .select(attachments::size)
+        .load(conn)?;
+    let used: i64 = sizes.iter().sum();
+    if used + size > quota {
+        return Err(Box::new(ClientError::PayloadTooLarge(format!(
+            "Project storage quota exceeded: {} of {} bytes used", used, quota
+        ))));
+    }
+    Ok(())
+}
@@ -0,0 +1,379 @@
+use diesel::{prelude::*};
+use chrono::{Duration, Timelike};
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+use crate::auth::RequestMeta;
+use crate::models::{AuditEntry, AuditEvent, AuditQuery, AuditVerification, Page, PageQuery};
+use crate::schema::audit_log;
+use super::{csv_field, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Успешный вход пользователя
+pub const ACTION_LOGIN: &str = "login";
+/// Неудачная попытка входа
+pub const ACTION_LOGIN_FAILED: &str = "login_failed";
+/// Изменение роли пользователя
+pub const ACTION_ROLE_CHANGE: &str = "role_change";
+/// Удаление пользователя
+pub const ACTION_USER_DELETE: &str = "user_delete";
+/// Выгрузка данных
+pub const ACTION_EXPORT: &str = "export";
+
+/// Хэш, с которым связана первая запись журнала
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Максимальное количество записей в одной выгрузке
+const MAX_EXPORT_ROWS: i64 = 50_000;
+
+/// Количество записей, проверяемых за один запрос к базе данных
+const VERIFY_BATCH: i64 = 1_000;
+
+/// Метод, создающий событие аудита со сведениями о запросе.
+/// # Arguments
+///
+/// * `action`      - действие.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+/// * `meta`        - сведения о запросе.
+///
+/// # Return
+///
+/// Возвращает событие без объекта действия и подробностей.
+pub fn event(action: &str, actor: Option<&str>, meta: &RequestMeta) -> AuditEvent{
+    AuditEvent{
+        action: action.to_string(),
+        actor_id: actor.map(|actor| actor.to_string()),
+        target_type: None,
+        target_id: None,
+        details: Value::Object(Default::default()),
+        ip: meta.ip.clone(),
+        user_agent: meta.user_agent.clone(),
+        request_id: Some(meta.request_id.clone()),
+    }
+}
+
+/// Метод, добавляющий событие в конец журнала аудита.
+/// Таблица блокируется до конца транзакции, чтобы записи выстраивались в одну цепочку.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `event`       - событие аудита.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо добавленную запись.
+pub fn record(event: AuditEvent, conn: &PgConnection) -> Result<AuditEntry, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        diesel::sql_query("LOCK TABLE audit_log IN SHARE ROW EXCLUSIVE MODE").execute(conn)?;
+        let last = audit_log::table
+            .order(audit_log::seq.desc())
+            .select((audit_log::seq, audit_log::hash))
+            .first::<(i64, String)>(conn)
+            .optional()?;
+        let (seq, prev_hash) = match last {
+            Some((seq, hash)) => (seq + 1, hash),
+            None => (1, GENESIS_HASH.to_string()),
+        };
+        // В базе время хранится с точностью до микросекунд, хэш должен совпадать после чтения
+        let now = chrono::Utc::now().naive_utc();
+        let created_at = now.with_nanosecond(now.nanosecond() / 1_000 * 1_000).unwrap_or(now);
+
+        let mut entry = AuditEntry{
+            id: Uuid::new_v4().to_string(),
+            seq,
+            action: event.action,
+            actor_id: event.actor_id,
+            target_type: event.target_type,
+            target_id: event.target_id,
+            details: event.details,
+            ip: event.ip,
+            user_agent: event.user_agent,
+            request_id: event.request_id,
+            created_at,
+            prev_hash,
+            hash: String::new(),
+        };
+        entry.hash = entry_hash(&entry)?;
+        diesel::insert_into(audit_log::table).values(&entry).execute(conn)?;
+        Ok(entry)
+    })
+}
+
+/// Метод, возвращающий страницу журнала аудита, начиная с последних записей
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `query`       - фильтры и параметры страницы.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу записей журнала.
+pub fn get_audit_log(query: &AuditQuery, conn: &PgConnection) -> Result<Page<AuditEntry>, DbError>{
+    let (page, per_page) = PageQuery{ page: query.page, per_page: query.per_page }.bounds();
+    let total = filtered(query)
+        .count()
+        .get_result::<i64>(conn)?;
+    let items = filtered(query)
+        .order(audit_log::seq.desc())
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .load::<AuditEntry>(conn)?;
+
+    Ok(Page{ items, total, page, per_page })
+}
+
+/// Метод, выгружающий записи журнала аудита по порядку цепочки.
+/// Выгрузка сама записывается в журнал.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `query`       - фильтры выгрузки.
+/// * `event`       - событие выгрузки для записи в журнал.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор записей журнала.
+pub fn export_audit_log(query: &AuditQuery, event: AuditEvent, conn: &PgConnection) -> Result<Vec<AuditEntry>, DbError>{
+    let total = filtered(query)
+        .count()
+        .get_result::<i64>(conn)?;
+    if total > MAX_EXPORT_ROWS {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "Export is limited to {} entries, {} match the filters", MAX_EXPORT_ROWS, total
+        ))));
+    }
+    let entries = filtered(query)
+        .order(audit_log::seq.asc())
+        .load::<AuditEntry>(conn)?;
+    record(event, conn)?;
+    Ok(entries)
+}
+
+/// Метод, проверяющий целостность цепочки хэшей всего журнала аудита
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо результат проверки.
+pub fn verify_audit_log(conn: &PgConnection) -> Result<AuditVerification, DbError>{
+    let mut chain = Chain::new();
+    loop {
+        let batch = audit_log::table
+            .filter(audit_log::seq.gt(chain.last_seq))
+            .order(audit_log::seq.asc())
+            .limit(VERIFY_BATCH)
+            .load::<AuditEntry>(conn)?;
+        if batch.is_empty() {
+            return Ok(AuditVerification{ valid: true, checked: chain.checked, broken_at: None });
+        }
+        for entry in batch {
+            let seq = entry.seq;
+            if !chain.append(entry)? {
+                return Ok(AuditVerification{ valid: false, checked: chain.checked, broken_at: Some(seq) });
+            }
+        }
+    }
+}
+
+/// Проверяемая цепочка записей журнала аудита: номер и хэш последней проверенной записи.
+struct Chain{
+    last_seq: i64,
+    prev_hash: String,
+    checked: i64,
+}
+
+impl Chain{
+    /// Метод, создающий пустую цепочку, начинающуюся с нулевого хэша.
+    fn new() -> Self{
+        Chain{ last_seq: 0, prev_hash: GENESIS_HASH.to_string(), checked: 0 }
+    }
+
+    /// Метод, проверяющий следующую запись и добавляющий её в цепочку.
+    /// Возвращает false, если запись не продолжает цепочку или её содержимое изменено.
+    fn append(&mut self, entry: AuditEntry) -> Result<bool, DbError>{
+        // Номера идут подряд, пропуск означает удалённую запись
+        if entry.seq != self.last_seq + 1 || entry.prev_hash != self.prev_hash || entry.hash != entry_hash(&entry)? {
+            return Ok(false);
+        }
+        self.checked += 1;
+        self.last_seq = entry.seq;
+        self.prev_hash = entry.hash;
+        Ok(true)
+    }
+}
+
+/// Метод, преобразующий записи журнала аудита в CSV.
+/// # Arguments
+///
+/// * `entries`     - записи журнала.
+///
+/// # Return
+///
+/// Возвращает текст CSV с заголовком.
+pub fn audit_to_csv(entries: &[AuditEntry]) -> String{
+    let mut csv = String::from(
+        "seq,created_at,action,actor_id,target_type,target_id,ip,user_agent,request_id,details,prev_hash,hash\n"
+    );
+    for entry in entries {
+        let fields = [
+            entry.seq.to_string(),
+            entry.created_at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+            entry.action.clone(),
+            entry.actor_id.clone().unwrap_or_default(),
+            entry.target_type.clone().unwrap_or_default(),
+            entry.target_id.clone().unwrap_or_default(),
+            entry.ip.clone().unwrap_or_default(),
+            entry.user_agent.clone().unwrap_or_default(),
+            entry.request_id.clone().unwrap_or_default(),
+            entry.details.to_string(),
+            entry.prev_hash.clone(),
+            entry.hash.clone(),
+        ];
+        let row: Vec<String> = fields.iter().map(|field| csv_field(field)).collect();
+        csv.push_str(&row.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Метод, строящий запрос к журналу аудита с фильтрами.
+fn filtered(query: &AuditQuery) -> audit_log::BoxedQuery<'static, diesel::pg::Pg>{
+    let mut select = audit_log::table.into_boxed();
+    if let Some(action) = &query.action {
+        select = select.filter(audit_log::action.eq(action.clone()));
+    }
+    if let Some(actor) = &query.actor_id {
+        select = select.filter(audit_log::actor_id.eq(actor.clone()));
+    }
+    if let Some(target) = &query.target_id {
+        select = select.filter(audit_log::target_id.eq(target.clone()));
+    }
+    if let Some(from) = query.from {
+        select = select.filter(audit_log::created_at.ge(from.and_hms_opt(0, 0, 0).unwrap_or_default()));
+    }
+    if let Some(to) = query.to {
+        let end = (to + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default();
+        select = select.filter(audit_log::created_at.lt(end));
+    }
+    select
+}
+
+/// Метод, вычисляющий хэш записи журнала по её содержимому и хэшу предыдущей записи.
+fn entry_hash(entry: &AuditEntry) -> Result<String, DbError>{
+    let payload = serde_json::to_string(&(
+        &entry.prev_hash,
+        entry.seq,
+        &entry.action,
+        &entry.actor_id,
+        &entry.target_type,
+        &entry.target_id,
+        &entry.details,
+        &entry.ip,
+        &entry.user_agent,
+        &entry.request_id,
+        entry.created_at.format("%Y-%m-%dT%H:%M:%S%.6f").to_string(),
+    ))?;
+    Ok(hex::encode(Sha256::digest(payload.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// Метод, строящий цепочку из `count` записей так же, как их записывает `record`.
+    fn chain(count: i64) -> Vec<AuditEntry>{
+        let mut prev_hash = GENESIS_HASH.to_string();
+        let created_at = chrono::NaiveDate::from_ymd_opt(2026, 10, 19).unwrap().and_hms_micro_opt(12, 0, 0, 123_456).unwrap();
+        (1..=count)
+            .map(|seq| {
+                let mut entry = AuditEntry{
+                    id: Uuid::new_v4().to_string(),
+                    seq,
+                    action: ACTION_LOGIN.to_string(),
+                    actor_id: Some(format!("user-{}", seq)),
+                    target_type: None,
+                    target_id: None,
+                    details: json!({ "seq": seq }),
+                    ip: Some("127.0.0.1".to_string()),
+                    user_agent: None,
+                    request_id: Some(format!("request-{}", seq)),
+                    created_at: created_at + Duration::seconds(seq),
+                    prev_hash: prev_hash.clone(),
+                    hash: String::new(),
+                };
+                entry.hash = entry_hash(&entry).unwrap();
+                prev_hash = entry.hash.clone();
+                entry
+            })
+            .collect()
+    }
+
+    /// Метод, возвращающий номер первой записи, нарушающей цепочку.
+    fn broken_at(entries: Vec<AuditEntry>) -> Option<i64>{
+        let mut chain = Chain::new();
+        for entry in entries {
+            let seq = entry.seq;
+            if !chain.append(entry).unwrap() {
+                return Some(seq);
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn intact_chain_is_valid(){
+        let mut chain_state = Chain::new();
+        for entry in chain(5) {
+            assert!(chain_state.append(entry).unwrap());
+        }
+        assert_eq!(chain_state.checked, 5);
+    }
+
+    #[test]
+    fn hash_covers_microseconds(){
+        let mut entry = chain(1).remove(0);
+        let original = entry.hash.clone();
+
+        assert_eq!(entry_hash(&entry).unwrap(), original);
+        entry.created_at += Duration::microseconds(1);
+        assert_ne!(entry_hash(&entry).unwrap(), original);
+    }
+
+    #[test]
+    fn modified_entry_breaks_chain(){
+        let mut entries = chain(4);
+        entries[2].details = json!({ "seq": 100 });
+
+        assert_eq!(broken_at(entries), Some(3));
+    }
+
+    #[test]
+    fn rehashed_entry_breaks_next_link(){
+        let mut entries = chain(4);
+        entries[1].actor_id = Some("intruder".to_string());
+        entries[1].hash = entry_hash(&entries[1]).unwrap();
+
+        assert_eq!(broken_at(entries), Some(3));
+    }
+
+    #[test]
+    fn deleted_entry_breaks_chain(){
+        let mut entries = chain(4);
+        entries.remove(1);
+
+        assert_eq!(broken_at(entries), Some(3));
+    }
+
+    #[test]
+    fn chain_starts_from_genesis_hash(){
+        let mut entries = chain(2);
+        entries.remove(0);
+
+        assert_eq!(broken_at(entries), Some(2));
+    }
+}
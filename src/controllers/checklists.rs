@@ -0,0 +1,218 @@
+use diesel::{prelude::*};
+use std::collections::{HashMap, HashSet};
+
+use crate::models::{ChecklistItem, ChecklistOrder, ChecklistProgress, NewChecklistItem};
+use crate::schema::{checklist_items, tasks, users};
+use super::ClientError;
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Метод, возвращающий пункты чек-листа задачи по порядку
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор пунктов чек-листа.
+/// Если задача не найдена, возвращается None.
+pub fn get_checklist(task: &Uuid, conn: &PgConnection) -> Result<Option<Vec<ChecklistItem>>, DbError>{
+    if !task_exists(task, conn)? {
+        return Ok(None);
+    }
+    Ok(Some(load_items(task, conn)?))
+}
+
+/// Метод, добавляющий пункт в конец чек-листа задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `new_item`    - указатель на десериализованный объект структуры NewChecklistItem.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект пункта чек-листа.
+/// Если задача не найдена, возвращается None.
+pub fn create_checklist_item(task: &Uuid, new_item: &NewChecklistItem, conn: &PgConnection) -> Result<Option<ChecklistItem>, DbError>{
+    if new_item.text.trim().is_empty() {
+        return Err(Box::new(ClientError::BadRequest("Checklist item must not be empty".to_string())));
+    }
+    conn.transaction::<_, DbError, _>(|| {
+        // Строка задачи блокируется, чтобы параллельные добавления не получили одинаковую позицию
+        let locked = tasks::table
+            .filter(tasks::id.eq(task.to_string()))
+            .select(tasks::id)
+            .for_update()
+            .first::<String>(conn)
+            .optional()?;
+        if locked.is_none() {
+            return Ok(None);
+        }
+        let last_position = checklist_items::table
+            .filter(checklist_items::task_id.eq(task.to_string()))
+            .select(diesel::dsl::max(checklist_items::position))
+            .first::<Option<i32>>(conn)?;
+
+        let new = ChecklistItem{
+            id: Uuid::new_v4().to_string(),
+            task_id: task.to_string(),
+            position: last_position.map_or(0, |position| position + 1),
+            text: new_item.text.clone(),
+            checked: false,
+            checked_by: None,
+            checked_at: None,
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        diesel::insert_into(checklist_items::table).values(&new).execute(conn)?;
+        Ok(Some(new))
+    })
+}
+
+/// Метод, меняющий порядок пунктов чек-листа задачи.
+/// Новый порядок должен содержать каждый пункт задачи ровно один раз.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `order`       - указатель на десериализованный объект структуры ChecklistOrder.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор пунктов в новом порядке.
+/// Если задача не найдена, возвращается None.
+pub fn reorder_checklist(task: &Uuid, order: &ChecklistOrder, conn: &PgConnection) -> Result<Option<Vec<ChecklistItem>>, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        if !task_exists(task, conn)? {
+            return Ok(None);
+        }
+        let current: HashSet<String> = checklist_items::table
+            .filter(checklist_items::task_id.eq(task.to_string()))
+            .select(checklist_items::id)
+            .for_update()
+            .load::<String>(conn)?
+            .into_iter()
+            .collect();
+        let requested: HashSet<&String> = order.item_ids.iter().collect();
+        if requested.len() != order.item_ids.len()
+            || requested.len() != current.len()
+            || !requested.iter().all(|item| current.contains(*item)) {
+            return Err(Box::new(ClientError::BadRequest(
+                "New order must list every checklist item of the task exactly once".to_string()
+            )));
+        }
+
+        for (position, item) in order.item_ids.iter().enumerate() {
+            diesel::update(checklist_items::table.filter(checklist_items::id.eq(item)))
+                .set(checklist_items::position.eq(position as i32))
+                .execute(conn)?;
+        }
+        Ok(Some(load_items(task, conn)?))
+    })
+}
+
+/// Метод, переключающий отметку пункта чек-листа.
+/// При отметке запоминаются пользователь и время, при снятии отметки они очищаются.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `item`        - уникальный идентификатор пункта чек-листа.
+/// * `actor`       - идентификатор пользователя, выполняющего действие.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный пункт чек-листа.
+pub fn toggle_checklist_item(task: &Uuid, item: &Uuid, actor: &str, conn: &PgConnection) -> Result<Option<ChecklistItem>, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let existing = checklist_items::table
+            .filter(checklist_items::id.eq(item.to_string()))
+            .filter(checklist_items::task_id.eq(task.to_string()))
+            .for_update()
+            .first::<ChecklistItem>(conn)
+            .optional()?;
+        let existing = match existing {
+            Some(existing) => existing,
+            None => return Ok(None),
+        };
+        let actor_exists = diesel::select(diesel::dsl::exists(users::table.filter(users::id.eq(actor))))
+            .get_result::<bool>(conn)?;
+        if !actor_exists {
+            return Err(Box::new(ClientError::BadRequest(format!("User {} not found", actor))));
+        }
+
+        let (checked_by, checked_at) = if existing.checked {
+            (None, None)
+        } else {
+            (Some(actor.to_string()), Some(chrono::Utc::now().naive_utc()))
+        };
+        let updated: ChecklistItem = diesel::update(checklist_items::table.filter(checklist_items::id.eq(&existing.id)))
+            .set((
+                checklist_items::checked.eq(!existing.checked),
+                checklist_items::checked_by.eq(checked_by),
+                checklist_items::checked_at.eq(checked_at)
+            )).get_result(conn)?;
+        Ok(Some(updated))
+    })
+}
+
+/// Метод, удаляющий пункт чек-листа
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `item`        - уникальный идентификатор пункта чек-листа.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_checklist_item(task: &Uuid, item: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(
+        checklist_items::table
+            .filter(checklist_items::id.eq(item.to_string()))
+            .filter(checklist_items::task_id.eq(task.to_string()))
+    ).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, возвращающий прогресс чек-листов задач из списка.
+/// Задачи без пунктов чек-листа в результат не попадают.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task_ids`    - идентификаторы задач.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо прогресс по идентификатору задачи.
+pub fn checklist_progress(task_ids: &[String], conn: &PgConnection) -> Result<HashMap<String, ChecklistProgress>, DbError>{
+    let rows: Vec<(String, bool)> = checklist_items::table
+        .filter(checklist_items::task_id.eq_any(task_ids))
+        .select((checklist_items::task_id, checklist_items::checked))
+        .load(conn)?;
+    let mut progress: HashMap<String, ChecklistProgress> = HashMap::new();
+    for (task_id, checked) in rows {
+        let entry = progress.entry(task_id).or_insert(ChecklistProgress{ checked: 0, total: 0 });
+        entry.checked += i64::from(checked);
+        entry.total += 1;
+    }
+    Ok(progress)
+}
+
+/// Метод, загружающий пункты чек-листа задачи по порядку.
+fn load_items(task: &Uuid, conn: &PgConnection) -> Result<Vec<ChecklistItem>, DbError>{
+    let items = checklist_items::table
+        .filter(checklist_items::task_id.eq(task.to_string()))
+        .order((checklist_items::position.asc(), checklist_items::created_at.asc()))
+        .load::<ChecklistItem>(conn)?;
+    Ok(items)
+}
+
+/// Метод проверки существования задачи.
+fn task_exists(task: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task.to_string()))))
+        .get_result::<bool>(conn)?;
+    Ok(exists)
+}
@@ -0,0 +1,319 @@
+use diesel::{prelude::*};
+use std::collections::HashMap;
+
+use crate::models::{
+    Comment, CommentMention, CommentRevision, CommentView, Mention, NewComment, Page, PageQuery
+};
+use crate::schema::{comment_mentions, comment_revisions, comments, tasks, users};
+use super::{events, notifications, users as accounts, watchers, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Метод, извлекающий из текста имена упомянутых пользователей (`@user_name`).
+/// Упоминание должно стоять в начале текста или после символа, не входящего в имя.
+/// # Arguments
+///
+/// * `text`        - текст комментария.
+///
+/// # Return
+///
+/// Возвращает вектор уникальных имён в порядке их появления в тексте.
+pub fn parse_mentions(text: &str) -> Vec<String>{
+    let is_name_char = |c: char| c.is_alphanumeric() || c == '_' || c == '-' || c == '.';
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut names: Vec<String> = Vec::new();
+    for (i, &(position, c)) in chars.iter().enumerate() {
+        // Символ имени перед @ означает адрес почты, а не упоминание
+        if c != '@' || (i > 0 && is_name_char(chars[i - 1].1)) {
+            continue;
+        }
+        let start = position + c.len_utf8();
+        let end = chars[i + 1..]
+            .iter()
+            .take_while(|(_, next)| is_name_char(*next))
+            .last()
+            .map_or(start, |(next_position, next)| next_position + next.len_utf8());
+        // Точка в конце упоминания считается знаком препинания
+        let name = text[start..end].trim_end_matches('.');
+        if !name.is_empty() && !names.iter().any(|known| known == name) {
+            names.push(name.to_string());
+        }
+    }
+    names
+}
+
+/// Метод, возвращающий страницу комментариев задачи в порядке создания
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `page_query`  - параметры постраничного вывода.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу комментариев.
+/// Если задача не найдена, возвращается None.
+pub fn get_comments(task: &Uuid, page_query: &PageQuery, conn: &PgConnection) -> Result<Option<Page<CommentView>>, DbError>{
+    if !task_exists(task, conn)? {
+        return Ok(None);
+    }
+    let (page, per_page) = page_query.bounds();
+    let total = comments::table
+        .filter(comments::task_id.eq(task.to_string()))
+        .count()
+        .get_result::<i64>(conn)?;
+    let comments_list = comments::table
+        .filter(comments::task_id.eq(task.to_string()))
+        .order((comments::created_at.asc(), comments::id.asc()))
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .load::<Comment>(conn)?;
+
+    Ok(Some(Page{
+        items: to_views(comments_list, conn)?,
+        total,
+        page,
+        per_page,
+    }))
+}
+
+/// Метод, возвращающий комментарий задачи по идентификатору
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `comment`     - уникальный идентификатор объекта комментария.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект комментария.
+pub fn get_comment(task: &Uuid, comment: &Uuid, conn: &PgConnection) -> Result<Option<CommentView>, DbError>{
+    let comment = match find_comment(task, comment, conn)? {
+        Some(comment) => comment,
+        None => return Ok(None),
+    };
+
+    Ok(to_views(vec![comment], conn)?.pop())
+}
+
+/// Метод, создающий комментарий к задаче от имени пользователя
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `task`            - уникальный идентификатор объекта задачи.
+/// * `author`          - идентификатор пользователя из токена, оставляющего комментарий.
+/// * `new_comment`     - указатель на десериализованный объект структуры NewComment.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект комментария.
+/// Если задача не найдена, возвращается None, если пользователь удалён после выдачи токена - ошибка 401.
+pub fn create_comment(task: &Uuid, author: &str, new_comment: &NewComment, conn: &PgConnection) -> Result<Option<CommentView>, DbError>{
+    validate_body(&new_comment.body)?;
+    conn.transaction::<_, DbError, _>(|| {
+        if !task_exists(task, conn)? {
+            return Ok(None);
+        }
+        accounts::authenticate(author, conn)?;
+
+        let new = Comment{
+            id: Uuid::new_v4().to_string(),
+            task_id: task.to_string(),
+            user_id: author.to_string(),
+            body: new_comment.body.clone(),
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None
+        };
+        diesel::insert_into(comments::table).values(&new).execute(conn)?;
+        watchers::add_watchers(&new.task_id, &[author], conn)?;
+        let mentioned = save_mentions(&new.id, &new.body, conn)?;
+        notifications::notify_mentions(&new, &mentioned, conn)?;
+        events::record_comment_event(events::COMMENT_CREATED, &new, author, conn)?;
+
+        Ok(to_views(vec![new], conn)?.pop())
+    })
+}
+
+/// Метод, изменяющий текст комментария. Изменять комментарий может только его автор,
+/// предыдущий текст сохраняется в истории правок.
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `task`            - уникальный идентификатор объекта задачи.
+/// * `comment`         - уникальный идентификатор объекта комментария.
+/// * `actor`           - идентификатор пользователя, выполняющего изменение.
+/// * `new_comment`     - указатель на десериализованный объект структуры NewComment.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект комментария.
+pub fn update_comment(
+    task: &Uuid,
+    comment: &Uuid,
+    actor: &str,
+    new_comment: &NewComment,
+    conn: &PgConnection
+) -> Result<Option<CommentView>, DbError>{
+    validate_body(&new_comment.body)?;
+    conn.transaction::<_, DbError, _>(|| {
+        let existing = match find_comment(task, comment, conn)? {
+            Some(existing) => existing,
+            None => return Ok(None),
+        };
+        check_author(&existing, actor)?;
+        if existing.body == new_comment.body {
+            return Ok(to_views(vec![existing], conn)?.pop());
+        }
+
+        let now = chrono::Utc::now().naive_utc();
+        let revision = CommentRevision{
+            id: Uuid::new_v4().to_string(),
+            comment_id: existing.id.clone(),
+            body: existing.body.clone(),
+            edited_at: now,
+        };
+        diesel::insert_into(comment_revisions::table).values(&revision).execute(conn)?;
+        let updated: Comment = diesel::update(comments::table.filter(comments::id.eq(&existing.id)))
+            .set((
+                comments::body.eq(new_comment.body.clone()),
+                comments::updated_at.eq(now)
+            )).get_result(conn)?;
+        let previous: Vec<String> = diesel::delete(comment_mentions::table.filter(comment_mentions::comment_id.eq(&updated.id)))
+            .returning(comment_mentions::user_id)
+            .get_results(conn)?;
+        let mentioned = save_mentions(&updated.id, &updated.body, conn)?;
+        // Уведомление получают только пользователи, упомянутые при этой правке впервые
+        let added: Vec<String> = mentioned.into_iter().filter(|user| !previous.contains(user)).collect();
+        notifications::notify_mentions(&updated, &added, conn)?;
+        events::record_comment_event(events::COMMENT_UPDATED, &updated, actor, conn)?;
+
+        Ok(to_views(vec![updated], conn)?.pop())
+    })
+}
+
+/// Метод, удаляющий комментарий. Удалять комментарий может только его автор.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `comment`     - уникальный идентификатор объекта комментария.
+/// * `actor`       - идентификатор пользователя, выполняющего удаление.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_comment(task: &Uuid, comment: &Uuid, actor: &str, conn: &PgConnection) -> Result<bool, DbError>{
+    let existing = match find_comment(task, comment, conn)? {
+        Some(existing) => existing,
+        None => return Ok(false),
+    };
+    check_author(&existing, actor)?;
+    conn.transaction::<_, DbError, _>(|| {
+        let deleted = diesel::delete(comments::table.filter(comments::id.eq(&existing.id))).execute(conn)?;
+        events::record_comment_event(events::COMMENT_DELETED, &existing, actor, conn)?;
+        Ok(deleted > 0)
+    })
+}
+
+/// Метод, возвращающий историю правок комментария, начиная с самой ранней версии
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `comment`     - уникальный идентификатор объекта комментария.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор предыдущих версий комментария.
+pub fn get_comment_history(task: &Uuid, comment: &Uuid, conn: &PgConnection) -> Result<Option<Vec<CommentRevision>>, DbError>{
+    if find_comment(task, comment, conn)?.is_none() {
+        return Ok(None);
+    }
+    let revisions = comment_revisions::table
+        .filter(comment_revisions::comment_id.eq(comment.to_string()))
+        .order(comment_revisions::edited_at.asc())
+        .load::<CommentRevision>(conn)?;
+
+    Ok(Some(revisions))
+}
+
+/// Метод, сохраняющий упоминания пользователей, найденные в тексте комментария.
+/// Имена, не соответствующие ни одному пользователю, игнорируются.
+/// Возвращает идентификаторы упомянутых пользователей.
+fn save_mentions(comment: &str, body: &str, conn: &PgConnection) -> Result<Vec<String>, DbError>{
+    let names = parse_mentions(body);
+    if names.is_empty() {
+        return Ok(Vec::new());
+    }
+    let user_ids: Vec<String> = users::table
+        .filter(users::user_name.eq_any(names))
+        .select(users::id)
+        .load(conn)?;
+    let mentions: Vec<CommentMention> = user_ids
+        .iter()
+        .map(|user_id| CommentMention{ comment_id: comment.to_string(), user_id: user_id.clone() })
+        .collect();
+    diesel::insert_into(comment_mentions::table)
+        .values(&mentions)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(user_ids)
+}
+
+/// Метод, дополняющий комментарии упомянутыми пользователями.
+fn to_views(comments_list: Vec<Comment>, conn: &PgConnection) -> Result<Vec<CommentView>, DbError>{
+    let ids: Vec<String> = comments_list.iter().map(|comment| comment.id.clone()).collect();
+    let rows: Vec<(String, String, String)> = comment_mentions::table
+        .inner_join(users::table)
+        .filter(comment_mentions::comment_id.eq_any(ids))
+        .select((comment_mentions::comment_id, users::id, users::user_name))
+        .order(users::user_name.asc())
+        .load(conn)?;
+    let mut by_comment: HashMap<String, Vec<Mention>> = HashMap::new();
+    for (comment_id, user_id, user_name) in rows {
+        by_comment.entry(comment_id).or_default().push(Mention{ user_id, user_name });
+    }
+
+    Ok(comments_list
+        .into_iter()
+        .map(|comment| {
+            let mentions = by_comment.remove(&comment.id).unwrap_or_default();
+            CommentView{ comment, mentions }
+        })
+        .collect())
+}
+
+/// Метод, возвращающий комментарий, принадлежащий указанной задаче.
+fn find_comment(task: &Uuid, comment: &Uuid, conn: &PgConnection) -> Result<Option<Comment>, DbError>{
+    let comment = comments::table
+        .filter(comments::id.eq(comment.to_string()))
+        .filter(comments::task_id.eq(task.to_string()))
+        .first::<Comment>(conn)
+        .optional()?;
+    Ok(comment)
+}
+
+/// Метод проверки, что действие над комментарием выполняет его автор.
+/// `actor` - пользователь из проверенного токена, а не переданный клиентом идентификатор.
+fn check_author(comment: &Comment, actor: &str) -> Result<(), ClientError>{
+    if comment.user_id != actor {
+        return Err(ClientError::Forbidden("Only the author can change this comment".to_string()));
+    }
+    Ok(())
+}
+
+/// Метод проверки текста комментария.
+fn validate_body(body: &str) -> Result<(), ClientError>{
+    if body.trim().is_empty() {
+        return Err(ClientError::BadRequest("Comment must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+/// Метод проверки существования задачи.
+fn task_exists(task: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task.to_string()))))
+        .get_result::<bool>(conn)?;
+    Ok(exists)
+}
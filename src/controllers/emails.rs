@@ -0,0 +1,413 @@
+use chrono::{NaiveDateTime, Timelike};
+use diesel::{prelude::*};
+use std::collections::HashMap;
+
+use crate::mailer::templates::{self, Email, Locale};
+use crate::models::{Notification, NotificationPreferences, OutboxEmail};
+use crate::schema::{email_outbox, notification_preferences, notifications, tasks, users};
+use super::{notifications as kinds, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Письмо о приближении срока задачи
+pub const KIND_DUE_SOON: &str = "due_soon";
+/// Ежедневная сводка
+pub const KIND_DIGEST: &str = "digest";
+
+/// Письма не отправляются
+pub const MODE_OFF: &str = "off";
+/// Письмо отправляется сразу о каждом событии
+pub const MODE_INSTANT: &str = "instant";
+/// События собираются в ежедневную сводку
+pub const MODE_DIGEST: &str = "digest";
+/// Допустимые режимы отправки писем
+pub const MODES: [&str; 3] = [MODE_OFF, MODE_INSTANT, MODE_DIGEST];
+
+/// Письмо ожидает отправки
+pub const STATUS_PENDING: &str = "pending";
+/// Письмо отправляется одним из экземпляров сервера
+pub const STATUS_SENDING: &str = "sending";
+/// Письмо отправлено
+pub const STATUS_SENT: &str = "sent";
+/// Попытки отправить письмо исчерпаны
+pub const STATUS_FAILED: &str = "failed";
+
+/// Количество попыток отправки, после которого письмо больше не отправляется
+const MAX_ATTEMPTS: i32 = 8;
+
+/// Задержка перед первой повторной попыткой отправки в секундах, далее удваивается
+const MIN_RETRY_DELAY: i64 = 30;
+
+/// Максимальная задержка перед повторной попыткой отправки в секундах
+const MAX_RETRY_DELAY: i64 = 60 * 60;
+
+/// Время в секундах, на которое письмо закрепляется за экземпляром сервера.
+/// Если результат отправки не записан за это время, например из-за остановки сервера, письмо отправляется повторно.
+const SEND_LEASE: i64 = 5 * 60;
+
+/// За сколько часов до срока по умолчанию отправляется письмо о приближении срока
+const DEFAULT_DUE_SOON_HOURS: i64 = 24;
+
+/// Час (UTC) отправки ежедневной сводки по умолчанию
+const DEFAULT_DIGEST_HOUR: u32 = 8;
+
+/// Метод, проверяющий, включена ли отправка писем.
+/// Письма отправляются, если задана переменная окружения SMTP_HOST.
+pub fn enabled() -> bool{
+    std::env::var("SMTP_HOST").is_ok_and(|host| !host.is_empty())
+}
+
+/// Метод, возвращающий, за сколько до срока отправляется письмо о приближении срока.
+/// Задаётся в часах переменной окружения EMAIL_DUE_SOON_HOURS.
+fn due_soon_window() -> chrono::Duration{
+    let hours = std::env::var("EMAIL_DUE_SOON_HOURS")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(DEFAULT_DUE_SOON_HOURS);
+    chrono::Duration::hours(hours)
+}
+
+/// Метод, возвращающий час (UTC), начиная с которого отправляются ежедневные сводки.
+/// Задаётся переменной окружения EMAIL_DIGEST_HOUR.
+fn digest_hour() -> u32{
+    std::env::var("EMAIL_DIGEST_HOUR")
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .filter(|hour| *hour < 24)
+        .unwrap_or(DEFAULT_DIGEST_HOUR)
+}
+
+/// Метод, проверяющий режим отправки писем и язык из настроек уведомлений.
+/// # Arguments
+///
+/// * `mode`        - режим отправки писем.
+/// * `locale`      - язык писем.
+///
+/// # Return
+///
+/// Возвращает Результат с ошибкой 400, если режим или язык неизвестны.
+pub fn validate_settings(mode: &str, locale: &str) -> Result<(), ClientError>{
+    if !MODES.contains(&mode) {
+        return Err(ClientError::BadRequest(format!("Unknown email mode {}, expected one of {}", mode, MODES.join(", "))));
+    }
+    if Locale::parse(locale).is_none() {
+        return Err(ClientError::BadRequest(format!("Unknown locale {}, expected ru or en", locale)));
+    }
+    Ok(())
+}
+
+/// Метод, ставящий в очередь письмо об уведомлении о назначении или упоминании.
+/// Письмо ставится в очередь в транзакции, создающей уведомление, и отправляется только после её завершения.
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `notification`    - созданное уведомление.
+/// * `preferences`     - настройки уведомлений получателя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если письмо не удалось поставить в очередь.
+pub fn enqueue_notification(notification: &Notification, preferences: &NotificationPreferences, conn: &PgConnection) -> Result<(), DbError>{
+    if !enabled() || preferences.email_mode != MODE_INSTANT {
+        return Ok(());
+    }
+    if notification.kind != kinds::KIND_ASSIGNED && notification.kind != kinds::KIND_MENTIONED {
+        return Ok(());
+    }
+    let recipient = users::table
+        .find(&notification.user_id)
+        .select(users::email)
+        .first::<String>(conn)?;
+    let titles = task_titles(notification.task_id.iter().cloned().collect(), conn)?;
+    let names = user_names(notification.actor_id.iter().cloned().collect(), conn)?;
+    let title = notification_title(notification, &titles);
+    let actor = notification.actor_id.as_ref().and_then(|actor| names.get(actor)).map(String::as_str);
+    let locale = Locale::parse(&preferences.locale).unwrap_or(Locale::Ru);
+    let email = if notification.kind == kinds::KIND_ASSIGNED {
+        templates::assigned(locale, &title, actor)
+    } else {
+        let comment = notification.details["body"].as_str().unwrap_or_default();
+        templates::mentioned(locale, &title, actor, comment)
+    };
+    let dedupe_key = format!("notification:{}", notification.id);
+    enqueue(&notification.user_id, &recipient, &notification.kind, email, &dedupe_key, conn)?;
+    Ok(())
+}
+
+/// Метод, ставящий в очередь письма исполнителям о приближении срока незавершённых задач.
+/// О каждом сроке задачи письмо отправляется один раз, при переносе срока - повторно.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `now`         - текущее время.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество поставленных в очередь писем.
+pub fn enqueue_due_soon(now: NaiveDateTime, conn: &PgConnection) -> Result<usize, DbError>{
+    if !enabled() {
+        return Ok(0);
+    }
+    let due = tasks::table
+        .inner_join(users::table.left_join(notification_preferences::table))
+        .filter(tasks::done.eq(false))
+        .filter(tasks::due_at.gt(now))
+        .filter(tasks::due_at.le(now + due_soon_window()))
+        .select((
+            tasks::id,
+            tasks::title,
+            tasks::due_at,
+            users::id,
+            users::email,
+            notification_preferences::email_mode.nullable(),
+            notification_preferences::locale.nullable(),
+            notification_preferences::due_soon.nullable(),
+        ))
+        .load::<(String, String, Option<NaiveDateTime>, String, String, Option<String>, Option<String>, Option<bool>)>(conn)?;
+
+    let mut queued = 0;
+    for (task, title, due_at, user, recipient, mode, locale, due_soon) in due {
+        let due_at = match due_at {
+            Some(due_at) => due_at,
+            None => continue,
+        };
+        // Пока пользователь не менял настройки, письма отправляются сразу
+        if mode.as_deref().unwrap_or(MODE_INSTANT) != MODE_INSTANT || !due_soon.unwrap_or(true) {
+            continue;
+        }
+        let locale = locale.as_deref().and_then(Locale::parse).unwrap_or(Locale::Ru);
+        let dedupe_key = format!("{}:{}:{}", KIND_DUE_SOON, task, due_at.and_utc().timestamp());
+        queued += enqueue(&user, &recipient, KIND_DUE_SOON, templates::due_soon(locale, &title, due_at), &dedupe_key, conn)?;
+    }
+    Ok(queued)
+}
+
+/// Метод, ставящий в очередь ежедневные сводки пользователям, выбравшим режим сводки.
+/// Сводка отправляется один раз в день, начиная с часа, заданного EMAIL_DIGEST_HOUR, и содержит
+/// уведомления с предыдущей сводки и задачи с приближающимся сроком. Пустая сводка не отправляется.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `now`         - текущее время.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество поставленных в очередь сводок.
+pub fn enqueue_digests(now: NaiveDateTime, conn: &PgConnection) -> Result<usize, DbError>{
+    if !enabled() || now.hour() < digest_hour() {
+        return Ok(0);
+    }
+    let scheduled_at = now.date().and_hms_opt(digest_hour(), 0, 0).unwrap_or(now);
+    conn.transaction::<_, DbError, _>(|| {
+        // Сводки другого экземпляра сервера пропускаются, а не ожидаются
+        let pending = notification_preferences::table
+            .filter(notification_preferences::email_mode.eq(MODE_DIGEST))
+            .filter(notification_preferences::digest_sent_at.is_null()
+                .or(notification_preferences::digest_sent_at.lt(scheduled_at)))
+            .for_update()
+            .skip_locked()
+            .load::<NotificationPreferences>(conn)?;
+
+        let mut queued = 0;
+        for preferences in pending {
+            queued += enqueue_digest(&preferences, now, conn)?;
+            diesel::update(notification_preferences::table.find(&preferences.user_id))
+                .set(notification_preferences::digest_sent_at.eq(now))
+                .execute(conn)?;
+        }
+        Ok(queued)
+    })
+}
+
+/// Метод, отправляющий письма, время отправки которых наступило.
+/// Письма сначала закрепляются за сервером в короткой транзакции, поэтому несколько экземпляров сервера
+/// не отправляют одно письмо дважды, а соединение с SMTP-сервером не удерживает блокировки в базе данных.
+/// Результат каждой отправки записывается отдельно. После неудачной попытки отправка повторяется
+/// с удваивающейся задержкой, пока не исчерпаны попытки.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `limit`       - максимальное количество отправляемых писем.
+/// * `send`        - функция отправки письма.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество обработанных писем.
+pub fn send_pending<F>(limit: i64, send: F, conn: &PgConnection) -> Result<usize, DbError>
+where
+    F: Fn(&OutboxEmail) -> Result<(), DbError>
+{
+    let (due, lease) = claim_due(limit, conn)?;
+    for email in &due {
+        let attempts = email.attempts + 1;
+        // Результат не записывается, если срок закрепления истёк и письмо закреплено повторно
+        let target = email_outbox::table
+            .filter(email_outbox::id.eq(&email.id))
+            .filter(email_outbox::status.eq(STATUS_SENDING))
+            .filter(email_outbox::next_attempt_at.eq(lease));
+        match send(email) {
+            Ok(()) => {
+                diesel::update(target)
+                    .set((
+                        email_outbox::status.eq(STATUS_SENT),
+                        email_outbox::attempts.eq(attempts),
+                        email_outbox::sent_at.eq(chrono::Utc::now().naive_utc()),
+                        email_outbox::last_error.eq(None::<String>),
+                    ))
+                    .execute(conn)?;
+            },
+            Err(err) => {
+                let status = if attempts >= MAX_ATTEMPTS { STATUS_FAILED } else { STATUS_PENDING };
+                log::warn!("Failed to send email {} (attempt {}): {}", email.id, attempts, err);
+                diesel::update(target)
+                    .set((
+                        email_outbox::status.eq(status),
+                        email_outbox::attempts.eq(attempts),
+                        email_outbox::next_attempt_at.eq(chrono::Utc::now().naive_utc() + retry_delay(attempts)),
+                        email_outbox::last_error.eq(err.to_string()),
+                    ))
+                    .execute(conn)?;
+            },
+        }
+    }
+    Ok(due.len())
+}
+
+/// Метод, закрепляющий за сервером письма, время отправки которых наступило.
+/// Выбираются ожидающие письма и письма, результат отправки которых не записан до истечения срока закрепления.
+/// Возвращает закреплённые письма и время окончания закрепления.
+fn claim_due(limit: i64, conn: &PgConnection) -> Result<(Vec<OutboxEmail>, NaiveDateTime), DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let now = chrono::Utc::now().naive_utc();
+        let due = email_outbox::table
+            .filter(email_outbox::status.eq_any(vec![STATUS_PENDING, STATUS_SENDING]))
+            .filter(email_outbox::next_attempt_at.le(now))
+            .order(email_outbox::next_attempt_at.asc())
+            .limit(limit)
+            .for_update()
+            .skip_locked()
+            .load::<OutboxEmail>(conn)?;
+        // Время хранится в базе данных с точностью до микросекунд и сравнивается при записи результата
+        let lease = now + chrono::Duration::seconds(SEND_LEASE);
+        let lease = lease.with_nanosecond(lease.nanosecond() / 1000 * 1000).unwrap_or(lease);
+        let ids: Vec<&String> = due.iter().map(|email| &email.id).collect();
+        diesel::update(email_outbox::table.filter(email_outbox::id.eq_any(ids)))
+            .set((
+                email_outbox::status.eq(STATUS_SENDING),
+                email_outbox::next_attempt_at.eq(lease),
+            ))
+            .execute(conn)?;
+        Ok((due, lease))
+    })
+}
+
+/// Метод, возвращающий задержку перед повторной отправкой после указанного количества попыток.
+fn retry_delay(attempts: i32) -> chrono::Duration{
+    let factor = 1_i64 << (attempts - 1).clamp(0, 20);
+    chrono::Duration::seconds((MIN_RETRY_DELAY * factor).min(MAX_RETRY_DELAY))
+}
+
+/// Метод, ставящий в очередь сводку одного пользователя.
+/// Возвращает количество поставленных в очередь писем: 0 для пустой сводки.
+fn enqueue_digest(preferences: &NotificationPreferences, now: NaiveDateTime, conn: &PgConnection) -> Result<usize, DbError>{
+    let since = preferences.digest_sent_at.unwrap_or(now - chrono::Duration::days(1));
+    let locale = Locale::parse(&preferences.locale).unwrap_or(Locale::Ru);
+    let recent = notifications::table
+        .filter(notifications::user_id.eq(&preferences.user_id))
+        .filter(notifications::created_at.gt(since))
+        .filter(notifications::created_at.le(now))
+        .order(notifications::created_at.asc())
+        .load::<Notification>(conn)?;
+    let due = if preferences.due_soon {
+        tasks::table
+            .filter(tasks::user_id.eq(&preferences.user_id))
+            .filter(tasks::done.eq(false))
+            .filter(tasks::due_at.gt(now))
+            .filter(tasks::due_at.le(now + due_soon_window()))
+            .order(tasks::due_at.asc())
+            .select((tasks::title, tasks::due_at))
+            .load::<(String, Option<NaiveDateTime>)>(conn)?
+    } else {
+        Vec::new()
+    };
+    if recent.is_empty() && due.is_empty() {
+        return Ok(0);
+    }
+
+    let titles = task_titles(recent.iter().filter_map(|notification| notification.task_id.clone()).collect(), conn)?;
+    let names = user_names(recent.iter().filter_map(|notification| notification.actor_id.clone()).collect(), conn)?;
+    let activity: Vec<String> = recent
+        .iter()
+        .map(|notification| {
+            let actor = notification.actor_id.as_ref().and_then(|actor| names.get(actor)).map(String::as_str);
+            templates::digest_line(locale, &notification.kind, &notification_title(notification, &titles), actor)
+        })
+        .collect();
+    let due: Vec<String> = due
+        .into_iter()
+        .filter_map(|(title, due_at)| due_at.map(|due_at| templates::digest_due_line(locale, &title, due_at)))
+        .collect();
+
+    let recipient = users::table
+        .find(&preferences.user_id)
+        .select(users::email)
+        .first::<String>(conn)?;
+    let dedupe_key = format!("{}:{}:{}", KIND_DIGEST, preferences.user_id, now.date());
+    let email = templates::digest(locale, now.date(), &activity, &due);
+    enqueue(&preferences.user_id, &recipient, KIND_DIGEST, email, &dedupe_key, conn)
+}
+
+/// Метод, ставящий письмо в очередь, если письмо с тем же ключом ещё не ставилось.
+/// Возвращает количество поставленных в очередь писем.
+fn enqueue(user: &str, recipient: &str, kind: &str, email: Email, dedupe_key: &str, conn: &PgConnection) -> Result<usize, DbError>{
+    let now = chrono::Utc::now().naive_utc();
+    let outbox_email = OutboxEmail{
+        id: Uuid::new_v4().to_string(),
+        user_id: Some(user.to_string()),
+        recipient: recipient.to_string(),
+        kind: kind.to_string(),
+        subject: email.subject,
+        body: email.body,
+        dedupe_key: Some(dedupe_key.to_string()),
+        status: STATUS_PENDING.to_string(),
+        attempts: 0,
+        next_attempt_at: now,
+        last_error: None,
+        created_at: now,
+        sent_at: None,
+    };
+    let queued = diesel::insert_into(email_outbox::table)
+        .values(&outbox_email)
+        .on_conflict(email_outbox::dedupe_key)
+        .do_nothing()
+        .execute(conn)?;
+    Ok(queued)
+}
+
+/// Метод, возвращающий название задачи уведомления: текущее или сохранённое в уведомлении.
+fn notification_title(notification: &Notification, titles: &HashMap<String, String>) -> String{
+    notification.task_id
+        .as_ref()
+        .and_then(|task| titles.get(task).cloned())
+        .or_else(|| notification.details["title"].as_str().map(str::to_string))
+        .unwrap_or_default()
+}
+
+/// Метод, возвращающий названия задач по идентификаторам.
+fn task_titles(ids: Vec<String>, conn: &PgConnection) -> Result<HashMap<String, String>, DbError>{
+    let titles = tasks::table
+        .filter(tasks::id.eq_any(ids))
+        .select((tasks::id, tasks::title))
+        .load::<(String, String)>(conn)?;
+    Ok(titles.into_iter().collect())
+}
+
+/// Метод, возвращающий имена пользователей по идентификаторам.
+fn user_names(ids: Vec<String>, conn: &PgConnection) -> Result<HashMap<String, String>, DbError>{
+    let names = users::table
+        .filter(users::id.eq_any(ids))
+        .select((users::id, users::user_name))
+        .load::<(String, String)>(conn)?;
+    Ok(names.into_iter().collect())
+}
@@ -0,0 +1,243 @@
+use diesel::{prelude::*};
+use chrono::NaiveDateTime;
+use serde_json::{json, Map, Value};
+
+use crate::models::{Comment, Event, NewEvent, Task, User};
+use crate::schema::{events, tasks};
+use super::{history, webhooks};
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Тип объекта события - задача
+pub const ENTITY_TASK: &str = "task";
+/// Тип объекта события - пользователь
+pub const ENTITY_USER: &str = "user";
+/// Тип объекта события - комментарий
+pub const ENTITY_COMMENT: &str = "comment";
+
+/// Создание задачи
+pub const TASK_CREATED: &str = "task.created";
+/// Изменение задачи
+pub const TASK_UPDATED: &str = "task.updated";
+/// Удаление задачи
+pub const TASK_DELETED: &str = "task.deleted";
+/// Создание пользователя
+pub const USER_CREATED: &str = "user.created";
+/// Изменение пользователя
+pub const USER_UPDATED: &str = "user.updated";
+/// Удаление пользователя
+pub const USER_DELETED: &str = "user.deleted";
+/// Создание комментария
+pub const COMMENT_CREATED: &str = "comment.created";
+/// Изменение комментария
+pub const COMMENT_UPDATED: &str = "comment.updated";
+/// Удаление комментария
+pub const COMMENT_DELETED: &str = "comment.deleted";
+/// Все типы событий
+pub const EVENT_TYPES: [&str; 9] = [
+    TASK_CREATED, TASK_UPDATED, TASK_DELETED,
+    USER_CREATED, USER_UPDATED, USER_DELETED,
+    COMMENT_CREATED, COMMENT_UPDATED, COMMENT_DELETED,
+];
+
+/// Максимальное количество пропущенных событий, которые можно получить после переподключения
+pub const MAX_MISSED_EVENTS: i64 = 10_000;
+
+/// Метод, записывающий событие об изменении задачи.
+/// Событие становится видно подписчикам после завершения транзакции, в которой оно записано.
+/// Проект и спринт события берутся из состояния после изменения, а если задача
+/// их лишилась или удалена - из состояния до изменения, чтобы о выбытии узнали прежние подписчики.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `action`      - действие из истории задачи: `create`, `update`, `delete` или `revert`.
+/// * `before`      - состояние задачи до изменения. None для новой задачи.
+/// * `after`       - состояние задачи после изменения. None для удалённой задачи.
+/// * `changes`     - изменённые поля задачи.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если событие не удалось сохранить.
+pub fn record_task_event(action: &str, before: Option<&Task>, after: Option<&Task>, changes: &Map<String, Value>, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let task = match after.or(before) {
+        Some(task) => task,
+        None => return Ok(()),
+    };
+    let kind = match action {
+        history::ACTION_CREATE => TASK_CREATED,
+        history::ACTION_DELETE => TASK_DELETED,
+        _ => TASK_UPDATED,
+    };
+    let field_of = |field: fn(&Task) -> &Option<String>| after
+        .and_then(|task| field(task).clone())
+        .or_else(|| before.and_then(|task| field(task).clone()));
+
+    let event = NewEvent{
+        kind: kind.to_string(),
+        entity_type: ENTITY_TASK.to_string(),
+        entity_id: task.id.clone(),
+        project_id: field_of(|task| &task.project_id),
+        sprint_id: field_of(|task| &task.sprint_id),
+        user_id: field_of(|task| &task.user_id),
+        actor_id: actor.map(|actor| actor.to_string()),
+        payload: json!({ "task": task, "changes": changes }),
+        created_at: chrono::Utc::now().naive_utc(),
+    };
+    record(&event, conn)
+}
+
+/// Метод, записывающий событие об изменении пользователя. Пароль в событие не попадает.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `kind`        - тип события: `user.created`, `user.updated` или `user.deleted`.
+/// * `user`        - состояние пользователя после изменения, для удаления - перед ним.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если событие не удалось сохранить.
+pub fn record_user_event(kind: &str, user: &User, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let event = NewEvent{
+        kind: kind.to_string(),
+        entity_type: ENTITY_USER.to_string(),
+        entity_id: user.id.clone(),
+        project_id: None,
+        sprint_id: None,
+        user_id: Some(user.id.clone()),
+        actor_id: actor.map(|actor| actor.to_string()),
+        payload: json!({ "user": {
+            "id": user.id,
+            "user_name": user.user_name,
+            "email": user.email,
+            "role": user.role,
+            "created_at": user.created_at,
+            "updated_at": user.updated_at,
+        } }),
+        created_at: chrono::Utc::now().naive_utc(),
+    };
+    record(&event, conn)
+}
+
+/// Метод, записывающий событие об изменении комментария.
+/// Проект и спринт события берутся из задачи комментария, пользователь события - автор комментария.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `kind`        - тип события: `comment.created`, `comment.updated` или `comment.deleted`.
+/// * `comment`     - состояние комментария после изменения, для удаления - перед ним.
+/// * `actor`       - идентификатор пользователя, выполнившего действие.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если событие не удалось сохранить.
+pub fn record_comment_event(kind: &str, comment: &Comment, actor: &str, conn: &PgConnection) -> Result<(), DbError>{
+    let (project_id, sprint_id) = tasks::table
+        .find(&comment.task_id)
+        .select((tasks::project_id, tasks::sprint_id))
+        .first::<(Option<String>, Option<String>)>(conn)
+        .optional()?
+        .unwrap_or_default();
+    let event = NewEvent{
+        kind: kind.to_string(),
+        entity_type: ENTITY_COMMENT.to_string(),
+        entity_id: comment.id.clone(),
+        project_id,
+        sprint_id,
+        user_id: Some(comment.user_id.clone()),
+        actor_id: Some(actor.to_string()),
+        payload: json!({ "comment": comment }),
+        created_at: chrono::Utc::now().naive_utc(),
+    };
+    record(&event, conn)
+}
+
+/// Метод, возвращающий идентификатор последнего записанного события
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо идентификатор. Если событий нет, возвращается 0.
+pub fn get_last_event_id(conn: &PgConnection) -> Result<i64, DbError>{
+    let last = events::table
+        .select(diesel::dsl::max(events::id))
+        .first::<Option<i64>>(conn)?;
+    Ok(last.unwrap_or(0))
+}
+
+/// Метод, возвращающий события с идентификаторами в диапазоне `(after, up_to]` по возрастанию
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `after`       - идентификатор события, после которого начинается выборка.
+/// * `up_to`       - идентификатор последнего события выборки включительно.
+/// * `limit`       - максимальное количество событий.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор событий.
+pub fn get_events_after(after: i64, up_to: i64, limit: i64, conn: &PgConnection) -> Result<Vec<Event>, DbError>{
+    let found = events::table
+        .filter(events::id.gt(after))
+        .filter(events::id.le(up_to))
+        .order(events::id.asc())
+        .limit(limit)
+        .load::<Event>(conn)?;
+    Ok(found)
+}
+
+/// Метод, возвращающий события, пропущенные клиентом, с идентификаторами в диапазоне `(after, up_to]`.
+/// Продолжить получение можно, только если все пропущенные события ещё хранятся и их не больше `MAX_MISSED_EVENTS`.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `after`       - идентификатор последнего полученного клиентом события.
+/// * `up_to`       - идентификатор последнего события, разосланного подписчикам.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор событий по возрастанию.
+/// Если продолжить получение нельзя и клиенту нужно заново загрузить данные, возвращается None.
+pub fn get_missed_events(after: i64, up_to: i64, conn: &PgConnection) -> Result<Option<Vec<Event>>, DbError>{
+    if after >= up_to {
+        return Ok(Some(Vec::new()));
+    }
+    if after < 0 || up_to - after > MAX_MISSED_EVENTS {
+        return Ok(None);
+    }
+    let oldest = events::table
+        .select(diesel::dsl::min(events::id))
+        .first::<Option<i64>>(conn)?;
+    if oldest.is_some_and(|oldest| oldest > after + 1) {
+        return Ok(None);
+    }
+    Ok(Some(get_events_after(after, up_to, MAX_MISSED_EVENTS, conn)?))
+}
+
+/// Метод, удаляющий события, записанные раньше указанного момента.
+/// Последнее событие сохраняется, чтобы по нему можно было определить, что более ранние удалены.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `before`      - момент, события до которого удаляются.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество удалённых событий.
+pub fn prune_events(before: NaiveDateTime, conn: &PgConnection) -> Result<usize, DbError>{
+    let last = get_last_event_id(conn)?;
+    let deleted = diesel::delete(events::table
+        .filter(events::created_at.lt(before))
+        .filter(events::id.lt(last)))
+        .execute(conn)?;
+    Ok(deleted)
+}
+
+/// Метод, сохраняющий событие и ставящий в очередь его отправку по подпискам проекта.
+fn record(event: &NewEvent, conn: &PgConnection) -> Result<(), DbError>{
+    let event = diesel::insert_into(events::table).values(event).get_result::<Event>(conn)?;
+    webhooks::enqueue_event(&event, conn)
+}
@@ -0,0 +1,253 @@
+use diesel::{prelude::*};
+use chrono::{Duration, NaiveDate, NaiveDateTime};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{Forecast, ForecastQuery};
+use crate::schema::{projects, task_status_changes, tasks};
+use super::{tasks as task_views, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Количество симуляций по умолчанию
+const DEFAULT_ITERATIONS: usize = 10_000;
+
+/// Максимальное количество симуляций
+const MAX_ITERATIONS: usize = 100_000;
+
+/// Длина истории пропускной способности по умолчанию в днях
+const DEFAULT_HISTORY_DAYS: i64 = 90;
+
+/// Максимальная длина истории пропускной способности в днях
+const MAX_HISTORY_DAYS: i64 = 366;
+
+/// Горизонт прогноза в днях. Симуляция, не завершившаяся за это время, считается незавершённой.
+const MAX_FORECAST_DAYS: i64 = 3650;
+
+/// Вероятности, для которых возвращается прогноз
+const PERCENTILES: [u32; 3] = [50, 85, 95];
+
+/// Метод, прогнозирующий методом Монте-Карло, когда будут выполнены оставшиеся задачи проекта
+/// и сколько задач будет выполнено к указанной дате.
+/// Каждая симуляция складывает дневную пропускную способность, случайно выбранную из истории
+/// проекта, начиная с сегодняшнего дня.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `query`       - параметры прогноза.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо прогноз.
+/// Если проект не найден, возвращается None.
+pub fn get_forecast(project: &Uuid, query: &ForecastQuery, conn: &PgConnection) -> Result<Option<Forecast>, DbError>{
+    let iterations = query.iterations.unwrap_or(DEFAULT_ITERATIONS);
+    if iterations == 0 || iterations > MAX_ITERATIONS {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "iterations must be between 1 and {}", MAX_ITERATIONS
+        ))));
+    }
+    let history_days = query.history_days.unwrap_or(DEFAULT_HISTORY_DAYS);
+    if !(1..=MAX_HISTORY_DAYS).contains(&history_days) {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "history_days must be between 1 and {}", MAX_HISTORY_DAYS
+        ))));
+    }
+    if query.remaining.is_some_and(|remaining| remaining < 0) {
+        return Err(Box::new(ClientError::BadRequest("remaining must not be negative".to_string())));
+    }
+    let today = chrono::Utc::now().date_naive();
+    let horizon = match query.by {
+        Some(by) if by < today => return Err(Box::new(ClientError::BadRequest(
+            "Forecast date must not be in the past".to_string()
+        ))),
+        Some(by) if (by - today).num_days() >= MAX_FORECAST_DAYS => return Err(Box::new(ClientError::BadRequest(
+            format!("Forecast date must be within {} days", MAX_FORECAST_DAYS)
+        ))),
+        Some(by) => Some((by - today).num_days() + 1),
+        None => None,
+    };
+
+    let exists = diesel::select(diesel::dsl::exists(projects::table.filter(projects::id.eq(project.to_string()))))
+        .get_result::<bool>(conn)?;
+    if !exists {
+        return Ok(None);
+    }
+    let remaining = match query.remaining {
+        Some(remaining) => remaining,
+        None => tasks::table
+            .filter(tasks::project_id.eq(project.to_string()))
+            .filter(tasks::done.eq(false))
+            .count()
+            .get_result(conn)?,
+    };
+
+    // История берётся по полным дням, сегодняшний день ещё не закончился
+    let history_to = today - Duration::days(1);
+    let history_from = history_to - Duration::days(history_days - 1);
+    let samples = daily_throughput(project, history_from, history_to, conn)?;
+    let completed_in_history: i64 = samples.iter().sum();
+    if completed_in_history == 0 {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "No tasks were completed between {} and {}, nothing to forecast from", history_from, history_to
+        ))));
+    }
+
+    let seed = query.seed.unwrap_or_else(|| u64::from(rand::random::<u32>()));
+    let (days_needed, completed_counts) = simulate(&samples, remaining, horizon, iterations, seed);
+    let completion_dates = PERCENTILES
+        .iter()
+        .map(|percentile| {
+            let days = nearest_rank(&days_needed, *percentile);
+            // Задачи, выполненные за первый день, выполняются сегодня
+            (format!("p{}", percentile), days.map(|days| today + Duration::days((days - 1).max(0))))
+        })
+        .collect();
+    let completed_by = horizon.map(|_| {
+        PERCENTILES
+            .iter()
+            .map(|percentile| (format!("p{}", percentile), nearest_rank(&completed_counts, 100 - percentile)))
+            .collect()
+    });
+
+    Ok(Some(Forecast{
+        seed,
+        iterations,
+        history_from,
+        history_to,
+        completed_in_history,
+        remaining,
+        completion_dates,
+        by: query.by,
+        completed_by,
+    }))
+}
+
+/// Метод, выполняющий симуляции Монте-Карло со случайными числами из указанного зерна.
+/// Возвращает отсортированные количества дней до выполнения оставшихся задач (None, если задачи
+/// не выполнены за MAX_FORECAST_DAYS дней) и отсортированные количества задач, выполненных за `horizon` дней.
+fn simulate(samples: &[i64], remaining: i64, horizon: Option<i64>, iterations: usize, seed: u64) -> (Vec<Option<i64>>, Vec<i64>){
+    let mut rng = ChaCha8Rng::seed_from_u64(seed);
+    let mut days_needed = Vec::with_capacity(iterations);
+    let mut completed_counts = Vec::with_capacity(iterations);
+    for _ in 0..iterations {
+        let mut completed = 0;
+        let mut day = 0;
+        let mut finished_in = if remaining == 0 { Some(0) } else { None };
+        while day < MAX_FORECAST_DAYS && (finished_in.is_none() || horizon.is_some_and(|horizon| day < horizon)) {
+            completed += samples[rng.gen_range(0..samples.len())];
+            day += 1;
+            if finished_in.is_none() && completed >= remaining {
+                finished_in = Some(day);
+            }
+            if horizon == Some(day) {
+                completed_counts.push(completed);
+            }
+        }
+        days_needed.push(finished_in);
+    }
+
+    // Незавершённые симуляции (None) сортируются после любых завершённых
+    days_needed.sort_by_key(|days| days.unwrap_or(i64::MAX));
+    completed_counts.sort_unstable();
+    (days_needed, completed_counts)
+}
+
+/// Метод, возвращающий количество задач проекта, завершённых в каждый день периода.
+/// Днём завершения считается последний переход задачи в статус `done`.
+fn daily_throughput(project: &Uuid, from: NaiveDate, to: NaiveDate, conn: &PgConnection) -> Result<Vec<i64>, DbError>{
+    let completions: Vec<(String, NaiveDateTime)> = task_status_changes::table
+        .inner_join(tasks::table)
+        .filter(tasks::project_id.eq(project.to_string()))
+        .filter(tasks::status.eq(task_views::STATUS_DONE))
+        .filter(task_status_changes::to_status.eq(task_views::STATUS_DONE))
+        .select((task_status_changes::task_id, task_status_changes::changed_at))
+        .load(conn)?;
+    let mut done_at: HashMap<String, NaiveDateTime> = HashMap::new();
+    for (task_id, changed_at) in completions {
+        let entry = done_at.entry(task_id).or_insert(changed_at);
+        *entry = (*entry).max(changed_at);
+    }
+
+    let mut per_day: BTreeMap<NaiveDate, i64> = BTreeMap::new();
+    for changed_at in done_at.values() {
+        let date = changed_at.date();
+        if date >= from && date <= to {
+            *per_day.entry(date).or_insert(0) += 1;
+        }
+    }
+    let days = (to - from).num_days() + 1;
+    Ok((0..days)
+        .map(|offset| per_day.get(&(from + Duration::days(offset))).copied().unwrap_or(0))
+        .collect())
+}
+
+/// Метод, возвращающий значение отсортированной выборки по методу ближайшего ранга.
+fn nearest_rank<T: Copy>(sorted: &[T], percentile: u32) -> T{
+    let rank = (f64::from(percentile) / 100.0 * sorted.len() as f64).ceil() as usize;
+    sorted[rank.clamp(1, sorted.len()) - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nearest_rank_picks_ceiling_rank(){
+        let sorted: Vec<i64> = (1..=10).collect();
+
+        assert_eq!(nearest_rank(&sorted, 50), 5);
+        assert_eq!(nearest_rank(&sorted, 85), 9);
+        assert_eq!(nearest_rank(&sorted, 95), 10);
+        assert_eq!(nearest_rank(&sorted, 100), 10);
+        assert_eq!(nearest_rank(&sorted, 0), 1);
+        assert_eq!(nearest_rank(&[7], 95), 7);
+    }
+
+    #[test]
+    fn nearest_rank_orders_unfinished_simulations_last(){
+        let sorted = [Some(3), Some(4), None, None];
+
+        assert_eq!(nearest_rank(&sorted, 50), Some(4));
+        assert_eq!(nearest_rank(&sorted, 85), None);
+    }
+
+    #[test]
+    fn same_seed_gives_same_forecast(){
+        let samples = [0, 1, 3, 0, 2, 5, 1];
+        let first = simulate(&samples, 40, Some(14), 500, 42);
+        let second = simulate(&samples, 40, Some(14), 500, 42);
+        let other = simulate(&samples, 40, Some(14), 500, 43);
+
+        assert_eq!(first, second);
+        assert_ne!(first, other);
+        assert_eq!(first.0.len(), 500);
+        assert_eq!(first.1.len(), 500);
+    }
+
+    #[test]
+    fn constant_throughput_is_exact(){
+        let (days_needed, completed_counts) = simulate(&[2, 2, 2], 5, Some(4), 50, 1);
+
+        assert!(days_needed.iter().all(|days| *days == Some(3)));
+        assert!(completed_counts.iter().all(|completed| *completed == 8));
+    }
+
+    #[test]
+    fn nothing_remaining_is_done_today(){
+        let (days_needed, completed_counts) = simulate(&[1], 0, None, 10, 1);
+
+        assert!(days_needed.iter().all(|days| *days == Some(0)));
+        assert!(completed_counts.is_empty());
+    }
+
+    #[test]
+    fn simulation_without_progress_does_not_finish(){
+        let (days_needed, _) = simulate(&[0], 1, None, 3, 1);
+
+        assert_eq!(days_needed, vec![None, None, None]);
+    }
+}
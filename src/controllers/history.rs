@@ -0,0 +1,152 @@
+use diesel::{prelude::*};
+use serde_json::{json, Map, Value};
+
+use crate::models::{NewTask, Task, TaskRevision};
+use crate::schema::task_revisions;
+use super::{tasks as task_views, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Создание задачи
+pub const ACTION_CREATE: &str = "create";
+/// Изменение задачи
+pub const ACTION_UPDATE: &str = "update";
+/// Удаление задачи
+pub const ACTION_DELETE: &str = "delete";
+/// Возврат задачи к одной из предыдущих версий
+pub const ACTION_REVERT: &str = "revert";
+
+/// Поля задачи, которые не попадают в список изменений
+const UNTRACKED_FIELDS: [&str; 3] = ["id", "created_at", "updated_at"];
+
+/// Метод, возвращающий историю изменений задачи в порядке времени.
+/// История удалённой задачи остаётся доступной.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор записей истории.
+/// Если у задачи нет истории, возвращается None.
+pub fn get_task_history(task: &Uuid, conn: &PgConnection) -> Result<Option<Vec<TaskRevision>>, DbError>{
+    let revisions = task_revisions::table
+        .filter(task_revisions::task_id.eq(task.to_string()))
+        .order(task_revisions::changed_at.asc())
+        .load::<TaskRevision>(conn)?;
+    if revisions.is_empty() {
+        return Ok(None);
+    }
+    Ok(Some(revisions))
+}
+
+/// Метод, возвращающий задачу к состоянию после указанной записи истории.
+/// Возврат выполняется как обычное изменение задачи и сам попадает в историю.
+/// Принадлежность к спринту и серии повторений не восстанавливается.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `revision`    - уникальный идентификатор записи истории.
+/// * `actor`       - идентификатор пользователя, выполняющего действие.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект задачи.
+/// Если задача или запись истории не найдены, возвращается None.
+pub fn revert_task(task: &Uuid, revision: &Uuid, actor: Option<&str>, conn: &PgConnection) -> Result<Option<Task>, DbError>{
+    let revision = task_revisions::table
+        .filter(task_revisions::id.eq(revision.to_string()))
+        .filter(task_revisions::task_id.eq(task.to_string()))
+        .first::<TaskRevision>(conn)
+        .optional()?;
+    let revision = match revision {
+        Some(revision) => revision,
+        None => return Ok(None),
+    };
+    if task_views::get_task(task, conn)?.is_none() {
+        return Ok(None);
+    }
+    let version: Task = serde_json::from_value(revision.snapshot)
+        .map_err(|err| ClientError::BadRequest(format!("Revision {} cannot be restored: {}", revision.id, err)))?;
+    let new_task = NewTask{
+        title: version.title,
+        body: version.body,
+        done: version.done,
+        user_id: version.user_id,
+        project_id: Some(version.project_id),
+        parent_id: Some(version.parent_id),
+        due_at: Some(version.due_at),
+        estimate_minutes: Some(version.estimate_minutes),
+        estimate_points: Some(version.estimate_points),
+        status: Some(version.status),
+    };
+    task_views::apply_update(task, &new_task, ACTION_REVERT, actor, conn)
+}
+
+/// Метод, вычисляющий изменённые поля задачи для записи в историю.
+/// # Arguments
+///
+/// * `before`      - состояние задачи до изменения. None для новой задачи.
+/// * `after`       - состояние задачи после изменения. None для удалённой задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо изменённые поля со значениями до и после изменения.
+pub fn changes(before: Option<&Task>, after: Option<&Task>) -> Result<Map<String, Value>, DbError>{
+    let before = before.map(serde_json::to_value).transpose()?;
+    let after = after.map(serde_json::to_value).transpose()?;
+    Ok(diff(before.as_ref(), after.as_ref()))
+}
+
+/// Метод, записывающий изменение задачи в историю.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `action`      - действие: `create`, `update`, `delete` или `revert`.
+/// * `before`      - состояние задачи до изменения. None для новой задачи.
+/// * `after`       - состояние задачи после изменения. None для удалённой задачи.
+/// * `changes`     - изменённые поля задачи.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если запись не удалось сохранить.
+pub fn record_revision(action: &str, before: Option<&Task>, after: Option<&Task>, changes: &Map<String, Value>, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let (task_id, snapshot) = match (after, before) {
+        (Some(task), _) | (None, Some(task)) => (task.id.clone(), serde_json::to_value(task)?),
+        (None, None) => return Ok(()),
+    };
+    let revision = TaskRevision{
+        id: Uuid::new_v4().to_string(),
+        task_id,
+        action: action.to_string(),
+        actor_id: actor.map(|actor| actor.to_string()),
+        changes: Value::Object(changes.clone()),
+        snapshot,
+        changed_at: chrono::Utc::now().naive_utc(),
+    };
+    diesel::insert_into(task_revisions::table).values(&revision).execute(conn)?;
+    Ok(())
+}
+
+/// Метод, вычисляющий изменённые поля между двумя состояниями задачи.
+fn diff(before: Option<&Value>, after: Option<&Value>) -> Map<String, Value>{
+    let empty = Map::new();
+    let before = before.and_then(Value::as_object).unwrap_or(&empty);
+    let after = after.and_then(Value::as_object).unwrap_or(&empty);
+    let mut changes = Map::new();
+    for field in before.keys().chain(after.keys()) {
+        if UNTRACKED_FIELDS.contains(&field.as_str()) || changes.contains_key(field) {
+            continue;
+        }
+        let from = before.get(field).unwrap_or(&Value::Null);
+        let to = after.get(field).unwrap_or(&Value::Null);
+        if from != to {
+            changes.insert(field.clone(), json!({ "from": from, "to": to }));
+        }
+    }
+    changes
+}
@@ -0,0 +1,300 @@
+use diesel::{prelude::*};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use std::collections::HashMap;
+
+use crate::models::{Label, LabelUsage, NewLabel, TaskLabel};
+use crate::schema::{labels, task_labels, tasks};
+use super::ClientError;
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Цвет, назначаемый метке, если он не указан при создании
+const DEFAULT_COLOR: &str = "#9e9e9e";
+
+/// Метод проверки цвета метки. Допускается только формат `#rrggbb`.
+fn validate_color(color: &str) -> Result<(), ClientError>{
+    let valid = color.len() == 7
+        && color.starts_with('#')
+        && color[1..].chars().all(|c| c.is_ascii_hexdigit());
+    if valid {
+        Ok(())
+    } else {
+        Err(ClientError::BadRequest(format!("Invalid label color {}, expected #rrggbb", color)))
+    }
+}
+
+/// Метод проверки имени метки.
+fn validate_name(name: &str) -> Result<(), ClientError>{
+    if name.trim().is_empty() {
+        return Err(ClientError::BadRequest("Label name must not be empty".to_string()));
+    }
+    Ok(())
+}
+
+/// Метод, возвращающий вектор меток с количеством их использований
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - идентификатор проекта. Если указан, возвращаются метки проекта и глобальные метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор меток.
+pub fn get_labels(project: Option<&str>, conn: &PgConnection) -> Result<Vec<LabelUsage>, DbError>{
+    let mut query = labels::table.into_boxed();
+    if let Some(project) = project {
+        query = query.filter(
+            labels::project_id.eq(project.to_string()).or(labels::project_id.is_null())
+        );
+    }
+    let labels_list = query.order(labels::name.asc()).load::<Label>(conn)?;
+
+    let label_ids: Vec<String> = labels_list.iter().map(|label| label.id.clone()).collect();
+    let used: Vec<String> = task_labels::table
+        .filter(task_labels::label_id.eq_any(label_ids))
+        .select(task_labels::label_id)
+        .load(conn)?;
+    let mut counts: HashMap<String, i64> = HashMap::new();
+    for label_id in used {
+        *counts.entry(label_id).or_insert(0) += 1;
+    }
+
+    let result = labels_list
+        .into_iter()
+        .map(|label| {
+            let usage = counts.get(&label.id).copied().unwrap_or(0);
+            LabelUsage{ label, usage }
+        })
+        .collect();
+    Ok(result)
+}
+
+/// Метод, возвращающий метку по идентификатору
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект метки.
+pub fn get_label(uuid: &Uuid, conn: &PgConnection) -> Result<Option<Label>, DbError>{
+    let label = labels::table
+        .filter(labels::id.eq(uuid.to_string()))
+        .first::<Label>(conn)
+        .optional()?;
+
+    Ok(label)
+}
+
+/// Метод, создающий метку
+/// # Arguments
+///
+/// * `conn`             - указатель на подключение к базе данных.
+/// * `new_label`        - указатель на десериализованный объект структуры NewLabel.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект метки.
+/// Если метка с таким именем уже есть в проекте, возвращается ошибка 409.
+pub fn create_label(new_label: &NewLabel, conn: &PgConnection) -> Result<Label, DbError>{
+    validate_name(&new_label.name)?;
+    let color = new_label.color.clone().unwrap_or_else(|| DEFAULT_COLOR.to_string());
+    validate_color(&color)?;
+    if let Some(project) = &new_label.project_id {
+        let exists = diesel::select(diesel::dsl::exists(
+            crate::schema::projects::table.filter(crate::schema::projects::id.eq(project))
+        )).get_result::<bool>(conn)?;
+        if !exists {
+            return Err(Box::new(ClientError::BadRequest(format!("Project {} not found", project))));
+        }
+    }
+
+    let new = Label{
+        id: Uuid::new_v4().to_string(),
+        name: new_label.name.trim().to_string(),
+        color,
+        project_id: new_label.project_id.clone(),
+        created_at: chrono::Utc::now().naive_utc(),
+        updated_at: None
+    };
+    diesel::insert_into(labels::table)
+        .values(&new)
+        .execute(conn)
+        .map_err(|err| name_conflict(err, &new.name))?;
+    Ok(new)
+}
+
+/// Метод, переименовывающий метку и изменяющий её цвет. Проект метки не изменяется.
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `uuid`            - уникальный идентификатор объекта метки.
+/// * `new_label`       - указатель на десериализованный объект структуры NewLabel.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект метки.
+/// Если метка с таким именем уже есть в проекте, возвращается ошибка 409.
+pub fn update_label(uuid: &Uuid, new_label: &NewLabel, conn: &PgConnection) -> Result<Option<Label>, DbError>{
+    validate_name(&new_label.name)?;
+    let label = match get_label(uuid, conn)? {
+        Some(label) => label,
+        None => return Ok(None),
+    };
+    let color = new_label.color.clone().unwrap_or(label.color);
+    validate_color(&color)?;
+
+    let label: Label = diesel::update(labels::table.filter(labels::id.eq(uuid.to_string())))
+        .set((
+            labels::name.eq(new_label.name.trim().to_string()),
+            labels::color.eq(color),
+            labels::updated_at.eq(chrono::Utc::now().naive_utc())
+        )).get_result(conn)
+        .map_err(|err| name_conflict(err, new_label.name.trim()))?;
+    Ok(Some(label))
+}
+
+/// Метод, сливающий одну метку с другой.
+/// Все задачи исходной метки получают целевую метку, исходная метка удаляется.
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `source`          - уникальный идентификатор сливаемой метки.
+/// * `target`          - уникальный идентификатор метки, в которую выполняется слияние.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо целевую метку.
+pub fn merge_labels(source: &Uuid, target: &Uuid, conn: &PgConnection) -> Result<Option<Label>, DbError>{
+    if source == target {
+        return Err(Box::new(ClientError::BadRequest("Cannot merge a label into itself".to_string())));
+    }
+    conn.transaction::<_, DbError, _>(|| {
+        let (source_label, target_label) = match (get_label(source, conn)?, get_label(target, conn)?) {
+            (Some(source_label), Some(target_label)) => (source_label, target_label),
+            _ => return Ok(None),
+        };
+        if target_label.project_id.is_some() && target_label.project_id != source_label.project_id {
+            return Err(Box::new(ClientError::BadRequest(
+                "Target label belongs to a different project".to_string()
+            )));
+        }
+
+        let task_ids: Vec<String> = task_labels::table
+            .filter(task_labels::label_id.eq(&source_label.id))
+            .select(task_labels::task_id)
+            .load(conn)?;
+        let links: Vec<TaskLabel> = task_ids
+            .into_iter()
+            .map(|task_id| TaskLabel{ task_id, label_id: target_label.id.clone() })
+            .collect();
+        diesel::insert_into(task_labels::table)
+            .values(&links)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        diesel::delete(labels::table.filter(labels::id.eq(&source_label.id))).execute(conn)?;
+
+        Ok(Some(target_label))
+    })
+}
+
+/// Метод, удаляющий метку по идентификатору
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_label(uuid: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(labels::table.filter(labels::id.eq(uuid.to_string())))
+        .execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, возвращающий метки задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор меток задачи.
+pub fn get_task_labels(task: &Uuid, conn: &PgConnection) -> Result<Vec<Label>, DbError>{
+    let labels_list = labels::table
+        .inner_join(task_labels::table)
+        .filter(task_labels::task_id.eq(task.to_string()))
+        .select(labels::all_columns)
+        .order(labels::name.asc())
+        .load::<Label>(conn)?;
+
+    Ok(labels_list)
+}
+
+/// Метод, добавляющий метку к задаче.
+/// Метку проекта можно назначить только задаче того же проекта.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `label`       - уникальный идентификатор объекта метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо метки задачи.
+/// Если задача или метка не найдены, возвращается None.
+pub fn add_task_label(task: &Uuid, label: &Uuid, conn: &PgConnection) -> Result<Option<Vec<Label>>, DbError>{
+    let task_project = tasks::table
+        .filter(tasks::id.eq(task.to_string()))
+        .select(tasks::project_id)
+        .first::<Option<String>>(conn)
+        .optional()?;
+    let (task_project, label) = match (task_project, get_label(label, conn)?) {
+        (Some(task_project), Some(label)) => (task_project, label),
+        _ => return Ok(None),
+    };
+    if label.project_id.is_some() && label.project_id != task_project {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "Label {} belongs to a different project", label.id
+        ))));
+    }
+
+    diesel::insert_into(task_labels::table)
+        .values(&TaskLabel{ task_id: task.to_string(), label_id: label.id })
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(Some(get_task_labels(task, conn)?))
+}
+
+/// Метод, снимающий метку с задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `label`       - уникальный идентификатор объекта метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn remove_task_label(task: &Uuid, label: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(
+        task_labels::table
+            .filter(task_labels::task_id.eq(task.to_string()))
+            .filter(task_labels::label_id.eq(label.to_string()))
+    ).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, превращающий нарушение уникальности имени метки в ошибку 409.
+fn name_conflict(err: DieselError, name: &str) -> DbError{
+    match err {
+        DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) => Box::new(
+            ClientError::Conflict(format!("Label {} already exists", name))
+        ),
+        err => err.into(),
+    }
+}
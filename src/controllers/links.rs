@@ -0,0 +1,188 @@
+use diesel::{prelude::*};
+use std::collections::{HashSet, VecDeque};
+
+use crate::models::{NewTaskLink, TaskLink};
+use crate::schema::{task_links, tasks};
+use super::ClientError;
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Тип связи "задача блокирует задачу"
+pub const BLOCKS: &str = "blocks";
+
+/// Допустимые типы связей между задачами
+const LINK_TYPES: [&str; 3] = [BLOCKS, "relates_to", "duplicates"];
+
+/// Метод, определяющий, запрещено ли завершать задачу с незавершёнными блокирующими задачами.
+/// Включается переменной окружения BLOCKERS_PREVENT_DONE (`true` или `1`).
+pub fn blockers_prevent_done() -> bool{
+    std::env::var("BLOCKERS_PREVENT_DONE")
+        .map(|value| value == "true" || value == "1")
+        .unwrap_or(false)
+}
+
+/// Метод, возвращающий связи задачи в обоих направлениях
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор связей.
+/// Если задача не найдена, возвращается None.
+pub fn get_task_links(task: &Uuid, conn: &PgConnection) -> Result<Option<Vec<TaskLink>>, DbError>{
+    if !task_exists(&task.to_string(), conn)? {
+        return Ok(None);
+    }
+    let links = task_links::table
+        .filter(task_links::source_id.eq(task.to_string()).or(task_links::target_id.eq(task.to_string())))
+        .order(task_links::created_at.asc())
+        .load::<TaskLink>(conn)?;
+
+    Ok(Some(links))
+}
+
+/// Метод, создающий связь задачи с другой задачей.
+/// Блокирующая связь, образующая цикл, отклоняется.
+/// Таблица связей блокируется до конца транзакции, чтобы параллельно созданные связи не образовали цикл или дубликат.
+/// Связи могут соединять задачи разных проектов, поэтому блокировки на уровне проекта недостаточно.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор исходной задачи.
+/// * `new_link`    - указатель на десериализованный объект структуры NewTaskLink.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект связи.
+/// Если исходная задача не найдена, возвращается None.
+pub fn create_task_link(task: &Uuid, new_link: &NewTaskLink, conn: &PgConnection) -> Result<Option<TaskLink>, DbError>{
+    if !LINK_TYPES.contains(&new_link.link_type.as_str()) {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "Unknown link type {}, expected one of {}", new_link.link_type, LINK_TYPES.join(", ")
+        ))));
+    }
+    let source = task.to_string();
+    let target = new_link.target_id.clone();
+    if source == target {
+        return Err(Box::new(ClientError::BadRequest("Task cannot be linked to itself".to_string())));
+    }
+
+    conn.transaction::<_, DbError, _>(|| {
+        diesel::sql_query("LOCK TABLE task_links IN SHARE ROW EXCLUSIVE MODE").execute(conn)?;
+        if !task_exists(&source, conn)? {
+            return Ok(None);
+        }
+        if !task_exists(&target, conn)? {
+            return Err(Box::new(ClientError::BadRequest(format!("Target task {} not found", target))));
+        }
+        let duplicate = diesel::select(diesel::dsl::exists(
+            task_links::table
+                .filter(task_links::source_id.eq(&source))
+                .filter(task_links::target_id.eq(&target))
+                .filter(task_links::link_type.eq(&new_link.link_type))
+        )).get_result::<bool>(conn)?;
+        if duplicate {
+            return Err(Box::new(ClientError::Conflict("Link already exists".to_string())));
+        }
+        if new_link.link_type == BLOCKS && blocking_path_exists(&target, &source, conn)? {
+            return Err(Box::new(ClientError::Conflict(format!(
+                "Task {} already depends on task {}, link would create a cycle", source, target
+            ))));
+        }
+
+        let new = TaskLink{
+            id: Uuid::new_v4().to_string(),
+            source_id: source.clone(),
+            target_id: target.clone(),
+            link_type: new_link.link_type.clone(),
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        diesel::insert_into(task_links::table).values(&new).execute(conn)?;
+        Ok(Some(new))
+    })
+}
+
+/// Метод, удаляющий связь задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор задачи, к которой относится связь.
+/// * `link`        - уникальный идентификатор объекта связи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_task_link(task: &Uuid, link: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(
+        task_links::table
+            .filter(task_links::id.eq(link.to_string()))
+            .filter(task_links::source_id.eq(task.to_string()).or(task_links::target_id.eq(task.to_string())))
+    ).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, возвращающий те задачи из списка, у которых есть незавершённые блокирующие задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task_ids`    - идентификаторы проверяемых задач.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо множество заблокированных задач.
+pub fn blocked_tasks(task_ids: &[String], conn: &PgConnection) -> Result<HashSet<String>, DbError>{
+    let blockers: Vec<(String, String)> = task_links::table
+        .filter(task_links::link_type.eq(BLOCKS))
+        .filter(task_links::target_id.eq_any(task_ids))
+        .select((task_links::source_id, task_links::target_id))
+        .load(conn)?;
+    if blockers.is_empty() {
+        return Ok(HashSet::new());
+    }
+    let source_ids: Vec<&String> = blockers.iter().map(|(source, _)| source).collect();
+    let open: HashSet<String> = tasks::table
+        .filter(tasks::id.eq_any(source_ids))
+        .filter(tasks::done.eq(false))
+        .select(tasks::id)
+        .load::<String>(conn)?
+        .into_iter()
+        .collect();
+
+    Ok(blockers
+        .into_iter()
+        .filter(|(source, _)| open.contains(source))
+        .map(|(_, target)| target)
+        .collect())
+}
+
+/// Метод, проверяющий наличие пути из блокирующих связей от задачи `from` к задаче `to`.
+fn blocking_path_exists(from: &str, to: &str, conn: &PgConnection) -> Result<bool, DbError>{
+    let mut visited: HashSet<String> = HashSet::new();
+    let mut queue: VecDeque<String> = VecDeque::new();
+    queue.push_back(from.to_string());
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            return Ok(true);
+        }
+        if !visited.insert(current.clone()) {
+            continue;
+        }
+        let next: Vec<String> = task_links::table
+            .filter(task_links::link_type.eq(BLOCKS))
+            .filter(task_links::source_id.eq(&current))
+            .select(task_links::target_id)
+            .load(conn)?;
+        queue.extend(next.into_iter().filter(|task| !visited.contains(task)));
+    }
+    Ok(false)
+}
+
+/// Метод проверки существования задачи.
+fn task_exists(task: &str, conn: &PgConnection) -> Result<bool, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task))))
+        .get_result::<bool>(conn)?;
+    Ok(exists)
+}
@@ -1,20 +1,71 @@
+pub mod assignees;
+pub mod attachments;
+pub mod audit;
+pub mod checklists;
+pub mod comments;
+pub mod emails;
+pub mod events;
+pub mod forecasts;
+pub mod history;
+pub mod labels;
+pub mod links;
+pub mod notifications;
+pub mod projects;
+pub mod recurrence;
+pub mod reports;
+pub mod schedule;
+pub mod sprints;
 pub mod tasks;
+pub mod thumbnails;
 pub mod users;
-use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
-use std::str::FromStr;
+pub mod watchers;
+pub mod webhooks;
+pub mod worklogs;
+use chrono::NaiveDateTime;
+use std::fmt;
+
+/// Ошибка, вызванная некорректным запросом клиента.
+/// В отличие от ошибок базы данных отдаётся клиенту с кодом 4xx.
+#[derive(Debug)]
+pub enum ClientError{
+    /// Некорректные входные данные (400).
+    BadRequest(String),
+    /// Пользователь не прошёл проверку подлинности (401).
+    Unauthorized(String),
+    /// Действие запрещено для текущего пользователя (403).
+    Forbidden(String),
+    /// Запрос противоречит текущему состоянию данных (409).
+    Conflict(String),
+    /// Превышен допустимый размер данных (413).
+    PayloadTooLarge(String),
+}
+
+impl fmt::Display for ClientError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self {
+            ClientError::BadRequest(message) => write!(f, "{}", message),
+            ClientError::Unauthorized(message) => write!(f, "{}", message),
+            ClientError::Forbidden(message) => write!(f, "{}", message),
+            ClientError::Conflict(message) => write!(f, "{}", message),
+            ClientError::PayloadTooLarge(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for ClientError {}
+
+/// Метод экранирования поля CSV: поля с запятыми, кавычками и переводами строк заключаются в кавычки.
+fn csv_field(value: &str) -> String{
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
 
 /// Метод для получения текущей даты.
 /// # Return
 /// Возвращает текущую дату в формате NaiveDateTime
 fn get_date() -> NaiveDateTime{
-    let utc_date_time = chrono::Utc::now().to_string();
-    let _naive = match NaiveDateTime::from_str(&utc_date_time) {
-        Ok(date_time) => {return date_time},
-        Err(_) => {
-            return NaiveDateTime::new(
-            NaiveDate::from_ymd(2000, 1, 1),
-            NaiveTime::from_hms(0, 0, 0)
-            )
-        },
-    };
-}
\ No newline at end of file
+    chrono::Utc::now().naive_utc()
+}
@@ -0,0 +1,268 @@
+use diesel::{prelude::*};
+use serde_json::{json, Map, Value};
+
+use crate::models::{
+    Comment, Notification, NotificationPreferences, NotificationPreferencesUpdate, NotificationQuery, Page, PageQuery, Task
+};
+use crate::schema::{notification_preferences, notifications};
+use super::{emails, history, users, watchers};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Пользователь назначен исполнителем задачи
+pub const KIND_ASSIGNED: &str = "assigned";
+/// Пользователь упомянут в комментарии
+pub const KIND_MENTIONED: &str = "mentioned";
+/// Изменена задача, за которой наблюдает пользователь
+pub const KIND_TASK_CHANGED: &str = "task_changed";
+
+/// Метод, создающий уведомления об изменении задачи.
+/// Новый исполнитель получает уведомление о назначении, остальные наблюдатели задачи - об изменении. Пользователь не получает уведомлений о собственных действиях.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `action`      - действие из истории задачи.
+/// * `before`      - состояние задачи до изменения. None для новой задачи.
+/// * `after`       - состояние задачи после изменения. None для удалённой задачи.
+/// * `changes`     - изменённые поля задачи.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если уведомления не удалось сохранить.
+pub fn notify_task_change(action: &str, before: Option<&Task>, after: Option<&Task>, changes: &Map<String, Value>, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let task = match after.or(before) {
+        Some(task) => task,
+        None => return Ok(()),
+    };
+    let assignee = |task: Option<&Task>| task.and_then(|task| task.user_id.clone());
+    let assigned = assignee(after).filter(|user| assignee(before).as_ref() != Some(user));
+    if let Some(user) = &assigned {
+        let details = json!({ "title": task.title });
+        notify(user, KIND_ASSIGNED, Some(&task.id), None, actor, details, conn)?;
+    }
+    if action == history::ACTION_CREATE {
+        return Ok(());
+    }
+
+    let fields: Vec<&String> = changes.keys().collect();
+    let details = json!({ "title": task.title, "action": action, "fields": fields });
+    for user in watchers::get_watcher_ids(&task.id, conn)? {
+        if assigned.as_ref() != Some(&user) {
+            notify(&user, KIND_TASK_CHANGED, Some(&task.id), None, actor, details.clone(), conn)?;
+        }
+    }
+    Ok(())
+}
+
+/// Метод, создающий уведомление о добавлении пользователя в исполнители задачи.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - задача.
+/// * `user`        - идентификатор добавленного исполнителя.
+/// * `role`        - роль исполнителя.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если уведомление не удалось сохранить.
+pub fn notify_assignment(task: &Task, user: &str, role: &str, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let details = json!({ "title": task.title, "role": role });
+    notify(user, KIND_ASSIGNED, Some(&task.id), None, actor, details, conn)
+}
+
+/// Метод, создающий уведомления для пользователей, упомянутых в комментарии.
+/// Автор комментария не получает уведомления об упоминании самого себя.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `comment`     - комментарий.
+/// * `mentioned`   - идентификаторы впервые упомянутых в комментарии пользователей.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если уведомления не удалось сохранить.
+pub fn notify_mentions(comment: &Comment, mentioned: &[String], conn: &PgConnection) -> Result<(), DbError>{
+    let details = json!({ "body": comment.body });
+    for user in mentioned {
+        notify(user, KIND_MENTIONED, Some(&comment.task_id), Some(&comment.id), Some(&comment.user_id), details.clone(), conn)?;
+    }
+    Ok(())
+}
+
+/// Метод, возвращающий страницу уведомлений пользователя, начиная с последних
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `user`        - идентификатор пользователя.
+/// * `query`       - фильтр непрочитанных и параметры страницы.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу уведомлений.
+pub fn get_notifications(user: &str, query: &NotificationQuery, conn: &PgConnection) -> Result<Page<Notification>, DbError>{
+    let (page, per_page) = PageQuery{ page: query.page, per_page: query.per_page }.bounds();
+    let unread = query.unread.unwrap_or(false);
+    let filtered = || {
+        let mut select = notifications::table
+            .filter(notifications::user_id.eq(user.to_string()))
+            .into_boxed();
+        if unread {
+            select = select.filter(notifications::read_at.is_null());
+        }
+        select
+    };
+    let total = filtered().count().get_result::<i64>(conn)?;
+    let items = filtered()
+        .order((notifications::created_at.desc(), notifications::id.desc()))
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .load::<Notification>(conn)?;
+
+    Ok(Page{ items, total, page, per_page })
+}
+
+/// Метод, отмечающий уведомление пользователя прочитанным
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `user`            - идентификатор пользователя.
+/// * `notification`    - уникальный идентификатор объекта уведомления.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект уведомления.
+/// Если уведомление не найдено среди уведомлений пользователя, возвращается None.
+pub fn mark_read(user: &str, notification: &Uuid, conn: &PgConnection) -> Result<Option<Notification>, DbError>{
+    let target = notifications::table
+        .filter(notifications::id.eq(notification.to_string()))
+        .filter(notifications::user_id.eq(user));
+    // Повторная отметка не меняет время прочтения
+    diesel::update(target.clone().filter(notifications::read_at.is_null()))
+        .set(notifications::read_at.eq(chrono::Utc::now().naive_utc()))
+        .execute(conn)?;
+    let updated = target.first::<Notification>(conn).optional()?;
+    Ok(updated)
+}
+
+/// Метод, отмечающий прочитанными все уведомления пользователя
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `user`        - идентификатор пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество отмеченных уведомлений.
+pub fn mark_all_read(user: &str, conn: &PgConnection) -> Result<usize, DbError>{
+    let updated = diesel::update(notifications::table
+        .filter(notifications::user_id.eq(user))
+        .filter(notifications::read_at.is_null()))
+        .set(notifications::read_at.eq(chrono::Utc::now().naive_utc()))
+        .execute(conn)?;
+    Ok(updated)
+}
+
+/// Метод, возвращающий настройки уведомлений пользователя
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `user`        - идентификатор пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо настройки.
+/// Если пользователь не менял настройки, возвращаются настройки по умолчанию.
+pub fn get_preferences(user: &str, conn: &PgConnection) -> Result<NotificationPreferences, DbError>{
+    let preferences = notification_preferences::table
+        .find(user)
+        .first::<NotificationPreferences>(conn)
+        .optional()?;
+    Ok(preferences.unwrap_or_else(|| NotificationPreferences{
+        user_id: user.to_string(),
+        assigned: true,
+        mentioned: true,
+        task_changed: true,
+        updated_at: chrono::Utc::now().naive_utc(),
+        email_mode: emails::MODE_INSTANT.to_string(),
+        locale: "ru".to_string(),
+        due_soon: true,
+        digest_sent_at: None,
+    }))
+}
+
+/// Метод, изменяющий настройки уведомлений пользователя
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `user`        - идентификатор пользователя.
+/// * `update`      - изменяемые настройки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо новые настройки.
+/// Если пользователь не найден, возвращается ошибка 401, если режим писем или язык неизвестны - ошибка 400.
+pub fn update_preferences(user: &str, update: &NotificationPreferencesUpdate, conn: &PgConnection) -> Result<NotificationPreferences, DbError>{
+    users::authenticate(user, conn)?;
+    let current = get_preferences(user, conn)?;
+    let email_mode = update.email_mode.clone().unwrap_or(current.email_mode.clone());
+    let locale = update.locale.clone().unwrap_or(current.locale);
+    emails::validate_settings(&email_mode, &locale)?;
+    let now = chrono::Utc::now().naive_utc();
+    // Первая сводка после включения режима сводки содержит только новые события
+    let digest_sent_at = match current.digest_sent_at {
+        None if email_mode == emails::MODE_DIGEST && current.email_mode != emails::MODE_DIGEST => Some(now),
+        digest_sent_at => digest_sent_at,
+    };
+    let preferences = NotificationPreferences{
+        user_id: current.user_id,
+        assigned: update.assigned.unwrap_or(current.assigned),
+        mentioned: update.mentioned.unwrap_or(current.mentioned),
+        task_changed: update.task_changed.unwrap_or(current.task_changed),
+        updated_at: now,
+        email_mode,
+        locale,
+        due_soon: update.due_soon.unwrap_or(current.due_soon),
+        digest_sent_at,
+    };
+    let saved = diesel::insert_into(notification_preferences::table)
+        .values(&preferences)
+        .on_conflict(notification_preferences::user_id)
+        .do_update()
+        .set(&preferences)
+        .get_result::<NotificationPreferences>(conn)?;
+    Ok(saved)
+}
+
+/// Метод, создающий уведомление, если пользователь не отключил уведомления этого вида
+/// и событие вызвано не им самим, и ставящий в очередь письмо о нём.
+fn notify(user: &str, kind: &str, task: Option<&str>, comment: Option<&str>, actor: Option<&str>, details: Value, conn: &PgConnection) -> Result<(), DbError>{
+    if actor == Some(user) {
+        return Ok(());
+    }
+    let preferences = get_preferences(user, conn)?;
+    let enabled = match kind {
+        KIND_ASSIGNED => preferences.assigned,
+        KIND_MENTIONED => preferences.mentioned,
+        _ => preferences.task_changed,
+    };
+    if !enabled {
+        return Ok(());
+    }
+    let notification = Notification{
+        id: Uuid::new_v4().to_string(),
+        user_id: user.to_string(),
+        kind: kind.to_string(),
+        task_id: task.map(|task| task.to_string()),
+        comment_id: comment.map(|comment| comment.to_string()),
+        actor_id: actor.map(|actor| actor.to_string()),
+        details,
+        read_at: None,
+        created_at: chrono::Utc::now().naive_utc(),
+    };
+    diesel::insert_into(notifications::table).values(&notification).execute(conn)?;
+    emails::enqueue_notification(&notification, &preferences, conn)?;
+    Ok(())
+}
@@ -0,0 +1,96 @@
+use diesel::{prelude::*};
+
+use crate::models::{self, NewProject, Project};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+use crate::schema::projects::dsl::*;
+
+/// Метод, возвращающий вектор объектов проектов
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор объектов проектов.
+pub fn get_projects(conn: &PgConnection) -> Result<Vec<models::Project>, DbError>{
+    let projects_list = projects.order(name.asc()).load(conn)?;
+
+    Ok(projects_list)
+}
+
+/// Метод, возвращающий проект по идентификатору
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект проекта.
+pub fn get_project(uuid: &Uuid, conn: &PgConnection) -> Result<Option<Project>, DbError>{
+    let project = projects
+    .filter(id.eq(uuid.to_string()))
+    .first::<Project>(conn)
+    .optional()?;
+
+    Ok(project)
+}
+
+/// Метод, создающий проект
+/// # Arguments
+///
+/// * `conn`             - указатель на подключение к базе данных.
+/// * `new_project`      - указатель на десериализованный объект структуры NewProject.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект проекта.
+pub fn create_project(new_project: &NewProject, conn: &PgConnection) -> Result<models::Project, DbError>{
+    let new = Project{
+        id: Uuid::new_v4().to_string(),
+        name: new_project.name.clone(),
+        created_at: chrono::Utc::now().naive_utc(),
+        updated_at: None,
+        storage_quota: new_project.storage_quota
+    };
+    diesel::insert_into(projects).values(&new).execute(conn)?;
+    Ok(new)
+}
+
+/// Метод, удаляющий проект по идентификатору.
+/// Задачи проекта остаются без проекта, метки проекта удаляются.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_project(uuid: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(projects.filter(id.eq(uuid.to_string())))
+        .execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, изменяющий проект по идентификатору
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `uuid`            - уникальный идентификатор объекта проекта.
+/// * `new_project`     - указатель на десериализованный объект структуры NewProject.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект проекта.
+pub fn update_project(uuid: &Uuid, new_project: &NewProject, conn: &PgConnection) -> Result<Project, DbError>{
+    let project: Project = diesel::update(projects.filter(id.eq(uuid.to_string())))
+        .set((
+            name.eq(new_project.name.clone()),
+            storage_quota.eq(new_project.storage_quota),
+            updated_at.eq(chrono::Utc::now().naive_utc())
+        )).get_result(conn)?;
+    Ok(project)
+}
@@ -0,0 +1,396 @@
+use diesel::{prelude::*};
+use chrono::{NaiveDateTime, TimeZone};
+use rrule::{RRule, RRuleSet, Unvalidated};
+
+use crate::models::{ChecklistItem, Occurrence, RecurrenceEdit, Task, TaskLabel, TaskSeries};
+use crate::schema::{checklist_items, task_labels, task_series, tasks};
+use super::{history, tasks as task_views, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Часовой пояс серии по умолчанию
+const DEFAULT_TIMEZONE: &str = "UTC";
+
+/// Количество повторений в предпросмотре по умолчанию
+const DEFAULT_PREVIEW_COUNT: usize = 5;
+
+/// Максимальное количество повторений в предпросмотре
+const MAX_PREVIEW_COUNT: usize = 100;
+
+/// Метод, возвращающий следующие повторения задачи после её срока
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта задачи.
+/// * `count`       - количество повторений, по умолчанию 5, не более 100.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор повторений.
+/// Если задача не найдена, возвращается None.
+pub fn get_occurrences(uuid: &Uuid, count: Option<usize>, conn: &PgConnection) -> Result<Option<Vec<Occurrence>>, DbError>{
+    let task = match tasks::table.filter(tasks::id.eq(uuid.to_string())).first::<Task>(conn).optional()? {
+        Some(task) => task,
+        None => return Ok(None),
+    };
+    let (series, due) = match (&task.series_id, task.due_at) {
+        (Some(series), Some(due)) => (get_series(series, conn)?, due),
+        _ => return Err(Box::new(ClientError::BadRequest(format!("Task {} is not recurring", uuid)))),
+    };
+    let count = count.unwrap_or(DEFAULT_PREVIEW_COUNT).clamp(1, MAX_PREVIEW_COUNT);
+    let tz = parse_timezone(&series.timezone)?;
+    let occurrences = occurrences_after(&series, due, count)?
+        .into_iter()
+        .map(|due_at| Occurrence{
+            due_at,
+            local: tz.from_utc_datetime(&due_at).to_rfc3339(),
+        })
+        .collect();
+
+    Ok(Some(occurrences))
+}
+
+/// Метод, изменяющий задачу и все её будущие повторения
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта задачи.
+/// * `edit`        - указатель на десериализованный объект структуры RecurrenceEdit.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо серию, к которой теперь относится задача.
+/// Если задача не найдена, возвращается None.
+pub fn update_recurrence(uuid: &Uuid, edit: &RecurrenceEdit, actor: Option<&str>, conn: &PgConnection) -> Result<Option<TaskSeries>, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let task = match tasks::table
+            .filter(tasks::id.eq(uuid.to_string()))
+            .for_update()
+            .first::<Task>(conn)
+            .optional()? {
+            Some(task) => task,
+            None => return Ok(None),
+        };
+        let due = match task.due_at {
+            Some(due) => due,
+            None => return Err(Box::new(ClientError::BadRequest(
+                "Recurring task must have a due date".to_string()
+            ))),
+        };
+        let current = match &task.series_id {
+            Some(series) => Some(get_series(series, conn)?),
+            None => None,
+        };
+        // Изменение затрагивает задачу и её будущие повторения, их прежнее состояние нужно для истории
+        let before = match &task.series_id {
+            Some(series) => tasks::table
+                .filter(tasks::series_id.eq(series))
+                .filter(tasks::due_at.ge(due))
+                .for_update()
+                .load::<Task>(conn)?,
+            None => tasks::table.filter(tasks::id.eq(&task.id)).load::<Task>(conn)?,
+        };
+
+        let rule_changed = edit.rrule.is_some() || edit.timezone.is_some();
+        let series = match current {
+            Some(current) if !rule_changed => current,
+            current => {
+                let rrule = match (&edit.rrule, &current) {
+                    (Some(rrule), _) => normalize_rule(rrule),
+                    (None, Some(current)) => current.rrule.clone(),
+                    (None, None) => return Err(Box::new(ClientError::BadRequest(
+                        "rrule is required to make a task recurring".to_string()
+                    ))),
+                };
+                let timezone = edit.timezone.clone()
+                    .or_else(|| current.as_ref().map(|current| current.timezone.clone()))
+                    .unwrap_or_else(|| DEFAULT_TIMEZONE.to_string());
+                let new_series = TaskSeries{
+                    id: Uuid::new_v4().to_string(),
+                    rrule,
+                    timezone,
+                    dtstart: due,
+                    created_at: chrono::Utc::now().naive_utc(),
+                };
+                build_rule_set(&new_series)?;
+                diesel::insert_into(task_series::table).values(&new_series).execute(conn)?;
+
+                match current {
+                    Some(current) => split_series(&current, &new_series, due, conn)?,
+                    None => {
+                        diesel::update(tasks::table.filter(tasks::id.eq(&task.id)))
+                            .set(tasks::series_id.eq(&new_series.id))
+                            .execute(conn)?;
+                    },
+                }
+                new_series
+            },
+        };
+
+        // Текст меняется у задачи и у всех следующих за ней повторений серии
+        let future = tasks::table
+            .filter(tasks::series_id.eq(&series.id))
+            .filter(tasks::due_at.ge(due));
+        if let Some(title) = &edit.title {
+            diesel::update(future).set(tasks::title.eq(title)).execute(conn)?;
+        }
+        if let Some(body) = &edit.body {
+            diesel::update(future).set(tasks::body.eq(body)).execute(conn)?;
+        }
+        task_views::on_tasks_changed(&before, actor, conn)?;
+
+        Ok(Some(series))
+    })
+}
+
+/// Метод, создающий следующее повторение задачи после её завершения.
+/// Новая задача копирует поля, метки и чек-лист завершённой задачи и получает срок
+/// следующего повторения. Если серия закончилась (`UNTIL`/`COUNT`) или следующее повторение
+/// уже существует, ничего не создаётся.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - завершённая задача серии.
+/// * `actor`       - идентификатор пользователя, завершившего задачу, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо созданную задачу.
+pub fn create_next_occurrence(task: &Task, actor: Option<&str>, conn: &PgConnection) -> Result<Option<Task>, DbError>{
+    let (series, due) = match (&task.series_id, task.due_at) {
+        (Some(series), Some(due)) => (get_series(series, conn)?, due),
+        _ => return Ok(None),
+    };
+    let next_due = match occurrences_after(&series, due, 1)?.pop() {
+        Some(next_due) => next_due,
+        None => return Ok(None),
+    };
+    let exists = diesel::select(diesel::dsl::exists(
+        tasks::table
+            .filter(tasks::series_id.eq(&series.id))
+            .filter(tasks::due_at.eq(next_due))
+    )).get_result::<bool>(conn)?;
+    if exists {
+        return Ok(None);
+    }
+
+    let next = Task{
+        id: Uuid::new_v4().to_string(),
+        title: task.title.clone(),
+        body: task.body.clone(),
+        done: false,
+        user_id: task.user_id.clone(),
+        created_at: chrono::Utc::now().naive_utc(),
+        updated_at: None,
+        project_id: task.project_id.clone(),
+        parent_id: task.parent_id.clone(),
+        due_at: Some(next_due),
+        estimate_minutes: task.estimate_minutes,
+        series_id: Some(series.id.clone()),
+        estimate_points: task.estimate_points,
+        sprint_id: None,
+        status: task_views::STATUS_TODO.to_string(),
+    };
+    diesel::insert_into(tasks::table).values(&next).execute(conn)?;
+    task_views::record_status_change(&next.id, None, &next.status, conn)?;
+    task_views::on_task_changed(history::ACTION_CREATE, None, Some(&next), actor, conn)?;
+
+    let labels: Vec<TaskLabel> = task_labels::table
+        .filter(task_labels::task_id.eq(&task.id))
+        .load::<TaskLabel>(conn)?
+        .into_iter()
+        .map(|label| TaskLabel{ task_id: next.id.clone(), label_id: label.label_id })
+        .collect();
+    diesel::insert_into(task_labels::table).values(&labels).execute(conn)?;
+
+    let checklist: Vec<ChecklistItem> = checklist_items::table
+        .filter(checklist_items::task_id.eq(&task.id))
+        .load::<ChecklistItem>(conn)?
+        .into_iter()
+        .map(|item| ChecklistItem{
+            id: Uuid::new_v4().to_string(),
+            task_id: next.id.clone(),
+            checked: false,
+            checked_by: None,
+            checked_at: None,
+            created_at: next.created_at,
+            ..item
+        })
+        .collect();
+    diesel::insert_into(checklist_items::table).values(&checklist).execute(conn)?;
+
+    Ok(Some(next))
+}
+
+/// Метод, разделяющий серию: старое правило ограничивается моментом `split_at`,
+/// а задачи серии, начиная с этого момента, переносятся в новую серию.
+/// Если разделение приходится на начало серии, старая серия удаляется.
+fn split_series(current: &TaskSeries, new_series: &TaskSeries, split_at: NaiveDateTime, conn: &PgConnection) -> Result<(), DbError>{
+    diesel::update(
+        tasks::table
+            .filter(tasks::series_id.eq(&current.id))
+            .filter(tasks::due_at.ge(split_at))
+    )
+        .set(tasks::series_id.eq(&new_series.id))
+        .execute(conn)?;
+    let current_row = task_series::table.filter(task_series::id.eq(&current.id));
+    if split_at <= current.dtstart {
+        diesel::delete(current_row).execute(conn)?;
+    } else {
+        let until = split_at - chrono::Duration::seconds(1);
+        diesel::update(current_row)
+            .set(task_series::rrule.eq(truncate_rule(&current.rrule, until)))
+            .execute(conn)?;
+    }
+    Ok(())
+}
+
+/// Метод, возвращающий серию по идентификатору.
+fn get_series(series: &str, conn: &PgConnection) -> Result<TaskSeries, DbError>{
+    let series = task_series::table
+        .filter(task_series::id.eq(series))
+        .first::<TaskSeries>(conn)?;
+    Ok(series)
+}
+
+/// Метод, вычисляющий до `count` повторений серии строго после момента `after` (UTC).
+fn occurrences_after(series: &TaskSeries, after: NaiveDateTime, count: usize) -> Result<Vec<NaiveDateTime>, DbError>{
+    let occurrences = build_rule_set(series)?
+        .limit()
+        .into_iter()
+        .map(|occurrence| occurrence.naive_utc())
+        .skip_while(|occurrence| *occurrence <= after)
+        .take(count)
+        .collect();
+    Ok(occurrences)
+}
+
+/// Метод, строящий набор повторений серии. Время начала переводится в часовой пояс серии,
+/// поэтому повторения сохраняют местное время при переходе на летнее время.
+fn build_rule_set(series: &TaskSeries) -> Result<RRuleSet, DbError>{
+    let tz = parse_timezone(&series.timezone)?;
+    let dtstart = tz
+        .from_utc_datetime(&series.dtstart)
+        .with_timezone(&rrule::Tz::Tz(tz));
+    let rule = series.rrule
+        .parse::<RRule<Unvalidated>>()
+        .map_err(|err| ClientError::BadRequest(format!("Invalid rrule: {}", err)))?;
+    let rule_set = rule
+        .build(dtstart)
+        .map_err(|err| ClientError::BadRequest(format!("Invalid rrule: {}", err)))?;
+    Ok(rule_set)
+}
+
+/// Метод разбора часового пояса IANA.
+pub fn parse_timezone(timezone: &str) -> Result<chrono_tz::Tz, ClientError>{
+    timezone
+        .parse::<chrono_tz::Tz>()
+        .map_err(|_| ClientError::BadRequest(format!("Unknown time zone {}", timezone)))
+}
+
+/// Метод, приводящий правило к виду без префикса `RRULE:`.
+fn normalize_rule(rrule: &str) -> String{
+    let rrule = rrule.trim();
+    rrule.strip_prefix("RRULE:").unwrap_or(rrule).to_string()
+}
+
+/// Метод, заменяющий ограничение правила (`COUNT` или `UNTIL`) на `UNTIL` с указанным моментом (UTC).
+fn truncate_rule(rrule: &str, until: NaiveDateTime) -> String{
+    let mut parts: Vec<String> = rrule
+        .split(';')
+        .filter(|part| !part.starts_with("COUNT=") && !part.starts_with("UNTIL="))
+        .map(|part| part.to_string())
+        .collect();
+    parts.push(format!("UNTIL={}", until.format("%Y%m%dT%H%M%SZ")));
+    parts.join(";")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    /// Метод, создающий серию с указанным правилом, часовым поясом и началом (UTC).
+    fn series(rrule: &str, timezone: &str, dtstart: NaiveDateTime) -> TaskSeries{
+        TaskSeries{
+            id: "series".to_string(),
+            rrule: rrule.to_string(),
+            timezone: timezone.to_string(),
+            dtstart,
+            created_at: dtstart,
+        }
+    }
+
+    fn at(year: i32, month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime{
+        NaiveDate::from_ymd_opt(year, month, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    #[test]
+    fn next_daily_occurrence_is_strictly_after(){
+        let series = series("FREQ=DAILY", "UTC", at(2026, 10, 19, 9, 0));
+
+        assert_eq!(occurrences_after(&series, at(2026, 10, 19, 9, 0), 1).unwrap(), vec![at(2026, 10, 20, 9, 0)]);
+        assert_eq!(occurrences_after(&series, at(2026, 10, 19, 8, 59), 1).unwrap(), vec![at(2026, 10, 19, 9, 0)]);
+    }
+
+    #[test]
+    fn next_weekly_occurrence_follows_byday(){
+        // 19 октября 2026 года - понедельник
+        let series = series("FREQ=WEEKLY;BYDAY=MO,WE", "UTC", at(2026, 10, 19, 9, 0));
+
+        assert_eq!(
+            occurrences_after(&series, at(2026, 10, 19, 9, 0), 3).unwrap(),
+            vec![at(2026, 10, 21, 9, 0), at(2026, 10, 26, 9, 0), at(2026, 10, 28, 9, 0)]
+        );
+    }
+
+    #[test]
+    fn occurrences_keep_local_time_across_dst(){
+        // 09:00 по Берлину: UTC+1 до 29 марта 2026 года, затем UTC+2
+        let series = series("FREQ=DAILY", "Europe/Berlin", at(2026, 3, 27, 8, 0));
+
+        assert_eq!(
+            occurrences_after(&series, at(2026, 3, 27, 8, 0), 2).unwrap(),
+            vec![at(2026, 3, 28, 8, 0), at(2026, 3, 29, 7, 0)]
+        );
+    }
+
+    #[test]
+    fn finished_series_has_no_next_occurrence(){
+        let series = series("FREQ=DAILY;COUNT=2", "UTC", at(2026, 10, 19, 9, 0));
+
+        assert_eq!(occurrences_after(&series, at(2026, 10, 19, 9, 0), 1).unwrap(), vec![at(2026, 10, 20, 9, 0)]);
+        assert!(occurrences_after(&series, at(2026, 10, 20, 9, 0), 1).unwrap().is_empty());
+    }
+
+    #[test]
+    fn truncated_rule_ends_before_split(){
+        let rule = truncate_rule("FREQ=DAILY;COUNT=10", at(2026, 10, 21, 8, 59));
+        let series = series(&rule, "UTC", at(2026, 10, 19, 9, 0));
+
+        assert_eq!(rule, "FREQ=DAILY;UNTIL=20261021T085900Z");
+        assert_eq!(
+            occurrences_after(&series, at(2026, 10, 18, 0, 0), 5).unwrap(),
+            vec![at(2026, 10, 19, 9, 0), at(2026, 10, 20, 9, 0)]
+        );
+    }
+
+    #[test]
+    fn normalizes_rrule_prefix(){
+        assert_eq!(normalize_rule(" RRULE:FREQ=WEEKLY "), "FREQ=WEEKLY");
+        assert_eq!(normalize_rule("FREQ=WEEKLY"), "FREQ=WEEKLY");
+    }
+
+    #[test]
+    fn rejects_invalid_rule_and_time_zone(){
+        let invalid_rule = series("FREQ=SOMETIMES", "UTC", at(2026, 10, 19, 9, 0));
+        let invalid_zone = series("FREQ=DAILY", "Mars/Olympus", at(2026, 10, 19, 9, 0));
+
+        for series in [invalid_rule, invalid_zone] {
+            let err = occurrences_after(&series, at(2026, 10, 19, 9, 0), 1).err().unwrap();
+            assert!(matches!(err.downcast_ref::<ClientError>(), Some(ClientError::BadRequest(_))));
+        }
+    }
+}
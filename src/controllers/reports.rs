@@ -0,0 +1,380 @@
+use diesel::{prelude::*};
+use chrono::{Datelike, Duration, NaiveDate, NaiveDateTime};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{
+    AgingTask, BurnChart, BurnPoint, CumulativeFlow, CumulativeFlowDay, FlowAnalytics, HistogramBucket, ReportQuery, Sprint,
+    TimeDistribution, WeeklyThroughput
+};
+use crate::schema::{projects, sprints, task_status_changes, tasks};
+use super::{tasks as task_views, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Максимальная длина периода отчёта в днях
+const MAX_REPORT_DAYS: i64 = 366;
+
+/// Длина периода отчёта по проекту по умолчанию в днях
+const DEFAULT_REPORT_DAYS: i64 = 30;
+
+/// Допустимые единицы объёма работ для диаграмм сгорания
+const UNITS: [&str; 2] = ["points", "tasks"];
+
+/// Перцентили, вычисляемые для распределений времени выполнения
+const PERCENTILES: [u32; 4] = [50, 75, 85, 95];
+
+/// История статусов одной задачи: смены статуса в порядке времени и объём задачи
+struct TaskTimeline{
+    changes: Vec<(NaiveDateTime, String)>,
+    weight: i64,
+}
+
+impl TaskTimeline{
+    /// Метод, возвращающий статус задачи на указанный момент. None - задача ещё не создана.
+    fn status_at(&self, moment: NaiveDateTime) -> Option<&str>{
+        self.changes
+            .iter()
+            .take_while(|(changed_at, _)| *changed_at < moment)
+            .last()
+            .map(|(_, status)| status.as_str())
+    }
+}
+
+/// Метод, возвращающий диаграмму сгорания спринта за период от его начала до конца
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `sprint`      - уникальный идентификатор объекта спринта.
+/// * `query`       - параметры отчёта, используется только единица объёма `unit`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо диаграмму сгорания.
+/// Если спринт не найден, возвращается None.
+pub fn get_sprint_burndown(sprint: &Uuid, query: &ReportQuery, conn: &PgConnection) -> Result<Option<BurnChart>, DbError>{
+    let unit = parse_unit(query)?;
+    let sprint = match sprints::table
+        .filter(sprints::id.eq(sprint.to_string()))
+        .first::<Sprint>(conn)
+        .optional()? {
+        Some(sprint) => sprint,
+        None => return Ok(None),
+    };
+    let task_rows: Vec<(String, Option<i32>)> = tasks::table
+        .filter(tasks::sprint_id.eq(&sprint.id))
+        .select((tasks::id, tasks::estimate_points))
+        .load(conn)?;
+    let timelines = load_timelines(task_rows, &unit, conn)?;
+
+    Ok(Some(burn_chart(&timelines, unit, sprint.start_date, sprint.end_date)?))
+}
+
+/// Метод, возвращающий диаграмму сгорания проекта за период
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `query`       - период `from`/`to` (по умолчанию последние 30 дней) и единица объёма `unit`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо диаграмму сгорания.
+/// Если проект не найден, возвращается None.
+pub fn get_project_burndown(project: &Uuid, query: &ReportQuery, conn: &PgConnection) -> Result<Option<BurnChart>, DbError>{
+    let unit = parse_unit(query)?;
+    let (from, to) = parse_period(query)?;
+    if !project_exists(project, conn)? {
+        return Ok(None);
+    }
+    let task_rows: Vec<(String, Option<i32>)> = tasks::table
+        .filter(tasks::project_id.eq(project.to_string()))
+        .select((tasks::id, tasks::estimate_points))
+        .load(conn)?;
+    let timelines = load_timelines(task_rows, &unit, conn)?;
+
+    Ok(Some(burn_chart(&timelines, unit, from, to)?))
+}
+
+/// Метод, возвращающий данные накопительной диаграммы потока проекта
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `query`       - период `from`/`to` (по умолчанию последние 30 дней).
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество задач по статусам на каждый день.
+/// Если проект не найден, возвращается None.
+pub fn get_cumulative_flow(project: &Uuid, query: &ReportQuery, conn: &PgConnection) -> Result<Option<CumulativeFlow>, DbError>{
+    let (from, to) = parse_period(query)?;
+    if !project_exists(project, conn)? {
+        return Ok(None);
+    }
+    let task_rows: Vec<(String, Option<i32>)> = tasks::table
+        .filter(tasks::project_id.eq(project.to_string()))
+        .select((tasks::id, tasks::estimate_points))
+        .load(conn)?;
+    let timelines = load_timelines(task_rows, "tasks", conn)?;
+
+    let mut days = Vec::new();
+    let mut date = from;
+    while date <= to {
+        let end_of_day = (date + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default();
+        let mut counts: BTreeMap<String, i64> = task_views::STATUSES
+            .iter()
+            .map(|status| (status.to_string(), 0))
+            .collect();
+        for timeline in &timelines {
+            if let Some(status) = timeline.status_at(end_of_day) {
+                *counts.entry(status.to_string()).or_insert(0) += 1;
+            }
+        }
+        days.push(CumulativeFlowDay{ date, counts });
+        date += Duration::days(1);
+    }
+
+    Ok(Some(CumulativeFlow{
+        statuses: task_views::STATUSES.iter().map(|status| status.to_string()).collect(),
+        days,
+    }))
+}
+
+/// Метод, возвращающий аналитику потока проекта: распределения времени выполнения (lead time)
+/// и времени цикла (cycle time) задач, завершённых за период, пропускную способность по неделям
+/// и задачи, находящиеся в работе сейчас.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `query`       - период `from`/`to` (по умолчанию последние 30 дней), в который задачи были завершены.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо аналитику потока.
+/// Если проект не найден, возвращается None.
+pub fn get_flow_analytics(project: &Uuid, query: &ReportQuery, conn: &PgConnection) -> Result<Option<FlowAnalytics>, DbError>{
+    let (from, to) = parse_period(query)?;
+    if !project_exists(project, conn)? {
+        return Ok(None);
+    }
+    let task_rows: Vec<(String, String, NaiveDateTime)> = tasks::table
+        .filter(tasks::project_id.eq(project.to_string()))
+        .select((tasks::id, tasks::title, tasks::created_at))
+        .load(conn)?;
+    let ids: Vec<&String> = task_rows.iter().map(|(id, _, _)| id).collect();
+    let by_task = load_changes(&ids, conn)?;
+
+    let mut lead_times = Vec::new();
+    let mut cycle_times = Vec::new();
+    let mut completions = Vec::new();
+    let mut in_progress = Vec::new();
+    for (id, title, created_at) in &task_rows {
+        let changes = match by_task.get(id) {
+            Some(changes) => changes,
+            None => continue,
+        };
+        let (last_at, last_status) = match changes.last() {
+            Some(last) => last,
+            None => continue,
+        };
+        if last_status == task_views::STATUS_IN_PROGRESS {
+            in_progress.push((id, title, *last_at));
+            continue;
+        }
+        if last_status != task_views::STATUS_DONE || last_at.date() < from || last_at.date() > to {
+            continue;
+        }
+        let done_at = *last_at;
+        completions.push(done_at.date());
+        lead_times.push(hours_between(*created_at, done_at));
+        // Время цикла отсчитывается от первого перехода в работу
+        let started_at = changes
+            .iter()
+            .find(|(_, status)| status == task_views::STATUS_IN_PROGRESS)
+            .map(|(changed_at, _)| *changed_at);
+        if let Some(started_at) = started_at {
+            cycle_times.push(hours_between(started_at, done_at));
+        }
+    }
+
+    let lead_time = distribution(lead_times);
+    let cycle_time = distribution(cycle_times);
+
+    let mut throughput = Vec::new();
+    let mut week_start = week_of(from);
+    while week_start <= to {
+        let week_end = week_start + Duration::days(7);
+        let completed = completions
+            .iter()
+            .filter(|date| **date >= week_start && **date < week_end)
+            .count() as i64;
+        throughput.push(WeeklyThroughput{ week_start, completed });
+        week_start = week_end;
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let p85 = cycle_time.percentiles.get("p85").copied();
+    let mut aging_wip: Vec<AgingTask> = in_progress
+        .into_iter()
+        .map(|(id, title, started_at)| {
+            let age_hours = hours_between(started_at, now);
+            AgingTask{
+                task_id: id.clone(),
+                title: title.clone(),
+                started_at,
+                age_hours,
+                over_p85: p85.is_some_and(|p85| age_hours > p85),
+            }
+        })
+        .collect();
+    aging_wip.sort_by(|a, b| b.age_hours.total_cmp(&a.age_hours));
+
+    Ok(Some(FlowAnalytics{ from, to, lead_time, cycle_time, throughput, aging_wip }))
+}
+
+/// Метод, загружающий историю статусов задач.
+fn load_timelines(task_rows: Vec<(String, Option<i32>)>, unit: &str, conn: &PgConnection) -> Result<Vec<TaskTimeline>, DbError>{
+    let ids: Vec<&String> = task_rows.iter().map(|(id, _)| id).collect();
+    let mut by_task = load_changes(&ids, conn)?;
+
+    Ok(task_rows
+        .into_iter()
+        .map(|(id, points)| TaskTimeline{
+            changes: by_task.remove(&id).unwrap_or_default(),
+            weight: if unit == "tasks" { 1 } else { i64::from(points.unwrap_or(0)) },
+        })
+        .collect())
+}
+
+/// Метод, загружающий смены статусов задач в порядке времени, сгруппированные по задаче.
+fn load_changes(ids: &[&String], conn: &PgConnection) -> Result<HashMap<String, Vec<(NaiveDateTime, String)>>, DbError>{
+    let changes: Vec<(String, String, NaiveDateTime)> = task_status_changes::table
+        .filter(task_status_changes::task_id.eq_any(ids))
+        .order(task_status_changes::changed_at.asc())
+        .select((task_status_changes::task_id, task_status_changes::to_status, task_status_changes::changed_at))
+        .load(conn)?;
+    let mut by_task: HashMap<String, Vec<(NaiveDateTime, String)>> = HashMap::new();
+    for (task_id, status, changed_at) in changes {
+        by_task.entry(task_id).or_default().push((changed_at, status));
+    }
+    Ok(by_task)
+}
+
+/// Метод, строящий диаграмму сгорания по истории статусов задач.
+/// Идеальная линия идёт от объёма работ на первый день до нуля в последний день.
+fn burn_chart(timelines: &[TaskTimeline], unit: String, from: NaiveDate, to: NaiveDate) -> Result<BurnChart, DbError>{
+    check_period(from, to)?;
+    let mut days = Vec::new();
+    let mut date = from;
+    while date <= to {
+        let end_of_day = (date + Duration::days(1)).and_hms_opt(0, 0, 0).unwrap_or_default();
+        let mut total = 0;
+        let mut completed = 0;
+        for timeline in timelines {
+            match timeline.status_at(end_of_day) {
+                Some(task_views::STATUS_DONE) => {
+                    total += timeline.weight;
+                    completed += timeline.weight;
+                },
+                Some(_) => total += timeline.weight,
+                None => {},
+            }
+        }
+        days.push(BurnPoint{ date, total, completed, remaining: total - completed, ideal: 0.0 });
+        date += Duration::days(1);
+    }
+
+    let span = (days.len().saturating_sub(1)) as f64;
+    let start_total = days.first().map_or(0, |day| day.total) as f64;
+    for (index, day) in days.iter_mut().enumerate() {
+        day.ideal = if span > 0.0 {
+            start_total * (1.0 - index as f64 / span)
+        } else {
+            0.0
+        };
+    }
+    Ok(BurnChart{ unit, from, to, days })
+}
+
+/// Метод, вычисляющий распределение длительностей в часах.
+fn distribution(mut hours: Vec<f64>) -> TimeDistribution{
+    hours.sort_by(|a, b| a.total_cmp(b));
+    let count = hours.len();
+    let percentiles = if count == 0 {
+        BTreeMap::new()
+    } else {
+        PERCENTILES
+            .iter()
+            .map(|percentile| {
+                // Метод ближайшего ранга: наименьшее значение, не меньшее заданной доли выборки
+                let rank = (*percentile as f64 / 100.0 * count as f64).ceil() as usize;
+                (format!("p{}", percentile), hours[rank.clamp(1, count) - 1])
+            })
+            .collect()
+    };
+    let mut histogram: BTreeMap<i64, i64> = BTreeMap::new();
+    for value in &hours {
+        *histogram.entry((value / 24.0).floor() as i64).or_insert(0) += 1;
+    }
+
+    TimeDistribution{
+        count,
+        mean_hours: (count > 0).then(|| hours.iter().sum::<f64>() / count as f64),
+        min_hours: hours.first().copied(),
+        max_hours: hours.last().copied(),
+        percentiles,
+        histogram: histogram
+            .into_iter()
+            .map(|(days, count)| HistogramBucket{ days, count })
+            .collect(),
+    }
+}
+
+/// Метод, возвращающий количество часов между двумя моментами.
+fn hours_between(start: NaiveDateTime, end: NaiveDateTime) -> f64{
+    (end - start).num_seconds() as f64 / 3600.0
+}
+
+/// Метод, возвращающий понедельник недели, к которой относится дата.
+fn week_of(date: NaiveDate) -> NaiveDate{
+    date - Duration::days(i64::from(date.weekday().num_days_from_monday()))
+}
+
+/// Метод разбора единицы объёма работ.
+fn parse_unit(query: &ReportQuery) -> Result<String, ClientError>{
+    let unit = query.unit.clone().unwrap_or_else(|| UNITS[0].to_string());
+    if !UNITS.contains(&unit.as_str()) {
+        return Err(ClientError::BadRequest(format!(
+            "Unknown unit {}, expected one of {}", unit, UNITS.join(", ")
+        )));
+    }
+    Ok(unit)
+}
+
+/// Метод разбора периода отчёта. По умолчанию - последние 30 дней, включая сегодняшний.
+fn parse_period(query: &ReportQuery) -> Result<(NaiveDate, NaiveDate), ClientError>{
+    let to = query.to.unwrap_or_else(|| chrono::Utc::now().date_naive());
+    let from = query.from.unwrap_or(to - Duration::days(DEFAULT_REPORT_DAYS - 1));
+    check_period(from, to)?;
+    Ok((from, to))
+}
+
+/// Метод проверки границ периода отчёта.
+fn check_period(from: NaiveDate, to: NaiveDate) -> Result<(), ClientError>{
+    if to < from {
+        return Err(ClientError::BadRequest("Report period must not end before it starts".to_string()));
+    }
+    if (to - from).num_days() >= MAX_REPORT_DAYS {
+        return Err(ClientError::BadRequest(format!("Report period must not exceed {} days", MAX_REPORT_DAYS)));
+    }
+    Ok(())
+}
+
+/// Метод проверки существования проекта.
+fn project_exists(project: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(projects::table.filter(projects::id.eq(project.to_string()))))
+        .get_result::<bool>(conn)?;
+    Ok(exists)
+}
@@ -0,0 +1,255 @@
+use diesel::{prelude::*};
+use std::collections::{HashMap, VecDeque};
+
+use chrono::{Duration, NaiveDateTime};
+use crate::models::{InfeasibleTask, Schedule, ScheduleEntry, Task};
+use crate::schema::{projects, task_links, tasks};
+use super::{links::BLOCKS, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Метод, рассчитывающий расписание проекта методом критического пути.
+/// Длительность задачи равна её оценке в минутах, завершённые и неоценённые задачи имеют нулевую длительность.
+/// Учитываются только блокирующие связи между задачами проекта.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор проекта.
+/// * `start`       - момент начала расписания. По умолчанию - текущее время.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо расписание проекта.
+/// Если проект не найден, возвращается None.
+pub fn get_schedule(project: &Uuid, start: Option<NaiveDateTime>, conn: &PgConnection) -> Result<Option<Schedule>, DbError>{
+    let project_exists = diesel::select(diesel::dsl::exists(
+        projects::table.filter(projects::id.eq(project.to_string()))
+    )).get_result::<bool>(conn)?;
+    if !project_exists {
+        return Ok(None);
+    }
+    let start = start.unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+    let tasks_list = tasks::table
+        .filter(tasks::project_id.eq(project.to_string()))
+        .order(tasks::created_at.asc())
+        .load::<Task>(conn)?;
+    let ids: Vec<String> = tasks_list.iter().map(|task| task.id.clone()).collect();
+    let edges: Vec<(String, String)> = task_links::table
+        .filter(task_links::link_type.eq(BLOCKS))
+        .filter(task_links::source_id.eq_any(&ids))
+        .filter(task_links::target_id.eq_any(&ids))
+        .select((task_links::source_id, task_links::target_id))
+        .load(conn)?;
+
+    let index: HashMap<&str, usize> = ids.iter().enumerate().map(|(i, task_id)| (task_id.as_str(), i)).collect();
+    let count = tasks_list.len();
+    let mut predecessors: Vec<Vec<usize>> = vec![Vec::new(); count];
+    let mut successors: Vec<Vec<usize>> = vec![Vec::new(); count];
+    for (source, target) in &edges {
+        let (source, target) = (index[source.as_str()], index[target.as_str()]);
+        predecessors[target].push(source);
+        successors[source].push(target);
+    }
+    let durations: Vec<i64> = tasks_list
+        .iter()
+        .map(|task| if task.done { 0 } else { i64::from(task.estimate_minutes.unwrap_or(0)) })
+        .collect();
+
+    let Timing{ order, earliest_start, earliest_finish, latest_start, latest_finish, finish: project_finish } =
+        timing(&durations, &predecessors, &successors)?;
+
+    let critical_path = critical_path(&predecessors, &earliest_start, &earliest_finish, &latest_start)
+        .into_iter()
+        .map(|task| ids[task].clone())
+        .collect();
+
+    let at = |offset: i64| start + Duration::minutes(offset);
+    let mut entries = Vec::with_capacity(count);
+    let mut infeasible = Vec::new();
+    for &task in &order {
+        let source = &tasks_list[task];
+        let slack = latest_start[task] - earliest_start[task];
+        if let Some(due) = source.due_at {
+            if !source.done && due < at(earliest_finish[task]) {
+                infeasible.push(InfeasibleTask{
+                    task_id: source.id.clone(),
+                    due_at: due,
+                    earliest_finish: at(earliest_finish[task]),
+                    late_by_minutes: (at(earliest_finish[task]) - due).num_minutes(),
+                });
+            }
+        }
+        entries.push(ScheduleEntry{
+            task_id: source.id.clone(),
+            title: source.title.clone(),
+            done: source.done,
+            duration_minutes: durations[task],
+            earliest_start: at(earliest_start[task]),
+            earliest_finish: at(earliest_finish[task]),
+            latest_start: at(latest_start[task]),
+            latest_finish: at(latest_finish[task]),
+            slack_minutes: slack,
+            critical: slack == 0 && durations[task] > 0,
+            due_at: source.due_at,
+            dependencies: predecessors[task].iter().map(|&pred| ids[pred].clone()).collect(),
+        });
+    }
+
+    Ok(Some(Schedule{
+        project_id: project.to_string(),
+        start,
+        finish: at(project_finish),
+        tasks: entries,
+        critical_path,
+        infeasible,
+    }))
+}
+
+/// Ранние и поздние сроки начала и окончания задач в минутах от начала расписания.
+struct Timing{
+    order: Vec<usize>,
+    earliest_start: Vec<i64>,
+    earliest_finish: Vec<i64>,
+    latest_start: Vec<i64>,
+    latest_finish: Vec<i64>,
+    finish: i64,
+}
+
+/// Метод, вычисляющий сроки задач методом критического пути.
+fn timing(durations: &[i64], predecessors: &[Vec<usize>], successors: &[Vec<usize>]) -> Result<Timing, DbError>{
+    let count = durations.len();
+    let order = topological_order(predecessors, successors)?;
+
+    // Прямой проход: ранние сроки начала и окончания
+    let mut earliest_start = vec![0i64; count];
+    let mut earliest_finish = vec![0i64; count];
+    for &task in &order {
+        earliest_start[task] = predecessors[task].iter().map(|&pred| earliest_finish[pred]).max().unwrap_or(0);
+        earliest_finish[task] = earliest_start[task] + durations[task];
+    }
+    let finish = earliest_finish.iter().copied().max().unwrap_or(0);
+
+    // Обратный проход: поздние сроки начала и окончания
+    let mut latest_finish = vec![finish; count];
+    let mut latest_start = vec![finish; count];
+    for &task in order.iter().rev() {
+        latest_finish[task] = successors[task].iter().map(|&succ| latest_start[succ]).min().unwrap_or(finish);
+        latest_start[task] = latest_finish[task] - durations[task];
+    }
+
+    Ok(Timing{ order, earliest_start, earliest_finish, latest_start, latest_finish, finish })
+}
+
+/// Метод топологической сортировки задач по блокирующим связям (алгоритм Кана).
+fn topological_order(predecessors: &[Vec<usize>], successors: &[Vec<usize>]) -> Result<Vec<usize>, DbError>{
+    let mut in_degree: Vec<usize> = predecessors.iter().map(Vec::len).collect();
+    let mut queue: VecDeque<usize> = (0..in_degree.len()).filter(|&task| in_degree[task] == 0).collect();
+    let mut order = Vec::with_capacity(in_degree.len());
+    while let Some(task) = queue.pop_front() {
+        order.push(task);
+        for &succ in &successors[task] {
+            in_degree[succ] -= 1;
+            if in_degree[succ] == 0 {
+                queue.push_back(succ);
+            }
+        }
+    }
+    if order.len() != in_degree.len() {
+        return Err(Box::new(ClientError::Conflict("Blocking links of the project contain a cycle".to_string())));
+    }
+    Ok(order)
+}
+
+/// Метод, восстанавливающий критический путь от задачи с наибольшим ранним окончанием
+/// назад по предшественникам без резерва времени.
+fn critical_path(
+    predecessors: &[Vec<usize>],
+    earliest_start: &[i64],
+    earliest_finish: &[i64],
+    latest_start: &[i64]
+) -> Vec<usize>{
+    let last = (0..earliest_finish.len())
+        .filter(|&task| earliest_finish[task] > 0)
+        .max_by_key(|&task| earliest_finish[task]);
+    let mut path = Vec::new();
+    let mut current = last;
+    while let Some(task) = current {
+        path.push(task);
+        current = predecessors[task]
+            .iter()
+            .copied()
+            .find(|&pred| earliest_finish[pred] == earliest_start[task] && latest_start[pred] == earliest_start[pred]);
+    }
+    path.reverse();
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Метод, строящий списки предшественников и последователей по блокирующим связям.
+    fn graph(count: usize, edges: &[(usize, usize)]) -> (Vec<Vec<usize>>, Vec<Vec<usize>>){
+        let mut predecessors = vec![Vec::new(); count];
+        let mut successors = vec![Vec::new(); count];
+        for &(source, target) in edges {
+            predecessors[target].push(source);
+            successors[source].push(target);
+        }
+        (predecessors, successors)
+    }
+
+    #[test]
+    fn computes_earliest_and_latest_times(){
+        // 0 -> 1 -> 3 и 0 -> 2 -> 3, путь через 1 длиннее
+        let durations = [30, 60, 20, 10];
+        let (predecessors, successors) = graph(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let timing = timing(&durations, &predecessors, &successors).unwrap();
+
+        assert_eq!(timing.earliest_start, vec![0, 30, 30, 90]);
+        assert_eq!(timing.earliest_finish, vec![30, 90, 50, 100]);
+        assert_eq!(timing.latest_start, vec![0, 30, 70, 90]);
+        assert_eq!(timing.latest_finish, vec![30, 90, 90, 100]);
+        assert_eq!(timing.finish, 100);
+    }
+
+    #[test]
+    fn critical_path_follows_tasks_without_slack(){
+        let durations = [30, 60, 20, 10];
+        let (predecessors, successors) = graph(4, &[(0, 1), (0, 2), (1, 3), (2, 3)]);
+        let timing = timing(&durations, &predecessors, &successors).unwrap();
+        let path = critical_path(&predecessors, &timing.earliest_start, &timing.earliest_finish, &timing.latest_start);
+
+        assert_eq!(path, vec![0, 1, 3]);
+    }
+
+    #[test]
+    fn independent_tasks_get_slack_up_to_the_longest_one(){
+        let durations = [45, 15, 0];
+        let (predecessors, successors) = graph(3, &[]);
+        let timing = timing(&durations, &predecessors, &successors).unwrap();
+        let path = critical_path(&predecessors, &timing.earliest_start, &timing.earliest_finish, &timing.latest_start);
+
+        assert_eq!(timing.finish, 45);
+        assert_eq!(timing.latest_start, vec![0, 30, 45]);
+        assert_eq!(path, vec![0]);
+    }
+
+    #[test]
+    fn empty_project_has_no_critical_path(){
+        let timing = timing(&[], &[], &[]).unwrap();
+
+        assert_eq!(timing.finish, 0);
+        assert!(critical_path(&[], &[], &[], &[]).is_empty());
+    }
+
+    #[test]
+    fn rejects_blocking_cycles(){
+        let (predecessors, successors) = graph(3, &[(0, 1), (1, 2), (2, 0)]);
+        let err = timing(&[10, 10, 10], &predecessors, &successors).err().unwrap();
+
+        assert!(matches!(err.downcast_ref::<ClientError>(), Some(ClientError::Conflict(_))));
+    }
+}
@@ -0,0 +1,366 @@
+use diesel::{prelude::*};
+
+use crate::models::{
+    NewSprint, Sprint, SprintClose, SprintCloseResult, SprintTask, Task, TaskView, Velocity, VelocityEntry
+};
+use crate::schema::{projects, sprints, tasks};
+use super::{history, tasks as task_views, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Количество последних закрытых спринтов, по которым считается средняя скорость
+const VELOCITY_WINDOW: usize = 3;
+
+/// Метод, возвращающий спринты проекта в порядке начала
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор спринтов.
+/// Если проект не найден, возвращается None.
+pub fn get_sprints(project: &Uuid, conn: &PgConnection) -> Result<Option<Vec<Sprint>>, DbError>{
+    if !project_exists(&project.to_string(), conn)? {
+        return Ok(None);
+    }
+    let sprints_list = sprints::table
+        .filter(sprints::project_id.eq(project.to_string()))
+        .order((sprints::start_date.asc(), sprints::created_at.asc()))
+        .load::<Sprint>(conn)?;
+
+    Ok(Some(sprints_list))
+}
+
+/// Метод, возвращающий спринт по идентификатору
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект спринта.
+pub fn get_sprint(uuid: &Uuid, conn: &PgConnection) -> Result<Option<Sprint>, DbError>{
+    let sprint = sprints::table
+        .filter(sprints::id.eq(uuid.to_string()))
+        .first::<Sprint>(conn)
+        .optional()?;
+
+    Ok(sprint)
+}
+
+/// Метод, создающий спринт проекта
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `project`         - уникальный идентификатор объекта проекта.
+/// * `new_sprint`      - указатель на десериализованный объект структуры NewSprint.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект спринта.
+/// Если проект не найден, возвращается None.
+pub fn create_sprint(project: &Uuid, new_sprint: &NewSprint, conn: &PgConnection) -> Result<Option<Sprint>, DbError>{
+    validate_sprint(new_sprint)?;
+    if !project_exists(&project.to_string(), conn)? {
+        return Ok(None);
+    }
+    let new = Sprint{
+        id: Uuid::new_v4().to_string(),
+        project_id: project.to_string(),
+        name: new_sprint.name.trim().to_string(),
+        start_date: new_sprint.start_date,
+        end_date: new_sprint.end_date,
+        goal: new_sprint.goal.clone(),
+        closed_at: None,
+        committed_points: None,
+        completed_points: None,
+        created_at: chrono::Utc::now().naive_utc(),
+        updated_at: None,
+    };
+    diesel::insert_into(sprints::table).values(&new).execute(conn)?;
+    Ok(Some(new))
+}
+
+/// Метод, изменяющий спринт по идентификатору
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `uuid`            - уникальный идентификатор объекта спринта.
+/// * `new_sprint`      - указатель на десериализованный объект структуры NewSprint.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект спринта.
+pub fn update_sprint(uuid: &Uuid, new_sprint: &NewSprint, conn: &PgConnection) -> Result<Option<Sprint>, DbError>{
+    validate_sprint(new_sprint)?;
+    let sprint = diesel::update(sprints::table.filter(sprints::id.eq(uuid.to_string())))
+        .set((
+            sprints::name.eq(new_sprint.name.trim()),
+            sprints::start_date.eq(new_sprint.start_date),
+            sprints::end_date.eq(new_sprint.end_date),
+            sprints::goal.eq(new_sprint.goal.clone()),
+            sprints::updated_at.eq(chrono::Utc::now().naive_utc())
+        ))
+        .get_result::<Sprint>(conn)
+        .optional()?;
+    Ok(sprint)
+}
+
+/// Метод, удаляющий спринт по идентификатору. Задачи спринта возвращаются в бэклог.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_sprint(uuid: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(sprints::table.filter(sprints::id.eq(uuid.to_string()))).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, возвращающий задачи спринта
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор задач спринта.
+/// Если спринт не найден, возвращается None.
+pub fn get_sprint_tasks(uuid: &Uuid, conn: &PgConnection) -> Result<Option<Vec<TaskView>>, DbError>{
+    if get_sprint(uuid, conn)?.is_none() {
+        return Ok(None);
+    }
+    let tasks_list = tasks::table
+        .filter(tasks::sprint_id.eq(uuid.to_string()))
+        .order(tasks::created_at.asc())
+        .load::<Task>(conn)?;
+
+    Ok(Some(task_views::to_views(tasks_list, conn)?))
+}
+
+/// Метод, добавляющий задачу в спринт. Задача должна принадлежать проекту спринта,
+/// закрытый спринт изменять нельзя.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта спринта.
+/// * `sprint_task` - указатель на десериализованный объект структуры SprintTask.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект задачи.
+/// Если спринт не найден, возвращается None.
+pub fn add_sprint_task(uuid: &Uuid, sprint_task: &SprintTask, actor: Option<&str>, conn: &PgConnection) -> Result<Option<Task>, DbError>{
+    let sprint = match get_sprint(uuid, conn)? {
+        Some(sprint) => sprint,
+        None => return Ok(None),
+    };
+    check_open(&sprint)?;
+    conn.transaction::<_, DbError, _>(|| {
+        let previous = tasks::table
+            .filter(tasks::id.eq(&sprint_task.task_id))
+            .for_update()
+            .first::<Task>(conn)
+            .optional()?;
+        let previous = match previous {
+            None => return Err(Box::new(ClientError::BadRequest(format!("Task {} not found", sprint_task.task_id)))),
+            Some(previous) if previous.project_id.as_deref() != Some(sprint.project_id.as_str()) => {
+                return Err(Box::new(ClientError::BadRequest(format!(
+                    "Task {} does not belong to project {}", sprint_task.task_id, sprint.project_id
+                ))));
+            },
+            Some(previous) => previous,
+        };
+
+        let task: Task = diesel::update(tasks::table.filter(tasks::id.eq(&sprint_task.task_id)))
+            .set(tasks::sprint_id.eq(&sprint.id))
+            .get_result(conn)?;
+        task_views::on_task_changed(history::ACTION_UPDATE, Some(&previous), Some(&task), actor, conn)?;
+        Ok(Some(task))
+    })
+}
+
+/// Метод, возвращающий задачу из спринта в бэклог
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта спринта.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn remove_sprint_task(uuid: &Uuid, task: &Uuid, actor: Option<&str>, conn: &PgConnection) -> Result<bool, DbError>{
+    if let Some(sprint) = get_sprint(uuid, conn)? {
+        check_open(&sprint)?;
+    }
+    conn.transaction::<_, DbError, _>(|| {
+        let in_sprint = tasks::table
+            .filter(tasks::id.eq(task.to_string()))
+            .filter(tasks::sprint_id.eq(uuid.to_string()));
+        let before = in_sprint.clone().for_update().load::<Task>(conn)?;
+        let updated = diesel::update(in_sprint)
+            .set(tasks::sprint_id.eq(None::<String>))
+            .execute(conn)?;
+        task_views::on_tasks_changed(&before, actor, conn)?;
+        Ok(updated > 0)
+    })
+}
+
+/// Метод, закрывающий спринт. Сохраняет запланированные и выполненные story points,
+/// незавершённые задачи переносятся в другой спринт проекта или в бэклог.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта спринта.
+/// * `close`       - указатель на десериализованный объект структуры SprintClose.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо закрытый спринт и перенесённые задачи.
+/// Если спринт не найден, возвращается None.
+pub fn close_sprint(uuid: &Uuid, close: &SprintClose, actor: Option<&str>, conn: &PgConnection) -> Result<Option<SprintCloseResult>, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let sprint = match sprints::table
+            .filter(sprints::id.eq(uuid.to_string()))
+            .for_update()
+            .first::<Sprint>(conn)
+            .optional()? {
+            Some(sprint) => sprint,
+            None => return Ok(None),
+        };
+        check_open(&sprint)?;
+        if let Some(target) = &close.carry_over_to {
+            let target_sprint = sprints::table
+                .filter(sprints::id.eq(target))
+                .first::<Sprint>(conn)
+                .optional()?;
+            match target_sprint {
+                Some(target_sprint) if target_sprint.id == sprint.id => {
+                    return Err(Box::new(ClientError::BadRequest("Cannot carry over into the same sprint".to_string())));
+                },
+                Some(target_sprint) if target_sprint.project_id != sprint.project_id => {
+                    return Err(Box::new(ClientError::BadRequest(format!(
+                        "Sprint {} belongs to another project", target
+                    ))));
+                },
+                Some(target_sprint) => check_open(&target_sprint)?,
+                None => return Err(Box::new(ClientError::BadRequest(format!("Sprint {} not found", target)))),
+            }
+        }
+
+        let sprint_tasks: Vec<(String, bool, Option<i32>)> = tasks::table
+            .filter(tasks::sprint_id.eq(&sprint.id))
+            .select((tasks::id, tasks::done, tasks::estimate_points))
+            .load(conn)?;
+        let committed: i32 = sprint_tasks.iter().map(|(_, _, points)| points.unwrap_or(0)).sum();
+        let completed: i32 = sprint_tasks
+            .iter()
+            .filter(|(_, done, _)| *done)
+            .map(|(_, _, points)| points.unwrap_or(0))
+            .sum();
+        let carried_over: Vec<String> = sprint_tasks
+            .into_iter()
+            .filter(|(_, done, _)| !done)
+            .map(|(id, _, _)| id)
+            .collect();
+
+        let before = tasks::table
+            .filter(tasks::id.eq_any(&carried_over))
+            .for_update()
+            .load::<Task>(conn)?;
+        diesel::update(tasks::table.filter(tasks::id.eq_any(&carried_over)))
+            .set(tasks::sprint_id.eq(close.carry_over_to.clone()))
+            .execute(conn)?;
+        task_views::on_tasks_changed(&before, actor, conn)?;
+        let now = chrono::Utc::now().naive_utc();
+        let sprint: Sprint = diesel::update(sprints::table.filter(sprints::id.eq(&sprint.id)))
+            .set((
+                sprints::closed_at.eq(now),
+                sprints::committed_points.eq(committed),
+                sprints::completed_points.eq(completed),
+                sprints::updated_at.eq(now)
+            ))
+            .get_result(conn)?;
+
+        Ok(Some(SprintCloseResult{ sprint, carried_over }))
+    })
+}
+
+/// Метод, возвращающий историю скорости проекта по закрытым спринтам
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо историю скорости.
+/// Если проект не найден, возвращается None.
+pub fn get_velocity(project: &Uuid, conn: &PgConnection) -> Result<Option<Velocity>, DbError>{
+    if !project_exists(&project.to_string(), conn)? {
+        return Ok(None);
+    }
+    let closed = sprints::table
+        .filter(sprints::project_id.eq(project.to_string()))
+        .filter(sprints::closed_at.is_not_null())
+        .order((sprints::start_date.asc(), sprints::closed_at.asc()))
+        .load::<Sprint>(conn)?;
+    let entries: Vec<VelocityEntry> = closed
+        .into_iter()
+        .map(|sprint| VelocityEntry{
+            sprint_id: sprint.id,
+            name: sprint.name,
+            start_date: sprint.start_date,
+            end_date: sprint.end_date,
+            committed_points: sprint.committed_points.unwrap_or(0),
+            completed_points: sprint.completed_points.unwrap_or(0),
+        })
+        .collect();
+    let recent = &entries[entries.len().saturating_sub(VELOCITY_WINDOW)..];
+    let average_points = if recent.is_empty() {
+        None
+    } else {
+        let sum: i32 = recent.iter().map(|entry| entry.completed_points).sum();
+        Some(sum as f64 / recent.len() as f64)
+    };
+
+    Ok(Some(Velocity{ sprints: entries, average_points }))
+}
+
+/// Метод проверки данных спринта.
+fn validate_sprint(new_sprint: &NewSprint) -> Result<(), ClientError>{
+    if new_sprint.name.trim().is_empty() {
+        return Err(ClientError::BadRequest("Sprint name must not be empty".to_string()));
+    }
+    if new_sprint.end_date < new_sprint.start_date {
+        return Err(ClientError::BadRequest("Sprint must not end before it starts".to_string()));
+    }
+    Ok(())
+}
+
+/// Метод проверки, что спринт ещё не закрыт.
+fn check_open(sprint: &Sprint) -> Result<(), ClientError>{
+    if sprint.closed_at.is_some() {
+        return Err(ClientError::Conflict(format!("Sprint {} is closed", sprint.id)));
+    }
+    Ok(())
+}
+
+/// Метод проверки существования проекта.
+fn project_exists(project: &str, conn: &PgConnection) -> Result<bool, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(projects::table.filter(projects::id.eq(project))))
+        .get_result::<bool>(conn)?;
+    Ok(exists)
+}
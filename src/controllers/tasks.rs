@@ -1,24 +1,100 @@
 
 use diesel::{prelude::*};
 
-use crate::models::{self, NewTask, Task};
+use crate::models::{self, MyTaskCounts, MyTasks, MyTasksQuery, NewTask, OnParentDelete, Task, TaskFilter, TaskNode, TaskStatusChange, TaskView};
+use crate::schema::{projects, task_assignees, task_labels, task_status_changes, task_watchers};
+use crate::storage::Storage;
+use super::{assignees, attachments, checklists, events, history, links, notifications, recurrence, users, watchers, ClientError};
+use chrono::TimeZone;
+use std::collections::{BTreeSet, HashMap};
 use uuid::Uuid;
 /// Тип ошибок, возникающих при работе с базой данных
 type DbError = Box<dyn std::error::Error + Send + Sync>;
 use crate::schema::tasks::dsl::*;
 
+/// Максимальная глубина вложенности задач. Задача верхнего уровня имеет глубину 1.
+const MAX_DEPTH: usize = 5;
+
+/// Статус новой задачи
+pub const STATUS_TODO: &str = "todo";
+/// Статус задачи, над которой ведётся работа
+pub const STATUS_IN_PROGRESS: &str = "in_progress";
+/// Статус завершённой задачи. Задача в этом статусе имеет `done = true`.
+pub const STATUS_DONE: &str = "done";
+/// Допустимые статусы задач в порядке прохождения
+pub const STATUSES: [&str; 3] = [STATUS_TODO, STATUS_IN_PROGRESS, STATUS_DONE];
+
+/// За сколько дней по умолчанию показываются недавно изменённые задачи, за которыми наблюдает пользователь
+const DEFAULT_WATCHING_DAYS: i64 = 7;
+/// Максимальный период для недавно изменённых задач в днях
+const MAX_WATCHING_DAYS: i64 = 90;
+/// Максимальное количество недавно изменённых задач, за которыми наблюдает пользователь
+const MAX_WATCHING_TASKS: i64 = 50;
+
 /// Метод, возвращающий вектор объектов задач
 /// # Arguments
 ///
 /// * `conn`        - указатель на подключение к базе данных.
+/// * `filter`      - параметры фильтрации списка задач.
+/// * `actor`       - идентификатор пользователя, выполняющего запрос, если он известен.
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор объектов задач.
-pub fn get_tasks(conn: &PgConnection) -> Result<Option<Vec<models::Task>>, DbError>{
-    let tasks_list = tasks.load(conn).optional()?;
+/// Если исполнитель задан как `me` без пользователя, возвращается ошибка 401.
+pub fn get_tasks(filter: &TaskFilter, actor: Option<&str>, conn: &PgConnection) -> Result<Option<Vec<TaskView>>, DbError>{
+    let mut query = tasks.into_boxed();
+    if let Some(project) = &filter.project_id {
+        query = query.filter(project_id.eq(project.clone()));
+    }
+    if filter.assignee.is_some() || filter.assignee_role.is_some() {
+        let mut assigned = task_assignees::table.select(task_assignees::task_id).into_boxed();
+        match filter.assignee.as_deref() {
+            Some("me") => {
+                let actor = actor.ok_or_else(|| ClientError::Unauthorized("Bearer token is required".to_string()))?;
+                assigned = assigned.filter(task_assignees::user_id.eq(actor.to_string()));
+            },
+            Some(assignee) => assigned = assigned.filter(task_assignees::user_id.eq(assignee.to_string())),
+            None => {},
+        }
+        if let Some(role) = &filter.assignee_role {
+            assignees::validate_role(role)?;
+            assigned = assigned.filter(task_assignees::role.eq(role.clone()));
+        }
+        query = query.filter(id.eq_any(assigned));
+    }
+    let label_ids: Vec<String> = filter.labels
+        .iter()
+        .flat_map(|label_list| label_list.split(','))
+        .map(|label| label.trim().to_string())
+        .filter(|label| !label.is_empty())
+        .collect::<BTreeSet<String>>()
+        .into_iter()
+        .collect();
+    // Пустой список меток не ограничивает выборку
+    if !label_ids.is_empty() {
+        let pairs: Vec<(String, String)> = task_labels::table
+            .filter(task_labels::label_id.eq_any(&label_ids))
+            .select((task_labels::task_id, task_labels::label_id))
+            .load(conn)?;
+        let mut matched: HashMap<String, usize> = HashMap::new();
+        for (task_id, _) in pairs {
+            *matched.entry(task_id).or_insert(0) += 1;
+        }
+        let match_all = filter.label_match.as_deref() == Some("all");
+        let task_ids: Vec<String> = matched
+            .into_iter()
+            .filter(|(_, count)| !match_all || *count == label_ids.len())
+            .map(|(task_id, _)| task_id)
+            .collect();
+        query = query.filter(id.eq_any(task_ids));
+    }
+    let tasks_list = match query.load::<Task>(conn).optional()? {
+        Some(tasks_list) => tasks_list,
+        None => return Ok(None),
+    };
 
-    Ok(tasks_list)
+    Ok(Some(to_views(tasks_list, conn)?))
 }
 
 /// Метод, возвращающий задачу по идентификатору
@@ -39,27 +115,219 @@ pub fn get_task(uuid: &Uuid, conn: &PgConnection) -> Result<Option<Task>, DbErro
     Ok(task)
 }
 
+/// Метод, возвращающий задачу по идентификатору вместе с вычисляемыми полями
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо представление задачи.
+pub fn get_task_view(uuid: &Uuid, conn: &PgConnection) -> Result<Option<TaskView>, DbError>{
+    let task = match get_task(uuid, conn)? {
+        Some(task) => task,
+        None => return Ok(None),
+    };
+
+    Ok(to_views(vec![task], conn)?.pop())
+}
+
+/// Метод, дополняющий задачи вычисляемыми полями
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `tasks_list`  - вектор объектов задач.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор представлений задач.
+pub fn to_views(tasks_list: Vec<Task>, conn: &PgConnection) -> Result<Vec<TaskView>, DbError>{
+    let ids: Vec<String> = tasks_list.iter().map(|task| task.id.clone()).collect();
+    let blocked = links::blocked_tasks(&ids, conn)?;
+    let checklist = checklists::checklist_progress(&ids, conn)?;
+    let subtasks = subtask_progress(&ids, conn)?;
+    let mut task_assignees = assignees::task_assignees_by_task(&ids, conn)?;
+
+    Ok(tasks_list
+        .into_iter()
+        .map(|task| {
+            let is_blocked = blocked.contains(&task.id);
+            let checklist_done = checklist.get(&task.id).copied();
+            let progress = subtasks.get(&task.id).copied();
+            let assigned = task_assignees.remove(&task.id).unwrap_or_default();
+            TaskView{ task, blocked: is_blocked, checklist: checklist_done, progress, assignees: assigned }
+        })
+        .collect())
+}
+
+/// Метод, возвращающий задачи пользователя: незавершённые задачи, в которых он является исполнителем,
+/// сгруппированные на просроченные, со сроком сегодня, с более поздним сроком и без срока,
+/// и недавно изменённые задачи, за которыми он наблюдает, не будучи исполнителем.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `user`        - идентификатор пользователя.
+/// * `query`       - часовой пояс пользователя и период для недавно изменённых задач.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сгруппированные задачи с их количеством.
+/// Если пользователь не найден, возвращается ошибка 401, если часовой пояс неизвестен - ошибка 400.
+pub fn get_my_tasks(user: &str, query: &MyTasksQuery, conn: &PgConnection) -> Result<MyTasks, DbError>{
+    users::authenticate(user, conn)?;
+    let timezone = match &query.timezone {
+        Some(timezone) => recurrence::parse_timezone(timezone)?,
+        None => chrono_tz::UTC,
+    };
+    let days = query.days.unwrap_or(DEFAULT_WATCHING_DAYS).clamp(1, MAX_WATCHING_DAYS);
+    let now = chrono::Utc::now().naive_utc();
+    let tomorrow = timezone.from_utc_datetime(&now).date_naive() + chrono::Duration::days(1);
+    let end_of_today = tomorrow
+        .and_hms_opt(0, 0, 0)
+        .and_then(|midnight| timezone.from_local_datetime(&midnight).earliest())
+        .map(|midnight| midnight.naive_utc())
+        .unwrap_or(now + chrono::Duration::days(1));
+
+    let assigned = tasks
+        .inner_join(task_assignees::table)
+        .filter(task_assignees::user_id.eq(user.to_string()))
+        .filter(done.eq(false))
+        .order((due_at.asc(), created_at.asc()))
+        .select(crate::schema::tasks::all_columns)
+        .load::<Task>(conn)?;
+    let assigned_ids = task_assignees::table
+        .filter(task_assignees::user_id.eq(user.to_string()))
+        .select(task_assignees::task_id);
+    let watched_ids = task_watchers::table
+        .filter(task_watchers::user_id.eq(user.to_string()))
+        .select(task_watchers::task_id);
+    let watched = tasks
+        .filter(id.eq_any(watched_ids))
+        .filter(diesel::dsl::not(id.eq_any(assigned_ids)))
+        .filter(updated_at.gt(now - chrono::Duration::days(days)))
+        .order(updated_at.desc())
+        .limit(MAX_WATCHING_TASKS)
+        .load::<Task>(conn)?;
+
+    // Вычисляемые поля загружаются для всех задач сразу
+    let assigned_count = assigned.len();
+    let mut views = to_views(assigned.into_iter().chain(watched).collect(), conn)?;
+    let watching = views.split_off(assigned_count);
+    let (mut overdue, mut due_today, mut upcoming, mut no_date) = (Vec::new(), Vec::new(), Vec::new(), Vec::new());
+    for view in views {
+        match view.task.due_at {
+            None => no_date.push(view),
+            Some(due) if due < now => overdue.push(view),
+            Some(due) if due < end_of_today => due_today.push(view),
+            Some(_) => upcoming.push(view),
+        }
+    }
+    let counts = MyTaskCounts{
+        overdue: overdue.len(),
+        due_today: due_today.len(),
+        upcoming: upcoming.len(),
+        no_date: no_date.len(),
+        watching: watching.len(),
+    };
+    Ok(MyTasks{ overdue, due_today, upcoming, no_date, watching, counts })
+}
+
 /// Метод, создающий задачу
 /// # Arguments
 ///
 /// * `conn`             - указатель на подключение к базе данных.
 /// * `new_task`         - указатель на десериализованный объект структуры NewTask.
+/// * `actor`            - идентификатор пользователя, выполняющего действие, если он известен.
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо объект задачи.
-pub fn create_task(new_task: &NewTask, conn: &PgConnection) -> Result<models::Task, DbError>{
+/// Если проект или родительская задача не найдены, возвращается ошибка 400.
+pub fn create_task(new_task: &NewTask, actor: Option<&str>, conn: &PgConnection) -> Result<models::Task, DbError>{
+    let new_project = new_task.project_id.clone().flatten();
+    let new_parent = new_task.parent_id.clone().flatten();
+    let new_estimate_minutes = new_task.estimate_minutes.flatten();
+    let new_estimate_points = new_task.estimate_points.flatten();
+    validate_estimate(new_estimate_minutes, new_estimate_points)?;
+    validate_project(new_project.as_deref(), conn)?;
+    validate_parent(None, new_parent.as_deref(), conn)?;
+    let new_status = match &new_task.status {
+        Some(new_status) => validate_status(new_status, new_task.done)?,
+        None => STATUS_TODO.to_string(),
+    };
     let new = Task{
         id: Uuid::new_v4().to_string(),
         title: new_task.title.clone(),
         body: new_task.body.clone(),
-        done: false,
+        done: new_status == STATUS_DONE,
         user_id: new_task.user_id.clone(),
         created_at: chrono::Utc::now().naive_utc(),
-        updated_at: None
+        updated_at: None,
+        project_id: new_project,
+        parent_id: new_parent,
+        due_at: new_task.due_at.flatten(),
+        estimate_minutes: new_estimate_minutes,
+        series_id: None,
+        estimate_points: new_estimate_points,
+        sprint_id: None,
+        status: new_status
     };
-    diesel::insert_into(tasks).values(&new).execute(conn)?;
-    Ok(new)
+    conn.transaction::<_, DbError, _>(|| {
+        diesel::insert_into(tasks).values(&new).execute(conn)?;
+        record_status_change(&new.id, None, &new.status, conn)?;
+        on_task_changed(history::ACTION_CREATE, None, Some(&new), actor, conn)?;
+        Ok(new)
+    })
+}
+
+/// Метод, вызываемый при каждом изменении задачи в транзакции, выполнившей изменение.
+/// Записывает изменение в историю, публикует событие о нём, согласует основного исполнителя
+/// со списком исполнителей, обновляет наблюдателей и создаёт уведомления заинтересованным пользователям.
+/// Изменение без отличающихся полей пропускается.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `action`      - действие: `create`, `update`, `delete` или `revert`.
+/// * `before`      - состояние задачи до изменения. None для новой задачи.
+/// * `after`       - состояние задачи после изменения. None для удалённой задачи.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если изменение не удалось записать.
+pub fn on_task_changed(action: &str, before: Option<&Task>, after: Option<&Task>, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let changes = history::changes(before, after)?;
+    if changes.is_empty() {
+        return Ok(());
+    }
+    history::record_revision(action, before, after, &changes, actor, conn)?;
+    events::record_task_event(action, before, after, &changes, actor, conn)?;
+    assignees::sync_primary(before, after, conn)?;
+    watchers::watch_task_change(action, after, actor, conn)?;
+    notifications::notify_task_change(action, before, after, &changes, actor, conn)?;
+    Ok(())
+}
+
+/// Метод, обрабатывающий изменения задач, выполненные одним запросом.
+/// Текущее состояние задач перечитывается и сравнивается с переданным.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `before`      - состояния задач до изменения.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если изменения не удалось записать.
+pub fn on_tasks_changed(before: &[Task], actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let ids: Vec<&String> = before.iter().map(|task| &task.id).collect();
+    let after = tasks.filter(id.eq_any(ids)).load::<Task>(conn)?;
+    for task in &after {
+        let previous = before.iter().find(|previous| previous.id == task.id);
+        on_task_changed(history::ACTION_UPDATE, previous, Some(task), actor, conn)?;
+    }
+    Ok(())
 }
 
 /// Метод, удаляющий задачу по идентификатору
@@ -67,40 +335,400 @@ pub fn create_task(new_task: &NewTask, conn: &PgConnection) -> Result<models::Ta
 ///
 /// * `conn`        - указатель на подключение к базе данных.
 /// * `uuid`        - уникальный идентификатор объекта задачи.
+/// * `on_delete`   - поведение по отношению к подзадачам удаляемой задачи.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+/// * `storage`     - хранилище содержимого файлов. Содержимое вложений удалённых задач удаляется после фиксации транзакции.
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
-pub fn delete_task(uuid: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
-    let old_count = tasks.count().first::<i64>(conn)?;
-    diesel::delete(tasks.filter(id.eq(uuid.to_string())))
-        .execute(conn)?;
-    let new_count = tasks.count().first::<i64>(conn)?;
-    let mut result = false;
-    if old_count > new_count{
-        result = true;
+pub fn delete_task(uuid: &Uuid, on_delete: OnParentDelete, actor: Option<&str>, storage: &dyn Storage, conn: &PgConnection) -> Result<bool, DbError>{
+    let (deleted, storage_keys) = conn.transaction::<_, DbError, _>(|| {
+        let children_count = tasks
+            .filter(parent_id.eq(uuid.to_string()))
+            .count()
+            .get_result::<i64>(conn)?;
+        let mut ids = vec![uuid.to_string()];
+        if children_count > 0 {
+            match on_delete {
+                OnParentDelete::Block => {
+                    return Err(Box::new(ClientError::Conflict(format!(
+                        "Task {} has {} subtasks", uuid, children_count
+                    ))));
+                },
+                OnParentDelete::Orphan => {
+                    let children = tasks
+                        .filter(parent_id.eq(uuid.to_string()))
+                        .for_update()
+                        .load::<Task>(conn)?;
+                    diesel::update(tasks.filter(parent_id.eq(uuid.to_string())))
+                        .set(parent_id.eq(None::<String>))
+                        .execute(conn)?;
+                    on_tasks_changed(&children, actor, conn)?;
+                },
+                OnParentDelete::Cascade => {
+                    ids.extend(get_descendants(&uuid.to_string(), conn)?.into_iter().map(|task| task.id));
+                },
+            }
+        }
+        let removed = tasks.filter(id.eq_any(&ids)).for_update().load::<Task>(conn)?;
+        // Удаление записывается до удаления задачи, пока её наблюдатели ещё известны
+        for task in &removed {
+            on_task_changed(history::ACTION_DELETE, Some(task), None, actor, conn)?;
+        }
+        let storage_keys = attachments::get_storage_keys(&ids, conn)?;
+        let deleted = diesel::delete(tasks.filter(id.eq_any(ids))).execute(conn)?;
+        Ok((deleted > 0, storage_keys))
+    })?;
+    for key in storage_keys {
+        storage.delete(&key)?;
     }
-    Ok(result)
+    Ok(deleted)
 }
 
-/// Метод, изменяющий задачу по идентификатору
+/// Метод, изменяющий задачу по идентификатору.
+/// Непереданные поля проекта, родителя, срока и оценок не меняются.
+/// Если повторяющаяся задача завершается, создаётся её следующее повторение.
 /// # Arguments
 ///
 /// * `conn`            - указатель на подключение к базе данных.
 /// * `uuid`            - уникальный идентификатор объекта задачи.
 /// * `new_task`        - указатель на десериализованный объект структуры NewTask.
+/// * `actor`           - идентификатор пользователя, выполняющего действие, если он известен.
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект задачи.
-pub fn update_task(uuid: &Uuid, new_task: &NewTask, conn: &PgConnection) -> Result<Task, DbError>{
-    let task: Task = diesel::update(tasks.filter(id.eq(uuid.to_string())))
-        .set((
-            title.eq(new_task.title.clone()),
-            body.eq(new_task.body.clone()),
-            done.eq(new_task.done),
-            user_id.eq(new_task.user_id.clone()),
-            updated_at.eq(super::get_date()) 
-        )).get_result(conn)?;
-    Ok(task)
-}
\ No newline at end of file
+/// Если задача не найдена, возвращается None.
+pub fn update_task(uuid: &Uuid, new_task: &NewTask, actor: Option<&str>, conn: &PgConnection) -> Result<Option<Task>, DbError>{
+    apply_update(uuid, new_task, history::ACTION_UPDATE, actor, conn)
+}
+
+/// Метод, изменяющий задачу и записывающий изменение в историю с указанным действием.
+/// Используется для обычного изменения задачи и для возврата к предыдущей версии.
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `uuid`            - уникальный идентификатор объекта задачи.
+/// * `new_task`        - указатель на десериализованный объект структуры NewTask.
+/// * `action`          - действие, записываемое в историю задачи.
+/// * `actor`           - идентификатор пользователя, выполняющего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект задачи.
+/// Если задача не найдена, возвращается None.
+pub fn apply_update(uuid: &Uuid, new_task: &NewTask, action: &str, actor: Option<&str>, conn: &PgConnection) -> Result<Option<Task>, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let previous = tasks
+            .filter(id.eq(uuid.to_string()))
+            .for_update()
+            .first::<Task>(conn)
+            .optional()?;
+        let previous = match previous {
+            Some(previous) => previous,
+            None => return Ok(None),
+        };
+        let new_project = new_task.project_id.clone().unwrap_or_else(|| previous.project_id.clone());
+        let new_parent = new_task.parent_id.clone().unwrap_or_else(|| previous.parent_id.clone());
+        let new_estimate_minutes = new_task.estimate_minutes.unwrap_or(previous.estimate_minutes);
+        let new_estimate_points = new_task.estimate_points.unwrap_or(previous.estimate_points);
+        validate_estimate(new_estimate_minutes, new_estimate_points)?;
+        if new_project != previous.project_id {
+            validate_project(new_project.as_deref(), conn)?;
+        }
+        if new_parent != previous.parent_id {
+            validate_parent(Some(&previous.id), new_parent.as_deref(), conn)?;
+        }
+        // Проверка выполняется под блокировкой задачи, чтобы не разойтись с одновременным изменением
+        if new_task.done && links::blockers_prevent_done()
+            && links::blocked_tasks(std::slice::from_ref(&previous.id), conn)?.contains(&previous.id) {
+            return Err(Box::new(ClientError::Conflict(format!(
+                "Task {} cannot be completed while blocking tasks are open", uuid
+            ))));
+        }
+        let previous_status = previous.status.clone();
+        let new_status = match &new_task.status {
+            Some(new_status) => validate_status(new_status, new_task.done)?,
+            None if new_task.done => STATUS_DONE.to_string(),
+            // Снятие отметки о выполнении возвращает задачу в начальный статус
+            None if previous_status == STATUS_DONE => STATUS_TODO.to_string(),
+            None => previous_status.clone(),
+        };
+        let task: Task = diesel::update(tasks.filter(id.eq(uuid.to_string())))
+            .set((
+                title.eq(new_task.title.clone()),
+                body.eq(new_task.body.clone()),
+                done.eq(new_task.done),
+                status.eq(&new_status),
+                user_id.eq(new_task.user_id.clone()),
+                project_id.eq(new_project),
+                parent_id.eq(new_parent),
+                due_at.eq(new_task.due_at.unwrap_or(previous.due_at)),
+                estimate_minutes.eq(new_estimate_minutes),
+                estimate_points.eq(new_estimate_points),
+                updated_at.eq(super::get_date()) 
+            )).get_result(conn)?;
+        if previous_status != task.status {
+            record_status_change(&task.id, Some(&previous_status), &task.status, conn)?;
+        }
+        on_task_changed(action, Some(&previous), Some(&task), actor, conn)?;
+        // Завершение повторяющейся задачи создаёт её следующее повторение
+        if !previous.done && task.done {
+            recurrence::create_next_occurrence(&task, actor, conn)?;
+        }
+        Ok(Some(task))
+    })
+}
+
+/// Метод, возвращающий непосредственные подзадачи задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор подзадач.
+/// Если задача не найдена, возвращается None.
+pub fn get_children(uuid: &Uuid, conn: &PgConnection) -> Result<Option<Vec<TaskView>>, DbError>{
+    if get_task(uuid, conn)?.is_none() {
+        return Ok(None);
+    }
+    let children = tasks
+        .filter(parent_id.eq(uuid.to_string()))
+        .order(created_at.asc())
+        .load::<Task>(conn)?;
+
+    Ok(Some(to_views(children, conn)?))
+}
+
+/// Метод, возвращающий дерево подзадач с процентом выполнения на каждом уровне
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `uuid`        - уникальный идентификатор корневой задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо дерево задач.
+pub fn get_task_tree(uuid: &Uuid, conn: &PgConnection) -> Result<Option<TaskNode>, DbError>{
+    let root = match get_task(uuid, conn)? {
+        Some(root) => root,
+        None => return Ok(None),
+    };
+    let mut by_parent: HashMap<String, Vec<Task>> = HashMap::new();
+    for task in get_descendants(&root.id, conn)? {
+        if let Some(parent) = task.parent_id.clone() {
+            by_parent.entry(parent).or_default().push(task);
+        }
+    }
+    let (node, _, _) = build_node(root, &mut by_parent);
+
+    Ok(Some(node))
+}
+
+/// Метод, рекурсивно собирающий узел дерева задач.
+/// Возвращает узел, количество выполненных потомков и общее количество потомков.
+fn build_node(task: Task, by_parent: &mut HashMap<String, Vec<Task>>) -> (TaskNode, usize, usize){
+    let mut children = by_parent.remove(&task.id).unwrap_or_default();
+    children.sort_by_key(|child| child.created_at);
+    let mut done_count = 0;
+    let mut total = 0;
+    let mut nodes = Vec::with_capacity(children.len());
+    for child in children {
+        let child_done = child.done;
+        let (node, sub_done, sub_total) = build_node(child, by_parent);
+        done_count += sub_done + usize::from(child_done);
+        total += sub_total + 1;
+        nodes.push(node);
+    }
+    let progress = if total > 0 {
+        Some(done_count as f64 * 100.0 / total as f64)
+    } else {
+        None
+    };
+    (TaskNode{ task, progress, children: nodes }, done_count, total)
+}
+
+/// Метод, возвращающий процент выполненных потомков для каждой задачи, у которой есть подзадачи.
+/// Считается так же, как `progress` в дереве задач: по всем потомкам, а не только непосредственным подзадачам.
+fn subtask_progress(ids: &[String], conn: &PgConnection) -> Result<HashMap<String, f64>, DbError>{
+    // Задачи, к прогрессу которых относится потомок. Задача из списка может быть потомком другой задачи из списка.
+    let mut roots: HashMap<String, Vec<String>> = ids.iter().map(|task| (task.clone(), vec![task.clone()])).collect();
+    let mut counts: HashMap<String, (usize, usize)> = HashMap::new();
+    let mut frontier: Vec<String> = ids.to_vec();
+    for _ in 0..MAX_DEPTH {
+        if frontier.is_empty() {
+            break;
+        }
+        let level = tasks
+            .filter(parent_id.eq_any(&frontier))
+            .select((id, parent_id, done))
+            .load::<(String, Option<String>, bool)>(conn)?;
+        let mut next_roots: HashMap<String, Vec<String>> = HashMap::new();
+        for (child, parent, child_done) in level {
+            let child_roots = parent.and_then(|parent| roots.get(&parent)).cloned().unwrap_or_default();
+            for root in &child_roots {
+                let (done_count, total) = counts.entry(root.clone()).or_default();
+                *done_count += usize::from(child_done);
+                *total += 1;
+            }
+            next_roots.entry(child).or_default().extend(child_roots);
+        }
+        frontier = next_roots.keys().cloned().collect();
+        roots = next_roots;
+    }
+    Ok(counts
+        .into_iter()
+        .map(|(task, (done_count, total))| (task, done_count as f64 * 100.0 / total as f64))
+        .collect())
+}
+
+/// Метод, возвращающий всех потомков задачи, обходя дерево по уровням.
+fn get_descendants(root: &str, conn: &PgConnection) -> Result<Vec<Task>, DbError>{
+    let mut result = Vec::new();
+    let mut frontier = vec![root.to_string()];
+    // Ограничение глубины обхода защищает от зацикливания на повреждённых данных
+    for _ in 0..MAX_DEPTH {
+        if frontier.is_empty() {
+            break;
+        }
+        let level = tasks
+            .filter(parent_id.eq_any(&frontier))
+            .load::<Task>(conn)?;
+        frontier = level.iter().map(|task| task.id.clone()).collect();
+        result.extend(level);
+    }
+    Ok(result)
+}
+
+/// Метод, записывающий смену статуса задачи в историю
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - идентификатор задачи.
+/// * `from`        - предыдущий статус. None для новой задачи.
+/// * `to`          - новый статус.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если запись не удалось сохранить.
+pub fn record_status_change(task: &str, from: Option<&str>, to: &str, conn: &PgConnection) -> Result<(), DbError>{
+    let change = TaskStatusChange{
+        id: Uuid::new_v4().to_string(),
+        task_id: task.to_string(),
+        from_status: from.map(|from| from.to_string()),
+        to_status: to.to_string(),
+        changed_at: chrono::Utc::now().naive_utc(),
+    };
+    diesel::insert_into(task_status_changes::table).values(&change).execute(conn)?;
+    Ok(())
+}
+
+/// Метод проверки статуса задачи. Статус `done` должен совпадать с флагом `done`.
+fn validate_status(new_status: &str, is_done: bool) -> Result<String, ClientError>{
+    if !STATUSES.contains(&new_status) {
+        return Err(ClientError::BadRequest(format!(
+            "Unknown status {}, expected one of {}", new_status, STATUSES.join(", ")
+        )));
+    }
+    if (new_status == STATUS_DONE) != is_done {
+        return Err(ClientError::BadRequest(format!(
+            "Status {} does not match done = {}", new_status, is_done
+        )));
+    }
+    Ok(new_status.to_string())
+}
+
+/// Метод проверки оценки задачи.
+fn validate_estimate(minutes: Option<i32>, points: Option<i32>) -> Result<(), ClientError>{
+    if minutes.is_some_and(|minutes| minutes < 0) || points.is_some_and(|points| points < 0) {
+        return Err(ClientError::BadRequest("Estimate must not be negative".to_string()));
+    }
+    Ok(())
+}
+
+/// Метод проверки проекта задачи. Несуществующий проект даёт ошибку 400, а не нарушение внешнего ключа.
+fn validate_project(project: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let project = match project {
+        Some(project) => project,
+        None => return Ok(()),
+    };
+    let exists = diesel::select(diesel::dsl::exists(projects::table.filter(projects::id.eq(project))))
+        .get_result::<bool>(conn)?;
+    if !exists {
+        return Err(Box::new(ClientError::BadRequest(format!("Project {} not found", project))));
+    }
+    Ok(())
+}
+
+/// Метод проверки нового родителя задачи.
+/// Запрещает несуществующих родителей, циклы и превышение максимальной глубины вложенности.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - идентификатор изменяемой задачи. None для новой задачи.
+/// * `parent`      - идентификатор нового родителя.
+fn validate_parent(task: Option<&str>, parent: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let parent = match parent {
+        Some(parent) => parent,
+        None => return Ok(()),
+    };
+    if task == Some(parent) {
+        return Err(Box::new(ClientError::BadRequest("Task cannot be its own parent".to_string())));
+    }
+
+    // Цепочка предков нового родителя, начиная с него самого
+    let mut ancestors: Vec<String> = Vec::new();
+    let mut current = Some(parent.to_string());
+    while let Some(ancestor) = current {
+        if task == Some(ancestor.as_str()) {
+            return Err(Box::new(ClientError::BadRequest(format!(
+                "Task {} cannot be moved under its own subtask", ancestor
+            ))));
+        }
+        if ancestors.len() >= MAX_DEPTH {
+            break;
+        }
+        let next = tasks
+            .filter(id.eq(&ancestor))
+            .select(parent_id)
+            .first::<Option<String>>(conn)
+            .optional()?;
+        match next {
+            Some(next) => current = next,
+            None => {
+                return Err(Box::new(ClientError::BadRequest(format!("Parent task {} not found", ancestor))));
+            },
+        }
+        ancestors.push(ancestor);
+    }
+
+    let subtree_height = match task {
+        Some(task) => subtree_height(task, conn)?,
+        None => 1,
+    };
+    if ancestors.len() + subtree_height > MAX_DEPTH {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "Task hierarchy cannot be deeper than {} levels", MAX_DEPTH
+        ))));
+    }
+    Ok(())
+}
+
+/// Метод, возвращающий высоту поддерева задачи (1 для задачи без подзадач).
+fn subtree_height(root: &str, conn: &PgConnection) -> Result<usize, DbError>{
+    let mut height = 1;
+    let mut frontier = vec![root.to_string()];
+    loop {
+        frontier = tasks
+            .filter(parent_id.eq_any(&frontier))
+            .select(id)
+            .load::<String>(conn)?;
+        if frontier.is_empty() || height > MAX_DEPTH {
+            return Ok(height);
+        }
+        height += 1;
+    }
+}
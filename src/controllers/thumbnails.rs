@@ -0,0 +1,238 @@
+use diesel::{prelude::*};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use std::collections::HashMap;
+use std::io::{Cursor, Read};
+use std::time::Duration;
+
+use image::{DynamicImage, ImageDecoder, ImageFormat, ImageReader};
+use sha2::{Digest, Sha256};
+
+use crate::database::DbPool;
+use crate::models::{Attachment, AttachmentThumbnail};
+use crate::schema::{attachment_thumbnails, attachments};
+use crate::storage::{SharedStorage, Storage};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Имена и максимальная сторона (в пикселях) генерируемых уменьшенных копий
+const THUMBNAIL_SIZES: [(&str, u32); 3] = [("small", 64), ("medium", 256), ("large", 1024)];
+
+/// Типы файлов, для которых генерируются уменьшенные копии
+const PREVIEW_TYPES: [&str; 4] = ["image/jpeg", "image/png", "image/gif", "image/webp"];
+
+/// Состояние генерации превью: ожидает обработки
+pub const PREVIEW_PENDING: &str = "pending";
+/// Состояние генерации превью: уменьшенные копии готовы
+const PREVIEW_READY: &str = "ready";
+/// Состояние генерации превью: файл не удалось обработать
+const PREVIEW_FAILED: &str = "failed";
+
+/// Интервал, с которым повторно обрабатываются вложения, оставшиеся в состоянии `pending`
+const SWEEP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// Сколько секунд вложение должно пробыть в состоянии `pending`, чтобы считаться брошенным.
+/// Более свежие вложения ещё могут обрабатываться после загрузки.
+const STALE_PENDING_SECONDS: i64 = 5 * 60;
+
+/// Метод, определяющий, генерируются ли уменьшенные копии для файла данного типа.
+pub fn supports_preview(content_type: &str) -> bool{
+    PREVIEW_TYPES.contains(&content_type)
+}
+
+/// Метод, повторно генерирующий уменьшенные копии вложений, оставшихся в состоянии `pending`
+/// после перезапуска или падения сервера во время фоновой обработки.
+/// # Arguments
+///
+/// * `pool`        - пул базы данных.
+/// * `storage`     - хранилище содержимого файлов.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество обработанных вложений.
+pub fn process_stale_pending(pool: &DbPool, storage: &dyn Storage) -> Result<usize, DbError>{
+    let stale_before = chrono::Utc::now().naive_utc() - chrono::Duration::seconds(STALE_PENDING_SECONDS);
+    let pending = attachments::table
+        .filter(attachments::preview_status.eq(PREVIEW_PENDING))
+        .filter(attachments::created_at.lt(stale_before))
+        .select(attachments::id)
+        .load::<String>(&pool.get()?)?;
+    for attachment in &pending {
+        if let Err(err) = generate_thumbnails(attachment, pool, storage) {
+            log::error!("Thumbnail generation for attachment {} failed: {}", attachment, err);
+        }
+    }
+    Ok(pending.len())
+}
+
+/// Метод инициализации повторной обработки брошенных вложений.
+/// Обработка выполняется при запуске и далее с интервалом SWEEP_INTERVAL в отдельном потоке.
+pub fn init_sweeper(pool: DbPool, storage: SharedStorage){
+    std::thread::Builder::new()
+        .name("thumbnail-sweeper".to_string())
+        .spawn(move || loop {
+            match process_stale_pending(&pool, storage.as_ref()) {
+                Ok(0) => {},
+                Ok(processed) => log::info!("Processed {} attachments left pending", processed),
+                Err(err) => log::error!("Failed to process pending attachments: {}", err),
+            }
+            std::thread::sleep(SWEEP_INTERVAL);
+        })
+        .expect("Failed to start thumbnail sweeper");
+}
+
+/// Метод, генерирующий уменьшенные копии изображения-вложения и сохраняющий их в хранилище.
+/// Вызывается в фоне после загрузки файла. Ориентация из EXIF применяется к изображению,
+/// сами метаданные в уменьшенные копии не попадают.
+/// # Arguments
+///
+/// * `attachment`  - идентификатор вложения.
+/// * `pool`        - пул базы данных.
+/// * `storage`     - хранилище содержимого файлов.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если не удалось обновить состояние вложения.
+pub fn generate_thumbnails(attachment: &str, pool: &DbPool, storage: &dyn Storage) -> Result<(), DbError>{
+    let conn = pool.get()?;
+    let attachment = match attachments::table
+        .filter(attachments::id.eq(attachment))
+        .first::<Attachment>(&conn)
+        .optional()? {
+        Some(attachment) => attachment,
+        None => return Ok(()),
+    };
+
+    let status = match render_thumbnails(&attachment, storage) {
+        Ok(thumbnails) => {
+            let inserted = diesel::insert_into(attachment_thumbnails::table)
+                .values(&thumbnails)
+                .execute(&conn);
+            if let Err(err) = inserted {
+                // Копии уже сохранены параллельной обработкой по тем же ключам с тем же содержимым
+                if let DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _) = err {
+                    return Ok(());
+                }
+                // Вложение могло быть удалено во время обработки
+                for thumbnail in &thumbnails {
+                    storage.delete(&thumbnail.storage_key)?;
+                }
+                return Err(err.into());
+            }
+            PREVIEW_READY
+        },
+        Err(err) => {
+            log::warn!("Failed to generate thumbnails for attachment {}: {}", attachment.id, err);
+            PREVIEW_FAILED
+        },
+    };
+    diesel::update(attachments::table.filter(attachments::id.eq(&attachment.id)))
+        .set(attachments::preview_status.eq(status))
+        .execute(&conn)?;
+    Ok(())
+}
+
+/// Метод, возвращающий уменьшенные копии для нескольких вложений, сгруппированные по вложению.
+pub fn get_thumbnails(attachment_ids: &[String], conn: &PgConnection) -> Result<HashMap<String, Vec<AttachmentThumbnail>>, DbError>{
+    let thumbnails = attachment_thumbnails::table
+        .filter(attachment_thumbnails::attachment_id.eq_any(attachment_ids))
+        .order(attachment_thumbnails::width.asc())
+        .load::<AttachmentThumbnail>(conn)?;
+    let mut grouped: HashMap<String, Vec<AttachmentThumbnail>> = HashMap::new();
+    for thumbnail in thumbnails {
+        grouped.entry(thumbnail.attachment_id.clone()).or_default().push(thumbnail);
+    }
+    Ok(grouped)
+}
+
+/// Метод, возвращающий уменьшенную копию вложения задачи по имени размера
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `attachment`  - уникальный идентификатор объекта вложения.
+/// * `size_name`   - имя размера: `small`, `medium` или `large`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо уменьшенную копию.
+pub fn get_thumbnail(task: &Uuid, attachment: &Uuid, size_name: &str, conn: &PgConnection) -> Result<Option<AttachmentThumbnail>, DbError>{
+    let thumbnail = attachment_thumbnails::table
+        .inner_join(attachments::table)
+        .filter(attachments::task_id.eq(task.to_string()))
+        .filter(attachment_thumbnails::attachment_id.eq(attachment.to_string()))
+        .filter(attachment_thumbnails::size_name.eq(size_name))
+        .select(attachment_thumbnails::all_columns)
+        .first::<AttachmentThumbnail>(conn)
+        .optional()?;
+
+    Ok(thumbnail)
+}
+
+/// Метод, декодирующий исходное изображение и сохраняющий его уменьшенные копии в хранилище.
+fn render_thumbnails(attachment: &Attachment, storage: &dyn Storage) -> Result<Vec<AttachmentThumbnail>, DbError>{
+    let mut original = Vec::new();
+    storage.open(&attachment.storage_key, None)?.read_to_end(&mut original)?;
+    let mut decoder = ImageReader::new(Cursor::new(original))
+        .with_guessed_format()?
+        .into_decoder()?;
+    let orientation = decoder.orientation()?;
+    let mut image = DynamicImage::from_decoder(decoder)?;
+    image.apply_orientation(orientation);
+
+    let (format, content_type, extension) = if image.color().has_alpha() {
+        (ImageFormat::Png, "image/png", "png")
+    } else {
+        (ImageFormat::Jpeg, "image/jpeg", "jpg")
+    };
+    let mut thumbnails = Vec::with_capacity(THUMBNAIL_SIZES.len());
+    for (size_name, max_side) in THUMBNAIL_SIZES {
+        let resized = if image.width() <= max_side && image.height() <= max_side {
+            image.clone()
+        } else {
+            image.thumbnail(max_side, max_side)
+        };
+        let resized = match format {
+            ImageFormat::Jpeg => DynamicImage::ImageRgb8(resized.to_rgb8()),
+            _ => resized,
+        };
+        let mut encoded = Cursor::new(Vec::new());
+        resized.write_to(&mut encoded, format)?;
+        let encoded = encoded.into_inner();
+
+        let thumbnail = AttachmentThumbnail{
+            id: Uuid::new_v4().to_string(),
+            attachment_id: attachment.id.clone(),
+            size_name: size_name.to_string(),
+            width: resized.width() as i32,
+            height: resized.height() as i32,
+            content_type: content_type.to_string(),
+            size: encoded.len() as i64,
+            storage_key: format!("{}.{}.{}", attachment.storage_key, size_name, extension),
+            created_at: chrono::Utc::now().naive_utc(),
+        };
+        thumbnails.push((thumbnail, encoded));
+    }
+
+    let mut stored: Vec<AttachmentThumbnail> = Vec::with_capacity(thumbnails.len());
+    for (thumbnail, encoded) in thumbnails {
+        let temp_path = std::env::temp_dir().join(format!("thumbnail-{}", thumbnail.id));
+        let result = std::fs::write(&temp_path, &encoded)
+            .map_err(DbError::from)
+            .and_then(|_| storage.put(
+                &thumbnail.storage_key,
+                &temp_path,
+                encoded.len() as u64,
+                &hex::encode(Sha256::digest(&encoded))
+            ));
+        let _ = std::fs::remove_file(&temp_path);
+        if let Err(err) = result {
+            for thumbnail in &stored {
+                let _ = storage.delete(&thumbnail.storage_key);
+            }
+            return Err(err);
+        }
+        stored.push(thumbnail);
+    }
+    Ok(stored)
+}
@@ -1,11 +1,20 @@
 use diesel::{prelude::*};
 
-use crate::models::{self, NewUser, User};
+use crate::auth::{self, RequestMeta};
+use crate::models::{self, Credentials, NewUser, Session, User};
+use super::{audit, events, ClientError};
+use serde_json::json;
+use sha2::{Digest, Sha256};
 use uuid::Uuid;
 /// Тип ошибок, возникающих при работе с базой данных
 type DbError = Box<dyn std::error::Error + Send + Sync>;
 use crate::schema::users::dsl::*;
 
+/// Роль администратора
+pub const ROLE_ADMIN: i32 = 1;
+/// Роль пользователя по умолчанию
+pub const ROLE_DEFAULT: i32 = 0;
+
 
 /// Метод, возвращающий вектор объектов пользователей
 /// # Arguments
@@ -39,27 +48,46 @@ pub fn get_user(uuid: &Uuid, conn: &PgConnection) -> Result<Option<User>, DbErro
     Ok(user)
 }
 
-/// Метод, создающий пользователя
+/// Метод, создающий пользователя.
+/// Роль, отличная от роли по умолчанию, назначается только администратором и записывается в журнал аудита,
+/// остальным пользователям назначается роль по умолчанию.
 /// # Arguments
 ///
 /// * `conn`             - указатель на подключение к базе данных.
 /// * `new_user`         - указатель на десериализованный объект структуры NewUser.
+/// * `actor`            - идентификатор пользователя, выполняющего действие, если он известен.
+/// * `meta`             - сведения о запросе для журнала аудита.
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо объект пользователя.
-pub fn create_user(new_user: &NewUser, conn: &PgConnection) -> Result<models::User, DbError>{
-    let new = User{
-        id: Uuid::new_v4().to_string(),
-        user_name: new_user.user_name.clone(),
-        password: new_user.password.clone(),
-        email: new_user.email.clone(),
-        role: new_user.role,
-        created_at: chrono::Utc::now().naive_utc(),
-        updated_at: None
-    };
-    diesel::insert_into(users).values(&new).execute(conn)?;
-    Ok(new)
+pub fn create_user(new_user: &NewUser, actor: Option<&str>, meta: &RequestMeta, conn: &PgConnection) -> Result<models::User, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let by_admin = match actor {
+            Some(actor) => require_admin(actor, conn).is_ok(),
+            None => false,
+        };
+        let new = User{
+            id: Uuid::new_v4().to_string(),
+            user_name: new_user.user_name.clone(),
+            password: new_user.password.clone(),
+            email: new_user.email.clone(),
+            role: if by_admin { new_user.role } else { ROLE_DEFAULT },
+            created_at: chrono::Utc::now().naive_utc(),
+            updated_at: None
+        };
+        diesel::insert_into(users).values(&new).execute(conn)?;
+        if new.role != ROLE_DEFAULT {
+            audit::record(models::AuditEvent{
+                target_type: Some("user".to_string()),
+                target_id: Some(new.id.clone()),
+                details: json!({ "from": null, "to": new.role }),
+                ..audit::event(audit::ACTION_ROLE_CHANGE, actor, meta)
+            }, conn)?;
+        }
+        events::record_user_event(events::USER_CREATED, &new, actor, conn)?;
+        Ok(new)
+    })
 }
 
 /// Метод, удаляющий пользователя по идентификатору
@@ -67,34 +95,160 @@ pub fn create_user(new_user: &NewUser, conn: &PgConnection) -> Result<models::Us
 ///
 /// * `conn`        - указатель на подключение к базе данных.
 /// * `uuid`        - уникальный идентификатор объекта пользователя.
+/// * `actor`       - идентификатор пользователя, выполняющего действие, если он известен.
+/// * `meta`        - сведения о запросе для журнала аудита.
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
-pub fn delete_user(uuid: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
-    let result = diesel::delete(users.filter(id.eq(uuid.to_string())))
-        .execute(conn).is_ok();
-    Ok(result)
+pub fn delete_user(uuid: &Uuid, actor: Option<&str>, meta: &RequestMeta, conn: &PgConnection) -> Result<bool, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        // Событие записывается до удаления, пока известны проекты, в задачах которых пользователь является исполнителем
+        let found = users.filter(id.eq(uuid.to_string())).for_update().first::<User>(conn).optional()?;
+        if let Some(found) = found {
+            events::record_user_event(events::USER_DELETED, &found, actor, conn)?;
+            let deleted = diesel::delete(users.filter(id.eq(&found.id))).get_result::<User>(conn)?;
+            audit::record(models::AuditEvent{
+                target_type: Some("user".to_string()),
+                target_id: Some(deleted.id.clone()),
+                details: json!({ "user_name": deleted.user_name, "email": deleted.email, "role": deleted.role }),
+                ..audit::event(audit::ACTION_USER_DELETE, actor, meta)
+            }, conn)?;
+            return Ok(true);
+        }
+        Ok(false)
+    })
 }
 
-/// Метод, изменяющий пользователя по идентификатору
+/// Метод, изменяющий пользователя по идентификатору.
+/// Пользователь может изменить свою учётную запись, кроме роли. Чужие учётные записи и роли изменяет только администратор.
 /// # Arguments
 ///
 /// * `conn`            - указатель на подключение к базе данных.
 /// * `uuid`            - уникальный идентификатор объекта пользователя.
 /// * `new_user`        - указатель на десериализованный объект структуры NewUser.
+/// * `actor`           - идентификатор пользователя, выполняющего действие.
+/// * `meta`            - сведения о запросе для журнала аудита.
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект пользователя.
-pub fn update_user(uuid: &Uuid, new_user: &NewUser, conn: &PgConnection) -> Result<User, DbError>{
-    let user: User = diesel::update(users.filter(id.eq(uuid.to_string())))
-        .set((
-            user_name.eq(new_user.user_name.clone()), 
-            password.eq(new_user.password.clone()),
-            email.eq(new_user.email.clone()),
-            role.eq(new_user.role),
-            updated_at.eq(super::get_date()) 
-        )).get_result(conn)?;
-    Ok(user)
-}
\ No newline at end of file
+/// Если пользователь не найден, возвращается None, если действие не разрешено - ошибка 403.
+pub fn update_user(uuid: &Uuid, new_user: &NewUser, actor: &str, meta: &RequestMeta, conn: &PgConnection) -> Result<Option<User>, DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let previous_role = users
+            .filter(id.eq(uuid.to_string()))
+            .select(role)
+            .for_update()
+            .first::<i32>(conn)
+            .optional()?;
+        let previous_role = match previous_role {
+            Some(previous_role) => previous_role,
+            None => return Ok(None),
+        };
+        if actor != uuid.to_string() || new_user.role != previous_role {
+            require_admin(actor, conn)?;
+        }
+        let user: User = diesel::update(users.filter(id.eq(uuid.to_string())))
+            .set((
+                user_name.eq(new_user.user_name.clone()), 
+                password.eq(new_user.password.clone()),
+                email.eq(new_user.email.clone()),
+                role.eq(new_user.role),
+                updated_at.eq(super::get_date()) 
+            )).get_result(conn)?;
+        if previous_role != user.role {
+            audit::record(models::AuditEvent{
+                target_type: Some("user".to_string()),
+                target_id: Some(user.id.clone()),
+                details: json!({ "from": previous_role, "to": user.role }),
+                ..audit::event(audit::ACTION_ROLE_CHANGE, Some(actor), meta)
+            }, conn)?;
+        }
+        events::record_user_event(events::USER_UPDATED, &user, Some(actor), conn)?;
+        Ok(Some(user))
+    })
+}
+
+/// Метод, проверяющий имя пользователя или адрес электронной почты и пароль и выдающий токен.
+/// Успешный вход и неудачная попытка записываются в журнал аудита.
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `credentials`     - указатель на десериализованный объект структуры Credentials.
+/// * `meta`            - сведения о запросе для журнала аудита.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо токен и объект пользователя.
+/// При неверных данных для входа возвращается ошибка 401.
+pub fn login(credentials: &Credentials, meta: &RequestMeta, conn: &PgConnection) -> Result<Session, DbError>{
+    let user = users
+        .filter(user_name.eq(&credentials.login).or(email.eq(&credentials.login)))
+        .load::<User>(conn)?
+        .into_iter()
+        .find(|user| passwords_match(&user.password, &credentials.password));
+    match user {
+        Some(user) => {
+            audit::record(models::AuditEvent{
+                target_type: Some("user".to_string()),
+                target_id: Some(user.id.clone()),
+                details: json!({ "login": credentials.login }),
+                ..audit::event(audit::ACTION_LOGIN, Some(&user.id), meta)
+            }, conn)?;
+            let (token, expires_at) = auth::issue_token(&user.id);
+            Ok(Session{ token, expires_at, user })
+        },
+        None => {
+            audit::record(models::AuditEvent{
+                details: json!({ "login": credentials.login }),
+                ..audit::event(audit::ACTION_LOGIN_FAILED, None, meta)
+            }, conn)?;
+            Err(Box::new(ClientError::Unauthorized("Invalid login or password".to_string())))
+        },
+    }
+}
+
+/// Метод сравнения паролей за постоянное время.
+/// Сравниваются хэши паролей, поэтому время сравнения не зависит и от их длины.
+fn passwords_match(stored: &str, given: &str) -> bool{
+    let (stored, given) = (Sha256::digest(stored.as_bytes()), Sha256::digest(given.as_bytes()));
+    stored.iter().zip(given.iter()).fold(0u8, |diff, (a, b)| diff | (a ^ b)) == 0
+}
+
+/// Метод, проверяющий, что пользователь является администратором.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `actor`       - идентификатор пользователя, выполняющего действие.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку 403, если пользователь не найден или не администратор.
+pub fn require_admin(actor: &str, conn: &PgConnection) -> Result<(), DbError>{
+    let actor_role = users
+        .filter(id.eq(actor))
+        .select(role)
+        .first::<i32>(conn)
+        .optional()?;
+    if actor_role != Some(ROLE_ADMIN) {
+        return Err(Box::new(ClientError::Forbidden("Administrator role required".to_string())));
+    }
+    Ok(())
+}
+/// Метод, проверяющий пользователя, который подключается к потоку событий
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `actor`       - идентификатор пользователя, переданный при подключении.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку 401, если пользователь не найден, либо объект пользователя.
+pub fn authenticate(actor: &str, conn: &PgConnection) -> Result<User, DbError>{
+    let user = users
+        .filter(id.eq(actor))
+        .first::<User>(conn)
+        .optional()?;
+    user.ok_or_else(|| Box::new(ClientError::Unauthorized(format!("Unknown user {}", actor))) as DbError)
+}
@@ -0,0 +1,149 @@
+use diesel::{prelude::*};
+
+use crate::models::{Task, TaskWatcher, WatcherView};
+use crate::schema::{task_watchers, tasks, users};
+use super::{history, users as accounts};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Метод, возвращающий наблюдателей задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор наблюдателей задачи.
+/// Если задача не найдена, возвращается None.
+pub fn get_watchers(task: &Uuid, conn: &PgConnection) -> Result<Option<Vec<WatcherView>>, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task.to_string()))))
+        .get_result::<bool>(conn)?;
+    if !exists {
+        return Ok(None);
+    }
+    let watchers = task_watchers::table
+        .inner_join(users::table)
+        .filter(task_watchers::task_id.eq(task.to_string()))
+        .select((task_watchers::user_id, users::user_name, task_watchers::created_at))
+        .order(task_watchers::created_at.asc())
+        .load::<WatcherView>(conn)?;
+    Ok(Some(watchers))
+}
+
+/// Метод, подписывающий пользователя на изменения задачи. Повторная подписка ничего не меняет.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `user`        - идентификатор пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор наблюдателей задачи.
+/// Если задача не найдена, возвращается None, если пользователь не найден - ошибка 401.
+pub fn watch(task: &Uuid, user: &str, conn: &PgConnection) -> Result<Option<Vec<WatcherView>>, DbError>{
+    accounts::authenticate(user, conn)?;
+    conn.transaction::<_, DbError, _>(|| {
+        let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task.to_string()))))
+            .get_result::<bool>(conn)?;
+        if !exists {
+            return Ok(None);
+        }
+        add_watchers(&task.to_string(), &[user], conn)?;
+        get_watchers(task, conn)
+    })
+}
+
+/// Метод, отписывающий пользователя от изменений задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `user`        - идентификатор пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+/// Флаг равен false, если пользователь не наблюдал за задачей.
+pub fn unwatch(task: &Uuid, user: &str, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(
+        task_watchers::table
+            .filter(task_watchers::task_id.eq(task.to_string()))
+            .filter(task_watchers::user_id.eq(user))
+    ).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, возвращающий идентификаторы наблюдателей задачи
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор идентификаторов пользователей.
+pub fn get_watcher_ids(task: &str, conn: &PgConnection) -> Result<Vec<String>, DbError>{
+    let watchers = task_watchers::table
+        .filter(task_watchers::task_id.eq(task))
+        .select(task_watchers::user_id)
+        .order(task_watchers::created_at.asc())
+        .load::<String>(conn)?;
+    Ok(watchers)
+}
+
+/// Метод, подписывающий на изменения задачи её автора и нового исполнителя.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `action`      - действие из истории задачи.
+/// * `after`       - состояние задачи после изменения. None для удалённой задачи.
+/// * `actor`       - идентификатор пользователя, выполнившего действие, если он известен.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если подписку не удалось сохранить.
+pub fn watch_task_change(action: &str, after: Option<&Task>, actor: Option<&str>, conn: &PgConnection) -> Result<(), DbError>{
+    let task = match after {
+        Some(task) => task,
+        None => return Ok(()),
+    };
+    let mut watchers: Vec<&str> = task.user_id.iter().map(String::as_str).collect();
+    if action == history::ACTION_CREATE {
+        watchers.extend(actor);
+    }
+    add_watchers(&task.id, &watchers, conn)
+}
+
+/// Метод, подписывающий пользователей на изменения задачи.
+/// Несуществующие пользователи пропускаются: действие могло быть выполнено от имени удалённого пользователя.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - идентификатор задачи.
+/// * `watchers`    - идентификаторы пользователей.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если подписку не удалось сохранить.
+pub fn add_watchers(task: &str, watchers: &[&str], conn: &PgConnection) -> Result<(), DbError>{
+    if watchers.is_empty() {
+        return Ok(());
+    }
+    let existing = users::table
+        .filter(users::id.eq_any(watchers))
+        .select(users::id)
+        .load::<String>(conn)?;
+    let now = chrono::Utc::now().naive_utc();
+    let rows: Vec<TaskWatcher> = existing
+        .into_iter()
+        .map(|user| TaskWatcher{ task_id: task.to_string(), user_id: user, created_at: now })
+        .collect();
+    diesel::insert_into(task_watchers::table)
+        .values(&rows)
+        .on_conflict_do_nothing()
+        .execute(conn)?;
+    Ok(())
+}
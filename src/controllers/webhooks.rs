@@ -0,0 +1,588 @@
+use diesel::{prelude::*};
+use chrono::Timelike;
+use diesel::pg::expression::dsl::any;
+use serde_json::json;
+use std::collections::HashMap;
+use std::net::IpAddr;
+
+use crate::models::{DeliveryQuery, Event, NewWebhook, Page, PageQuery, Webhook, WebhookDelivery, WebhookUpdate};
+use crate::schema::{projects, task_assignees, tasks, webhook_deliveries, webhooks};
+use super::{events, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Подписка на все типы событий
+pub const ALL_EVENTS: &str = "*";
+
+/// Отправка ожидает очередной попытки
+pub const STATUS_PENDING: &str = "pending";
+/// Отправка выполняется одним из экземпляров сервера
+pub const STATUS_SENDING: &str = "sending";
+/// Получатель подтвердил отправку ответом 2xx
+pub const STATUS_DELIVERED: &str = "delivered";
+/// Попытки отправки исчерпаны
+pub const STATUS_FAILED: &str = "failed";
+/// Допустимые состояния отправки
+pub const STATUSES: [&str; 4] = [STATUS_PENDING, STATUS_SENDING, STATUS_DELIVERED, STATUS_FAILED];
+
+/// Количество попыток, после которого отправка больше не повторяется
+const MAX_ATTEMPTS: i32 = 10;
+
+/// Задержка перед первой повторной попыткой в секундах, далее удваивается
+const MIN_RETRY_DELAY: i64 = 15;
+
+/// Максимальная задержка перед повторной попыткой в секундах
+const MAX_RETRY_DELAY: i64 = 60 * 60;
+
+/// Время в секундах, на которое отправка закрепляется за экземпляром сервера.
+/// Если результат попытки не записан за это время, например из-за остановки сервера, отправка выполняется повторно.
+const SEND_LEASE: i64 = 5 * 60;
+
+/// Ошибка попытки отправки по отключённой подписке
+const DISABLED_ERROR: &str = "Webhook is disabled";
+
+/// Максимальная длина сохраняемого ответа получателя в символах
+const MAX_RESPONSE_BODY: usize = 1024;
+
+/// Результат попытки отправки: код ответа получателя, если он ответил, его тело и ошибка.
+#[derive(Debug, Default)]
+pub struct AttemptResult{
+    pub status: Option<i32>,
+    pub body: Option<String>,
+    pub error: Option<String>,
+}
+
+/// Метод проверки адреса, ключа и типов событий подписки.
+/// Адреса внутренней сети отклоняются, если они не разрешены переменной окружения WEBHOOKS_ALLOW_PRIVATE_HOSTS.
+fn validate(url: &str, secret: &str, event_types: &[String]) -> Result<(), ClientError>{
+    let parsed = url::Url::parse(url)
+        .ok()
+        .filter(|parsed| matches!(parsed.scheme(), "http" | "https"));
+    let host = match parsed.as_ref().and_then(|parsed| parsed.host()) {
+        Some(host) => host,
+        None => return Err(ClientError::BadRequest(format!("Webhook URL {} must be an absolute http(s) URL", url))),
+    };
+    let internal = match host {
+        url::Host::Domain(domain) => {
+            let domain = domain.trim_end_matches('.').to_ascii_lowercase();
+            domain == "localhost" || domain.ends_with(".localhost")
+        },
+        url::Host::Ipv4(ip) => !is_public_address(&IpAddr::V4(ip)),
+        url::Host::Ipv6(ip) => !is_public_address(&IpAddr::V6(ip)),
+    };
+    if internal && !private_hosts_allowed() {
+        return Err(ClientError::BadRequest(format!("Webhook URL {} must not point to a private or local address", url)));
+    }
+    if secret.is_empty() {
+        return Err(ClientError::BadRequest("Webhook secret must not be empty".to_string()));
+    }
+    if event_types.is_empty() {
+        return Err(ClientError::BadRequest("At least one event type is required".to_string()));
+    }
+    if let Some(unknown) = event_types.iter().find(|kind| *kind != ALL_EVENTS && !events::EVENT_TYPES.contains(&kind.as_str())) {
+        return Err(ClientError::BadRequest(format!(
+            "Unknown event type {}, expected {} or one of {}", unknown, ALL_EVENTS, events::EVENT_TYPES.join(", ")
+        )));
+    }
+    Ok(())
+}
+
+/// Метод, проверяющий, разрешена ли отправка событий на адреса внутренней сети.
+/// Задаётся переменной окружения WEBHOOKS_ALLOW_PRIVATE_HOSTS, например для проверки подписок на локальной машине.
+pub fn private_hosts_allowed() -> bool{
+    std::env::var("WEBHOOKS_ALLOW_PRIVATE_HOSTS")
+        .map(|value| matches!(value.trim().to_ascii_lowercase().as_str(), "1" | "true" | "yes"))
+        .unwrap_or(false)
+}
+
+/// Метод, проверяющий, что адрес доступен из интернета: не является локальным, частным,
+/// адресом самой машины, служебным или групповым адресом.
+pub fn is_public_address(ip: &IpAddr) -> bool{
+    match ip {
+        IpAddr::V4(ip) => {
+            let [first, second, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || first == 0
+                // Общий адрес провайдера 100.64.0.0/10
+                || (first == 100 && (64..128).contains(&second))
+                // Адреса для тестирования производительности 198.18.0.0/15
+                || (first == 198 && (18..20).contains(&second))
+                // Зарезервированные адреса 240.0.0.0/4
+                || first >= 240)
+        },
+        IpAddr::V6(ip) => {
+            if let Some(mapped) = ip.to_ipv4_mapped() {
+                return is_public_address(&IpAddr::V4(mapped));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                // Уникальные локальные адреса fc00::/7
+                || (first & 0xfe00) == 0xfc00
+                // Локальные адреса канала fe80::/10
+                || (first & 0xffc0) == 0xfe80
+                // Адреса для документации 2001:db8::/32
+                || (first == 0x2001 && ip.segments()[1] == 0x0db8))
+        },
+    }
+}
+
+/// Метод, возвращающий подписки проекта
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор подписок.
+/// Если проект не найден, возвращается None.
+pub fn get_webhooks(project: &Uuid, conn: &PgConnection) -> Result<Option<Vec<Webhook>>, DbError>{
+    if !project_exists(project, conn)? {
+        return Ok(None);
+    }
+    let list = webhooks::table
+        .filter(webhooks::project_id.eq(project.to_string()))
+        .order(webhooks::created_at.asc())
+        .load::<Webhook>(conn)?;
+    Ok(Some(list))
+}
+
+/// Метод, возвращающий подписку проекта
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `webhook`     - уникальный идентификатор объекта подписки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект подписки.
+/// Если подписка не найдена в проекте, возвращается None.
+pub fn get_webhook(project: &Uuid, webhook: &Uuid, conn: &PgConnection) -> Result<Option<Webhook>, DbError>{
+    let found = webhooks::table
+        .filter(webhooks::id.eq(webhook.to_string()))
+        .filter(webhooks::project_id.eq(project.to_string()))
+        .first::<Webhook>(conn)
+        .optional()?;
+    Ok(found)
+}
+
+/// Метод, создающий подписку проекта
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `new_webhook` - указатель на десериализованный объект структуры NewWebhook.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект подписки.
+/// Если проект не найден, возвращается None, если данные подписки некорректны - ошибка 400.
+pub fn create_webhook(project: &Uuid, new_webhook: &NewWebhook, conn: &PgConnection) -> Result<Option<Webhook>, DbError>{
+    validate(&new_webhook.url, &new_webhook.secret, &new_webhook.event_types)?;
+    if !project_exists(project, conn)? {
+        return Ok(None);
+    }
+    let new = Webhook{
+        id: Uuid::new_v4().to_string(),
+        project_id: project.to_string(),
+        url: new_webhook.url.clone(),
+        secret: new_webhook.secret.clone(),
+        event_types: new_webhook.event_types.clone(),
+        active: new_webhook.active.unwrap_or(true),
+        created_at: chrono::Utc::now().naive_utc(),
+        updated_at: None,
+    };
+    let created = diesel::insert_into(webhooks::table).values(&new).get_result::<Webhook>(conn)?;
+    Ok(Some(created))
+}
+
+/// Метод, изменяющий подписку проекта
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `webhook`     - уникальный идентификатор объекта подписки.
+/// * `update`      - изменяемые поля подписки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект подписки.
+/// Если подписка не найдена в проекте, возвращается None, если данные подписки некорректны - ошибка 400.
+pub fn update_webhook(project: &Uuid, webhook: &Uuid, update: &WebhookUpdate, conn: &PgConnection) -> Result<Option<Webhook>, DbError>{
+    let current = match get_webhook(project, webhook, conn)? {
+        Some(current) => current,
+        None => return Ok(None),
+    };
+    let url = update.url.clone().unwrap_or(current.url);
+    let secret = update.secret.clone().unwrap_or(current.secret);
+    let event_types = update.event_types.clone().unwrap_or(current.event_types);
+    validate(&url, &secret, &event_types)?;
+    let updated = diesel::update(webhooks::table.find(&current.id))
+        .set((
+            webhooks::url.eq(url),
+            webhooks::secret.eq(secret),
+            webhooks::event_types.eq(event_types),
+            webhooks::active.eq(update.active.unwrap_or(current.active)),
+            webhooks::updated_at.eq(chrono::Utc::now().naive_utc()),
+        ))
+        .get_result::<Webhook>(conn)?;
+    Ok(Some(updated))
+}
+
+/// Метод, удаляющий подписку проекта вместе с журналом её отправок
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `webhook`     - уникальный идентификатор объекта подписки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_webhook(project: &Uuid, webhook: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let deleted = diesel::delete(webhooks::table
+        .filter(webhooks::id.eq(webhook.to_string()))
+        .filter(webhooks::project_id.eq(project.to_string())))
+        .execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, возвращающий страницу журнала отправок подписки, начиная с последних
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `webhook`     - уникальный идентификатор объекта подписки.
+/// * `query`       - фильтр по состоянию и параметры страницы.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу отправок.
+/// Если подписка не найдена в проекте, возвращается None, если состояние неизвестно - ошибка 400.
+pub fn get_deliveries(project: &Uuid, webhook: &Uuid, query: &DeliveryQuery, conn: &PgConnection) -> Result<Option<Page<WebhookDelivery>>, DbError>{
+    if let Some(status) = &query.status {
+        if !STATUSES.contains(&status.as_str()) {
+            return Err(Box::new(ClientError::BadRequest(format!(
+                "Unknown delivery status {}, expected one of {}", status, STATUSES.join(", ")
+            ))));
+        }
+    }
+    let webhook = match get_webhook(project, webhook, conn)? {
+        Some(webhook) => webhook,
+        None => return Ok(None),
+    };
+    let (page, per_page) = PageQuery{ page: query.page, per_page: query.per_page }.bounds();
+    let filtered = || {
+        let mut select = webhook_deliveries::table
+            .filter(webhook_deliveries::webhook_id.eq(webhook.id.clone()))
+            .into_boxed();
+        if let Some(status) = &query.status {
+            select = select.filter(webhook_deliveries::status.eq(status.clone()));
+        }
+        select
+    };
+    let total = filtered().count().get_result::<i64>(conn)?;
+    let items = filtered()
+        .order((webhook_deliveries::created_at.desc(), webhook_deliveries::id.desc()))
+        .offset((page - 1) * per_page)
+        .limit(per_page)
+        .load::<WebhookDelivery>(conn)?;
+
+    Ok(Some(Page{ items, total, page, per_page }))
+}
+
+/// Метод, повторно отправляющий событие по подписке. Создаётся новая отправка с тем же телом,
+/// исходная отправка в журнале не меняется.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `project`     - уникальный идентификатор объекта проекта.
+/// * `webhook`     - уникальный идентификатор объекта подписки.
+/// * `delivery`    - уникальный идентификатор исходной отправки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо новую отправку.
+/// Если подписка или отправка не найдены, возвращается None.
+pub fn redeliver(project: &Uuid, webhook: &Uuid, delivery: &Uuid, conn: &PgConnection) -> Result<Option<WebhookDelivery>, DbError>{
+    let webhook = match get_webhook(project, webhook, conn)? {
+        Some(webhook) => webhook,
+        None => return Ok(None),
+    };
+    let original = webhook_deliveries::table
+        .filter(webhook_deliveries::id.eq(delivery.to_string()))
+        .filter(webhook_deliveries::webhook_id.eq(&webhook.id))
+        .first::<WebhookDelivery>(conn)
+        .optional()?;
+    let original = match original {
+        Some(original) => original,
+        None => return Ok(None),
+    };
+    let mut redelivery = new_delivery(&webhook.id, original.event_id, &original.event_type, original.payload);
+    redelivery.redelivery_of = Some(original.id);
+    let created = diesel::insert_into(webhook_deliveries::table)
+        .values(&redelivery)
+        .get_result::<WebhookDelivery>(conn)?;
+    Ok(Some(created))
+}
+
+/// Метод, ставящий в очередь отправку события по подходящим подпискам.
+/// Отправки создаются в транзакции, записавшей событие, поэтому отправляются только сохранённые изменения.
+/// События задач и комментариев отправляются по подпискам их проекта, события пользователей - по подпискам проектов,
+/// в задачах которых пользователь является исполнителем.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `event`       - записанное событие.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим ошибку, если отправки не удалось сохранить.
+pub fn enqueue_event(event: &Event, conn: &PgConnection) -> Result<(), DbError>{
+    let mut query = webhooks::table
+        .filter(webhooks::active.eq(true))
+        .filter(webhooks::event_types.overlaps_with(vec![event.kind.clone(), ALL_EVENTS.to_string()]))
+        .select(webhooks::id)
+        .into_boxed();
+    if event.entity_type == events::ENTITY_USER {
+        let projects = user_projects(&event.entity_id, conn)?;
+        if projects.is_empty() {
+            return Ok(());
+        }
+        query = query.filter(webhooks::project_id.eq_any(projects));
+    } else {
+        match &event.project_id {
+            Some(project) => query = query.filter(webhooks::project_id.eq(project.clone())),
+            None => return Ok(()),
+        }
+    }
+    let subscribers = query.load::<String>(conn)?;
+    if subscribers.is_empty() {
+        return Ok(());
+    }
+    let payload = json!({
+        "event": event.kind,
+        "event_id": event.id,
+        "entity_type": event.entity_type,
+        "entity_id": event.entity_id,
+        "project_id": event.project_id,
+        "actor_id": event.actor_id,
+        "created_at": event.created_at,
+        "data": event.payload,
+    });
+    let deliveries: Vec<WebhookDelivery> = subscribers
+        .iter()
+        .map(|webhook| new_delivery(webhook, event.id, &event.kind, payload.clone()))
+        .collect();
+    diesel::insert_into(webhook_deliveries::table).values(&deliveries).execute(conn)?;
+    Ok(())
+}
+
+/// Метод, выполняющий отправки, время которых наступило.
+/// Отправки сначала закрепляются за сервером в короткой транзакции, поэтому несколько экземпляров сервера
+/// не отправляют одно событие дважды, а запросы к получателям выполняются без блокировок в базе данных.
+/// Результат каждой попытки записывается отдельно. После неудачной попытки отправка повторяется
+/// с удваивающейся задержкой, пока не исчерпаны попытки.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `limit`       - максимальное количество выполняемых отправок.
+/// * `send`        - функция, отправляющая тело отправки по подписке.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество обработанных отправок.
+pub fn send_pending<F>(limit: i64, send: F, conn: &PgConnection) -> Result<usize, DbError>
+where
+    F: Fn(&Webhook, &WebhookDelivery) -> AttemptResult
+{
+    let (due, lease) = claim_due(limit, conn)?;
+    let ids: Vec<&String> = due.iter().map(|delivery| &delivery.webhook_id).collect();
+    let subscriptions: HashMap<String, Webhook> = webhooks::table
+        .filter(webhooks::id.eq(any(ids)))
+        .load::<Webhook>(conn)?
+        .into_iter()
+        .map(|webhook| (webhook.id.clone(), webhook))
+        .collect();
+
+    for delivery in &due {
+        let result = match subscriptions.get(&delivery.webhook_id) {
+            Some(webhook) if webhook.active => send(webhook, delivery),
+            _ => AttemptResult{ error: Some(DISABLED_ERROR.to_string()), ..Default::default() },
+        };
+        record_attempt(delivery, lease, result, conn)?;
+    }
+    Ok(due.len())
+}
+
+/// Метод, закрепляющий за сервером отправки, время которых наступило.
+/// Выбираются ожидающие отправки и отправки, результат которых не записан до истечения срока закрепления.
+/// Возвращает закреплённые отправки и время окончания закрепления.
+fn claim_due(limit: i64, conn: &PgConnection) -> Result<(Vec<WebhookDelivery>, chrono::NaiveDateTime), DbError>{
+    conn.transaction::<_, DbError, _>(|| {
+        let now = chrono::Utc::now().naive_utc();
+        let due = webhook_deliveries::table
+            .filter(webhook_deliveries::status.eq_any(vec![STATUS_PENDING, STATUS_SENDING]))
+            .filter(webhook_deliveries::next_attempt_at.le(now))
+            .order(webhook_deliveries::next_attempt_at.asc())
+            .limit(limit)
+            .for_update()
+            .skip_locked()
+            .load::<WebhookDelivery>(conn)?;
+        // Время хранится в базе данных с точностью до микросекунд и сравнивается при записи результата
+        let lease = now + chrono::Duration::seconds(SEND_LEASE);
+        let lease = lease.with_nanosecond(lease.nanosecond() / 1000 * 1000).unwrap_or(lease);
+        let ids: Vec<&String> = due.iter().map(|delivery| &delivery.id).collect();
+        diesel::update(webhook_deliveries::table.filter(webhook_deliveries::id.eq_any(ids)))
+            .set((
+                webhook_deliveries::status.eq(STATUS_SENDING),
+                webhook_deliveries::next_attempt_at.eq(lease),
+            ))
+            .execute(conn)?;
+        Ok((due, lease))
+    })
+}
+
+/// Метод, записывающий результат попытки отправки.
+/// Результат не записывается, если за время попытки срок закрепления истёк и отправка закреплена повторно.
+fn record_attempt(delivery: &WebhookDelivery, lease: chrono::NaiveDateTime, result: AttemptResult, conn: &PgConnection) -> Result<(), DbError>{
+    let attempts = delivery.attempts + 1;
+    let delivered = result.error.is_none() && result.status.is_some_and(|status| (200..300).contains(&status));
+    let status = if delivered {
+        STATUS_DELIVERED
+    } else if attempts >= MAX_ATTEMPTS || result.error.as_deref() == Some(DISABLED_ERROR) {
+        STATUS_FAILED
+    } else {
+        STATUS_PENDING
+    };
+    let error = result.error.or_else(|| (!delivered).then(|| {
+        format!("Unexpected response status {}", result.status.unwrap_or_default())
+    }));
+    let body = result.body.map(|body| body.chars().take(MAX_RESPONSE_BODY).collect::<String>());
+    let now = chrono::Utc::now().naive_utc();
+    diesel::update(webhook_deliveries::table
+        .filter(webhook_deliveries::id.eq(&delivery.id))
+        .filter(webhook_deliveries::status.eq(STATUS_SENDING))
+        .filter(webhook_deliveries::next_attempt_at.eq(lease)))
+        .set((
+            webhook_deliveries::status.eq(status),
+            webhook_deliveries::attempts.eq(attempts),
+            webhook_deliveries::next_attempt_at.eq(now + retry_delay(attempts)),
+            webhook_deliveries::response_status.eq(result.status),
+            webhook_deliveries::response_body.eq(body),
+            webhook_deliveries::last_error.eq(error),
+            webhook_deliveries::delivered_at.eq(delivered.then_some(now)),
+        ))
+        .execute(conn)?;
+    Ok(())
+}
+
+/// Метод, возвращающий задержку перед повторной отправкой после указанного количества попыток.
+fn retry_delay(attempts: i32) -> chrono::Duration{
+    let factor = 1_i64 << (attempts - 1).clamp(0, 20);
+    chrono::Duration::seconds((MIN_RETRY_DELAY * factor).min(MAX_RETRY_DELAY))
+}
+
+/// Метод, создающий новую отправку события, ожидающую первой попытки.
+fn new_delivery(webhook: &str, event_id: i64, event_type: &str, payload: serde_json::Value) -> WebhookDelivery{
+    let now = chrono::Utc::now().naive_utc();
+    WebhookDelivery{
+        id: Uuid::new_v4().to_string(),
+        webhook_id: webhook.to_string(),
+        event_id,
+        event_type: event_type.to_string(),
+        payload,
+        status: STATUS_PENDING.to_string(),
+        attempts: 0,
+        next_attempt_at: now,
+        response_status: None,
+        response_body: None,
+        last_error: None,
+        redelivery_of: None,
+        created_at: now,
+        delivered_at: None,
+    }
+}
+
+/// Метод, возвращающий проекты, в задачах которых пользователь является исполнителем.
+fn user_projects(user: &str, conn: &PgConnection) -> Result<Vec<String>, DbError>{
+    let projects = task_assignees::table
+        .inner_join(tasks::table)
+        .filter(task_assignees::user_id.eq(user))
+        .filter(tasks::project_id.is_not_null())
+        .select(tasks::project_id)
+        .distinct()
+        .load::<Option<String>>(conn)?;
+    Ok(projects.into_iter().flatten().collect())
+}
+
+/// Метод проверки существования проекта.
+fn project_exists(project: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(projects::table.filter(projects::id.eq(project.to_string()))))
+        .get_result::<bool>(conn)?;
+    Ok(exists)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retry_delay_doubles_up_to_an_hour(){
+        let delays: Vec<i64> = (1..=10).map(|attempts| retry_delay(attempts).num_seconds()).collect();
+
+        assert_eq!(delays, vec![15, 30, 60, 120, 240, 480, 960, 1920, 3600, 3600]);
+        assert_eq!(retry_delay(0).num_seconds(), MIN_RETRY_DELAY);
+        assert_eq!(retry_delay(1000).num_seconds(), MAX_RETRY_DELAY);
+    }
+
+    #[test]
+    fn private_addresses_are_not_public(){
+        for ip in [
+            "127.0.0.1", "10.0.0.1", "172.16.5.4", "192.168.1.1", "169.254.169.254", "100.64.0.1", "0.0.0.0",
+            "255.255.255.255", "224.0.0.1", "198.18.0.1", "240.0.0.1", "::1", "::", "fd00::1", "fe80::1",
+            "::ffff:127.0.0.1", "::ffff:10.0.0.1", "2001:db8::1",
+        ] {
+            assert!(!is_public_address(&ip.parse().unwrap()), "{} must not be public", ip);
+        }
+        for ip in ["8.8.8.8", "1.1.1.1", "100.128.0.1", "2606:4700:4700::1111", "::ffff:8.8.8.8"] {
+            assert!(is_public_address(&ip.parse().unwrap()), "{} must be public", ip);
+        }
+    }
+
+    #[test]
+    fn validates_webhook_url(){
+        let events = vec![ALL_EVENTS.to_string()];
+
+        assert!(validate("https://hooks.example.com/path?x=1", "s3cret", &events).is_ok());
+        assert!(validate("http://8.8.8.8:8080/", "s3cret", &events).is_ok());
+        for url in ["ftp://example.com", "example.com/hook", "https://", "not a url"] {
+            assert!(matches!(validate(url, "s3cret", &events), Err(ClientError::BadRequest(_))), "{} must be rejected", url);
+        }
+        if !private_hosts_allowed() {
+            for url in ["http://localhost:8080", "http://api.localhost/", "http://LOCALHOST./", "http://127.0.0.1/", "http://[::1]/", "http://user@10.0.0.1/"] {
+                assert!(matches!(validate(url, "s3cret", &events), Err(ClientError::BadRequest(_))), "{} must be rejected", url);
+            }
+        }
+    }
+
+    #[test]
+    fn validates_secret_and_event_types(){
+        let url = "https://hooks.example.com/";
+
+        assert!(validate(url, "", &[ALL_EVENTS.to_string()]).is_err());
+        assert!(validate(url, "s3cret", &[]).is_err());
+        assert!(validate(url, "s3cret", &["task.exploded".to_string()]).is_err());
+        assert!(validate(url, "s3cret", &[events::TASK_CREATED.to_string()]).is_ok());
+    }
+}
@@ -0,0 +1,400 @@
+use diesel::{prelude::*};
+use diesel::result::{DatabaseErrorKind, Error as DieselError};
+use chrono::{Datelike, Duration};
+use std::collections::{BTreeMap, HashMap};
+
+use crate::models::{
+    AuditEvent, NewWorklog, TimeTotal, TimeTotalEntry, Timesheet, TimesheetQuery, TimesheetRow, TimerStart, Worklog
+};
+use crate::schema::{projects, tasks, users, worklogs};
+use super::{audit, csv_field, ClientError};
+use uuid::Uuid;
+/// Тип ошибок, возникающих при работе с базой данных
+type DbError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Строка выборки для отчёта: начало записи, длительность, проект задачи и его название
+type TimesheetEntry = (chrono::NaiveDateTime, Option<i32>, Option<String>, Option<String>);
+
+/// Допустимые способы группировки отчёта по затраченному времени
+const GROUP_BY: [&str; 3] = ["day", "week", "project"];
+
+/// Метод, возвращающий записи о затраченном времени по задаче
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор записей.
+/// Если задача не найдена, возвращается None.
+pub fn get_worklogs(task: &Uuid, conn: &PgConnection) -> Result<Option<Vec<Worklog>>, DbError>{
+    if !task_exists(task, conn)? {
+        return Ok(None);
+    }
+    let worklogs_list = worklogs::table
+        .filter(worklogs::task_id.eq(task.to_string()))
+        .order(worklogs::started_at.asc())
+        .load::<Worklog>(conn)?;
+
+    Ok(Some(worklogs_list))
+}
+
+/// Метод, создающий запись о затраченном времени вручную
+/// # Arguments
+///
+/// * `conn`            - указатель на подключение к базе данных.
+/// * `task`            - уникальный идентификатор объекта задачи.
+/// * `author`          - идентификатор пользователя, затратившего время.
+/// * `new_worklog`     - указатель на десериализованный объект структуры NewWorklog.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект записи.
+/// Если задача не найдена, возвращается None.
+pub fn create_worklog(task: &Uuid, author: &str, new_worklog: &NewWorklog, conn: &PgConnection) -> Result<Option<Worklog>, DbError>{
+    if new_worklog.duration_seconds <= 0 {
+        return Err(Box::new(ClientError::BadRequest("Duration must be positive".to_string())));
+    }
+    if !task_exists(task, conn)? {
+        return Ok(None);
+    }
+    check_user(author, conn)?;
+
+    let new = Worklog{
+        id: Uuid::new_v4().to_string(),
+        task_id: task.to_string(),
+        user_id: author.to_string(),
+        started_at: new_worklog.started_at,
+        duration_seconds: Some(new_worklog.duration_seconds),
+        note: new_worklog.note.clone(),
+        created_at: chrono::Utc::now().naive_utc(),
+    };
+    diesel::insert_into(worklogs::table).values(&new).execute(conn)?;
+    Ok(Some(new))
+}
+
+/// Метод, удаляющий запись о затраченном времени. Удалять запись может только её автор.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `worklog`     - уникальный идентификатор записи.
+/// * `actor`       - идентификатор пользователя, выполняющего удаление.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, флаг, реализованный через тип bool.
+pub fn delete_worklog(task: &Uuid, worklog: &Uuid, actor: &str, conn: &PgConnection) -> Result<bool, DbError>{
+    let existing = worklogs::table
+        .filter(worklogs::id.eq(worklog.to_string()))
+        .filter(worklogs::task_id.eq(task.to_string()))
+        .first::<Worklog>(conn)
+        .optional()?;
+    let existing = match existing {
+        Some(existing) => existing,
+        None => return Ok(false),
+    };
+    if existing.user_id != actor {
+        return Err(Box::new(ClientError::Forbidden("Only the author can delete this worklog".to_string())));
+    }
+    let deleted = diesel::delete(worklogs::table.filter(worklogs::id.eq(&existing.id))).execute(conn)?;
+    Ok(deleted > 0)
+}
+
+/// Метод, запускающий таймер пользователя по задаче.
+/// У пользователя может быть только один запущенный таймер.
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+/// * `actor`       - идентификатор пользователя, запускающего таймер.
+/// * `start`       - указатель на десериализованный объект структуры TimerStart.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо запись запущенного таймера.
+/// Если задача не найдена, возвращается None.
+pub fn start_timer(task: &Uuid, actor: &str, start: &TimerStart, conn: &PgConnection) -> Result<Option<Worklog>, DbError>{
+    if !task_exists(task, conn)? {
+        return Ok(None);
+    }
+    check_user(actor, conn)?;
+    if let Some(running) = get_running_timer(actor, conn)? {
+        return Err(Box::new(ClientError::Conflict(format!(
+            "Timer is already running for task {}", running.task_id
+        ))));
+    }
+
+    let now = chrono::Utc::now().naive_utc();
+    let new = Worklog{
+        id: Uuid::new_v4().to_string(),
+        task_id: task.to_string(),
+        user_id: actor.to_string(),
+        started_at: now,
+        duration_seconds: None,
+        note: start.note.clone(),
+        created_at: now,
+    };
+    match diesel::insert_into(worklogs::table).values(&new).execute(conn) {
+        Ok(_) => Ok(Some(new)),
+        // Таймер мог быть запущен параллельным запросом
+        Err(DieselError::DatabaseError(DatabaseErrorKind::UniqueViolation, _)) => Err(Box::new(
+            ClientError::Conflict("Timer is already running".to_string())
+        )),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// Метод, останавливающий запущенный таймер пользователя и записывающий затраченное время
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `actor`       - идентификатор пользователя, останавливающего таймер.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо завершённую запись.
+/// Если запущенного таймера нет, возвращается None.
+pub fn stop_timer(actor: &str, conn: &PgConnection) -> Result<Option<Worklog>, DbError>{
+    let running = match get_running_timer(actor, conn)? {
+        Some(running) => running,
+        None => return Ok(None),
+    };
+    let elapsed = (chrono::Utc::now().naive_utc() - running.started_at).num_seconds().max(0);
+    // Таймер мог быть остановлен параллельным запросом
+    let stopped = diesel::update(
+        worklogs::table
+            .filter(worklogs::id.eq(&running.id))
+            .filter(worklogs::duration_seconds.is_null())
+    )
+        .set(worklogs::duration_seconds.eq(elapsed as i32))
+        .get_result::<Worklog>(conn)
+        .optional()?;
+    Ok(stopped)
+}
+
+/// Метод, возвращающий запущенный таймер пользователя
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `actor`       - идентификатор пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо запись запущенного таймера.
+pub fn get_running_timer(actor: &str, conn: &PgConnection) -> Result<Option<Worklog>, DbError>{
+    let running = worklogs::table
+        .filter(worklogs::user_id.eq(actor))
+        .filter(worklogs::duration_seconds.is_null())
+        .first::<Worklog>(conn)
+        .optional()?;
+    Ok(running)
+}
+
+/// Метод, возвращающий суммарное время по задаче с разбивкой по пользователям
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `task`        - уникальный идентификатор объекта задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо суммарное время.
+/// Если задача не найдена, возвращается None.
+pub fn get_task_total(task: &Uuid, conn: &PgConnection) -> Result<Option<TimeTotal>, DbError>{
+    if !task_exists(task, conn)? {
+        return Ok(None);
+    }
+    let rows: Vec<(String, String, Option<i32>)> = worklogs::table
+        .inner_join(users::table)
+        .filter(worklogs::task_id.eq(task.to_string()))
+        .filter(worklogs::duration_seconds.is_not_null())
+        .select((users::id, users::user_name, worklogs::duration_seconds))
+        .load(conn)?;
+
+    Ok(Some(to_total(rows)))
+}
+
+/// Метод, возвращающий суммарное время пользователя с разбивкой по задачам
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `user`        - уникальный идентификатор объекта пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо суммарное время.
+/// Если пользователь не найден, возвращается None.
+pub fn get_user_total(user: &Uuid, conn: &PgConnection) -> Result<Option<TimeTotal>, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(users::table.filter(users::id.eq(user.to_string()))))
+        .get_result::<bool>(conn)?;
+    if !exists {
+        return Ok(None);
+    }
+    let rows: Vec<(String, String, Option<i32>)> = worklogs::table
+        .inner_join(tasks::table)
+        .filter(worklogs::user_id.eq(user.to_string()))
+        .filter(worklogs::duration_seconds.is_not_null())
+        .select((tasks::id, tasks::title, worklogs::duration_seconds))
+        .load(conn)?;
+
+    Ok(Some(to_total(rows)))
+}
+
+/// Метод, формирующий отчёт по затраченному времени
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `query`       - параметры отчёта: пользователь, проект, период и группировка.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо отчёт.
+pub fn get_timesheet(query: &TimesheetQuery, conn: &PgConnection) -> Result<Timesheet, DbError>{
+    let group_by = query.group_by.clone().unwrap_or_else(|| GROUP_BY[0].to_string());
+    if !GROUP_BY.contains(&group_by.as_str()) {
+        return Err(Box::new(ClientError::BadRequest(format!(
+            "Unknown grouping {}, expected one of {}", group_by, GROUP_BY.join(", ")
+        ))));
+    }
+
+    let mut rows_query = worklogs::table
+        .inner_join(tasks::table.left_join(projects::table))
+        .filter(worklogs::duration_seconds.is_not_null())
+        .select((
+            worklogs::started_at,
+            worklogs::duration_seconds,
+            tasks::project_id,
+            projects::name.nullable()
+        ))
+        .into_boxed();
+    if let Some(user) = &query.user_id {
+        rows_query = rows_query.filter(worklogs::user_id.eq(user.clone()));
+    }
+    if let Some(project) = &query.project_id {
+        rows_query = rows_query.filter(tasks::project_id.eq(project.clone()));
+    }
+    if let Some(from) = query.from {
+        rows_query = rows_query.filter(worklogs::started_at.ge(from));
+    }
+    if let Some(to) = query.to {
+        rows_query = rows_query.filter(worklogs::started_at.lt(to));
+    }
+    let rows: Vec<TimesheetEntry> = rows_query.load(conn)?;
+
+    // Ключ группы -> (представление, секунды); BTreeMap упорядочивает строки по ключу
+    let mut groups: BTreeMap<String, (String, i64)> = BTreeMap::new();
+    for (started_at, duration, project, project_name) in rows {
+        let date = started_at.date();
+        let (key, label) = match group_by.as_str() {
+            "week" => {
+                let week = date.iso_week();
+                let monday = date - Duration::days(date.weekday().num_days_from_monday() as i64);
+                (
+                    format!("{}-W{:02}", week.year(), week.week()),
+                    format!("{} - {}", monday, monday + Duration::days(6))
+                )
+            },
+            "project" => (
+                project.unwrap_or_default(),
+                project_name.unwrap_or_else(|| "No project".to_string())
+            ),
+            _ => (date.to_string(), date.format("%a, %d %b %Y").to_string()),
+        };
+        let entry = groups.entry(key).or_insert((label, 0));
+        entry.1 += i64::from(duration.unwrap_or(0));
+    }
+
+    let rows: Vec<TimesheetRow> = groups
+        .into_iter()
+        .map(|(key, (label, seconds))| TimesheetRow{
+            key,
+            label,
+            seconds,
+            hours: (seconds as f64 / 36.0).round() / 100.0,
+        })
+        .collect();
+    Ok(Timesheet{
+        group_by,
+        total_seconds: rows.iter().map(|row| row.seconds).sum(),
+        rows,
+    })
+}
+
+/// Метод, формирующий отчёт по затраченному времени для выгрузки и записывающий выгрузку в журнал аудита
+/// # Arguments
+///
+/// * `conn`        - указатель на подключение к базе данных.
+/// * `query`       - параметры отчёта: пользователь, проект, период и группировка.
+/// * `event`       - событие выгрузки со сведениями о запросе.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо отчёт.
+pub fn export_timesheet(query: &TimesheetQuery, event: AuditEvent, conn: &PgConnection) -> Result<Timesheet, DbError>{
+    let timesheet = get_timesheet(query, conn)?;
+    audit::record(AuditEvent{
+        target_type: Some("timesheet".to_string()),
+        details: serde_json::json!({
+            "user_id": query.user_id,
+            "project_id": query.project_id,
+            "from": query.from,
+            "to": query.to,
+            "group_by": timesheet.group_by,
+        }),
+        ..event
+    }, conn)?;
+    Ok(timesheet)
+}
+
+/// Метод, преобразующий отчёт по затраченному времени в CSV.
+/// # Arguments
+///
+/// * `timesheet`   - отчёт по затраченному времени.
+///
+/// # Return
+///
+/// Возвращает текст CSV с заголовком `key,label,seconds,hours`.
+pub fn timesheet_to_csv(timesheet: &Timesheet) -> String{
+    let mut csv = String::from("key,label,seconds,hours\n");
+    for row in &timesheet.rows {
+        csv.push_str(&format!(
+            "{},{},{},{:.2}\n",
+            csv_field(&row.key), csv_field(&row.label), row.seconds, row.hours
+        ));
+    }
+    csv
+}
+
+/// Метод, суммирующий длительности записей по идентификатору.
+fn to_total(rows: Vec<(String, String, Option<i32>)>) -> TimeTotal{
+    let mut by_id: HashMap<String, TimeTotalEntry> = HashMap::new();
+    for (id, name, duration) in rows {
+        let entry = by_id.entry(id.clone()).or_insert(TimeTotalEntry{ id, name, seconds: 0 });
+        entry.seconds += i64::from(duration.unwrap_or(0));
+    }
+    let mut entries: Vec<TimeTotalEntry> = by_id.into_values().collect();
+    entries.sort_by(|a, b| b.seconds.cmp(&a.seconds).then_with(|| a.name.cmp(&b.name)));
+    TimeTotal{
+        total_seconds: entries.iter().map(|entry| entry.seconds).sum(),
+        entries,
+    }
+}
+
+/// Метод проверки существования пользователя, от имени которого записывается время.
+fn check_user(user: &str, conn: &PgConnection) -> Result<(), DbError>{
+    let exists = diesel::select(diesel::dsl::exists(users::table.filter(users::id.eq(user))))
+        .get_result::<bool>(conn)?;
+    if !exists {
+        return Err(Box::new(ClientError::BadRequest(format!("User {} not found", user))));
+    }
+    Ok(())
+}
+
+/// Метод проверки существования задачи.
+fn task_exists(task: &Uuid, conn: &PgConnection) -> Result<bool, DbError>{
+    let exists = diesel::select(diesel::dsl::exists(tasks::table.filter(tasks::id.eq(task.to_string()))))
+        .get_result::<bool>(conn)?;
+    Ok(exists)
+}
@@ -0,0 +1,117 @@
+pub mod templates;
+
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+use std::time::{Duration, Instant};
+
+use crate::controllers::emails;
+use crate::database::DbPool;
+use crate::models::OutboxEmail;
+
+/// Тип ошибок, возникающих при отправке писем
+pub type MailError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Интервал проверки очереди писем
+const SEND_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Интервал, с которым в очередь ставятся письма о сроках и ежедневные сводки
+const SCHEDULE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Количество писем, отправляемых за одну проверку очереди
+const SEND_BATCH: i64 = 50;
+
+/// Время ожидания ответа SMTP-сервера
+const SMTP_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Отправитель писем из очереди через SMTP
+struct Mailer{
+    transport: SmtpTransport,
+    from: Mailbox,
+}
+
+impl Mailer{
+    /// Метод, создающий отправителя по переменным окружения:
+    /// SMTP_HOST, SMTP_PORT, SMTP_TLS (`none`, `starttls` по умолчанию или `tls`),
+    /// SMTP_USER и SMTP_PASSWORD для авторизации и SMTP_FROM - адрес отправителя.
+    fn from_env() -> Result<Self, MailError>{
+        let host = std::env::var("SMTP_HOST")?;
+        let tls = std::env::var("SMTP_TLS").unwrap_or_else(|_| "starttls".to_string());
+        let mut builder = match tls.as_str() {
+            "none" => SmtpTransport::builder_dangerous(&host),
+            "starttls" => SmtpTransport::starttls_relay(&host)?,
+            "tls" => SmtpTransport::relay(&host)?,
+            other => return Err(format!("Unknown SMTP_TLS {}", other).into()),
+        };
+        if let Ok(port) = std::env::var("SMTP_PORT") {
+            builder = builder.port(port.parse()?);
+        }
+        if let (Ok(user), Ok(password)) = (std::env::var("SMTP_USER"), std::env::var("SMTP_PASSWORD")) {
+            builder = builder.credentials(Credentials::new(user, password));
+        }
+        let from = std::env::var("SMTP_FROM").unwrap_or_else(|_| "noreply@localhost".to_string());
+        Ok(Mailer{ transport: builder.timeout(Some(SMTP_TIMEOUT)).build(), from: from.parse()? })
+    }
+
+    /// Метод, отправляющий письмо из очереди.
+    fn send(&self, email: &OutboxEmail) -> Result<(), MailError>{
+        let message = Message::builder()
+            .from(self.from.clone())
+            .to(email.recipient.parse()?)
+            .subject(email.subject.clone())
+            .header(ContentType::TEXT_PLAIN)
+            .body(email.body.clone())?;
+        self.transport.send(&message)?;
+        Ok(())
+    }
+
+    /// Метод, ставящий в очередь письма о сроках и ежедневные сводки.
+    fn schedule(&self, pool: &DbPool) -> Result<(), MailError>{
+        let conn = pool.get()?;
+        let now = chrono::Utc::now().naive_utc();
+        let due_soon = emails::enqueue_due_soon(now, &conn)?;
+        let digests = emails::enqueue_digests(now, &conn)?;
+        if due_soon + digests > 0 {
+            log::info!("Queued {} due soon emails and {} digests", due_soon, digests);
+        }
+        Ok(())
+    }
+
+    /// Метод, в цикле отправляющий письма из очереди. Выполняется в отдельном потоке.
+    fn run(&self, pool: DbPool){
+        let mut scheduled_at: Option<Instant> = None;
+        loop {
+            if scheduled_at.is_none_or(|scheduled_at| scheduled_at.elapsed() >= SCHEDULE_INTERVAL) {
+                if let Err(err) = self.schedule(&pool) {
+                    log::error!("Failed to schedule emails: {}", err);
+                }
+                scheduled_at = Some(Instant::now());
+            }
+            let result = pool
+                .get()
+                .map_err(MailError::from)
+                .and_then(|conn| emails::send_pending(SEND_BATCH, |email| self.send(email), &conn));
+            match result {
+                // Полная партия означает, что в очереди могут остаться письма
+                Ok(processed) if processed as i64 == SEND_BATCH => continue,
+                Ok(_) => {},
+                Err(err) => log::error!("Failed to send emails: {}", err),
+            }
+            std::thread::sleep(SEND_INTERVAL);
+        }
+    }
+}
+
+/// Метод инициализации отправки писем.
+/// Если SMTP_HOST не задан, письма не ставятся в очередь и не отправляются.
+pub fn init_mailer(pool: DbPool){
+    if !emails::enabled() {
+        log::info!("SMTP_HOST is not set, emails are disabled");
+        return;
+    }
+    let mailer = Mailer::from_env().expect("Failed to configure SMTP");
+    std::thread::Builder::new()
+        .name("email-sender".to_string())
+        .spawn(move || mailer.run(pool))
+        .expect("Failed to start email sender");
+}
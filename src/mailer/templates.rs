@@ -0,0 +1,144 @@
+//! Шаблоны писем на русском и английском языках.
+
+use chrono::NaiveDateTime;
+
+/// Формат даты и времени в письмах
+const DATE_FORMAT: &str = "%Y-%m-%d %H:%M UTC";
+
+/// Язык письма
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Locale{
+    Ru,
+    En,
+}
+
+impl Locale{
+    /// Метод, возвращающий язык по коду `ru` или `en`. Для неизвестного кода возвращается None.
+    pub fn parse(code: &str) -> Option<Locale>{
+        match code {
+            "ru" => Some(Locale::Ru),
+            "en" => Some(Locale::En),
+            _ => None,
+        }
+    }
+}
+
+/// Тема и текст письма
+#[derive(Debug)]
+pub struct Email{
+    pub subject: String,
+    pub body: String,
+}
+
+/// Метод, возвращающий письмо о назначении исполнителем задачи.
+/// # Arguments
+///
+/// * `locale`      - язык письма.
+/// * `title`       - название задачи.
+/// * `actor`       - имя назначившего пользователя, если он известен.
+pub fn assigned(locale: Locale, title: &str, actor: Option<&str>) -> Email{
+    match locale {
+        Locale::Ru => Email{
+            subject: format!("Вам назначена задача «{}»", title),
+            body: format!("{} назначил(а) вас исполнителем задачи «{}».\n", actor.unwrap_or("Кто-то"), title),
+        },
+        Locale::En => Email{
+            subject: format!("You were assigned to \"{}\"", title),
+            body: format!("{} assigned you to the task \"{}\".\n", actor.unwrap_or("Someone"), title),
+        },
+    }
+}
+
+/// Метод, возвращающий письмо об упоминании в комментарии.
+/// # Arguments
+///
+/// * `locale`      - язык письма.
+/// * `title`       - название задачи.
+/// * `actor`       - имя автора комментария.
+/// * `comment`     - текст комментария.
+pub fn mentioned(locale: Locale, title: &str, actor: Option<&str>, comment: &str) -> Email{
+    match locale {
+        Locale::Ru => Email{
+            subject: format!("Вас упомянули в задаче «{}»", title),
+            body: format!("{} упомянул(а) вас в комментарии к задаче «{}»:\n\n{}\n", actor.unwrap_or("Кто-то"), title, comment),
+        },
+        Locale::En => Email{
+            subject: format!("You were mentioned in \"{}\"", title),
+            body: format!("{} mentioned you in a comment on the task \"{}\":\n\n{}\n", actor.unwrap_or("Someone"), title, comment),
+        },
+    }
+}
+
+/// Метод, возвращающий письмо о приближении срока задачи.
+/// # Arguments
+///
+/// * `locale`      - язык письма.
+/// * `title`       - название задачи.
+/// * `due_at`      - срок задачи.
+pub fn due_soon(locale: Locale, title: &str, due_at: NaiveDateTime) -> Email{
+    let due_at = due_at.format(DATE_FORMAT);
+    match locale {
+        Locale::Ru => Email{
+            subject: format!("Скоро срок задачи «{}»", title),
+            body: format!("Срок задачи «{}» истекает {}.\n", title, due_at),
+        },
+        Locale::En => Email{
+            subject: format!("\"{}\" is due soon", title),
+            body: format!("The task \"{}\" is due {}.\n", title, due_at),
+        },
+    }
+}
+
+/// Метод, возвращающий строку сводки об уведомлении.
+/// # Arguments
+///
+/// * `locale`      - язык письма.
+/// * `kind`        - вид уведомления.
+/// * `title`       - название задачи.
+/// * `actor`       - имя пользователя, вызвавшего уведомление, если он известен.
+pub fn digest_line(locale: Locale, kind: &str, title: &str, actor: Option<&str>) -> String{
+    let (ru, en) = match kind {
+        "assigned" => ("назначил(а) вас исполнителем задачи", "assigned you to"),
+        "mentioned" => ("упомянул(а) вас в задаче", "mentioned you in"),
+        _ => ("изменил(а) задачу", "changed"),
+    };
+    match locale {
+        Locale::Ru => format!("- {} {} «{}»", actor.unwrap_or("Кто-то"), ru, title),
+        Locale::En => format!("- {} {} \"{}\"", actor.unwrap_or("Someone"), en, title),
+    }
+}
+
+/// Метод, возвращающий строку сводки о задаче с приближающимся сроком.
+/// # Arguments
+///
+/// * `locale`      - язык письма.
+/// * `title`       - название задачи.
+/// * `due_at`      - срок задачи.
+pub fn digest_due_line(locale: Locale, title: &str, due_at: NaiveDateTime) -> String{
+    match locale {
+        Locale::Ru => format!("- «{}» - срок {}", title, due_at.format(DATE_FORMAT)),
+        Locale::En => format!("- \"{}\" - due {}", title, due_at.format(DATE_FORMAT)),
+    }
+}
+
+/// Метод, возвращающий ежедневную сводку.
+/// # Arguments
+///
+/// * `locale`      - язык письма.
+/// * `date`        - дата сводки.
+/// * `activity`    - строки об уведомлениях.
+/// * `due`         - строки о задачах с приближающимся сроком.
+pub fn digest(locale: Locale, date: chrono::NaiveDate, activity: &[String], due: &[String]) -> Email{
+    let (subject, activity_header, due_header) = match locale {
+        Locale::Ru => (format!("Сводка за {}", date), "Новое:", "Скоро срок:"),
+        Locale::En => (format!("Your digest for {}", date), "Activity:", "Due soon:"),
+    };
+    let mut sections = Vec::new();
+    if !activity.is_empty() {
+        sections.push(format!("{}\n{}\n", activity_header, activity.join("\n")));
+    }
+    if !due.is_empty() {
+        sections.push(format!("{}\n{}\n", due_header, due.join("\n")));
+    }
+    Email{ subject, body: sections.join("\n") }
+}
@@ -2,11 +2,16 @@
 extern  crate diesel;
 
 
+mod auth;
 mod database;
+mod mailer;
 mod models;
+mod realtime;
 mod schema;
 mod controllers;
 mod router;
+mod storage;
+mod webhooks;
 
 use actix_web::{App, middleware, HttpServer};
 use actix_web::web::Data;
@@ -22,13 +27,20 @@ async fn main() -> std::io::Result<()>{
     let port = std::env::var("PORT").expect("Port");
     let address = format!("{}:{}",host,port);
     log::info!("Starting HTTP server at http://{}", &address);
-    HttpServer::new(|| {
+    let storage = storage::init_storage();
+    controllers::thumbnails::init_sweeper(database::init_pool(), storage.clone());
+    let hub = realtime::init_hub(database::init_pool());
+    mailer::init_mailer(database::init_pool());
+    webhooks::init_webhooks(database::init_pool());
+    HttpServer::new(move || {
       let cors = Cors::default()
         .allow_any_header()
         .allow_any_method()
         .allow_any_origin();
       App::new()
         .app_data(Data::new(database::init_pool().clone()))
+        .app_data(Data::new(storage.clone()))
+        .app_data(Data::new(hub.clone()))
         .wrap(middleware::Logger::default())
         .wrap(cors)
         .service(router::get_tasks)
@@ -36,11 +48,100 @@ async fn main() -> std::io::Result<()>{
         .service(router::get_task)
         .service(router::delete_task)
         .service(router::update_task)
+        .service(router::get_task_children)
+        .service(router::get_task_tree)
+        .service(router::get_task_occurrences)
+        .service(router::update_task_recurrence)
+        .service(router::get_task_history)
+        .service(router::revert_task)
         .service(router::get_users)
         .service(router::add_user)
         .service(router::get_user)
         .service(router::delete_user)
         .service(router::update_user)
+        .service(router::login)
+        .service(router::get_projects)
+        .service(router::add_project)
+        .service(router::get_project)
+        .service(router::delete_project)
+        .service(router::update_project)
+        .service(router::get_project_schedule)
+        .service(router::get_labels)
+        .service(router::add_label)
+        .service(router::get_label)
+        .service(router::update_label)
+        .service(router::merge_labels)
+        .service(router::delete_label)
+        .service(router::get_task_labels)
+        .service(router::add_task_label)
+        .service(router::remove_task_label)
+        .service(router::get_task_links)
+        .service(router::add_task_link)
+        .service(router::delete_task_link)
+        .service(router::get_comments)
+        .service(router::add_comment)
+        .service(router::get_comment)
+        .service(router::update_comment)
+        .service(router::delete_comment)
+        .service(router::get_comment_history)
+        .service(router::get_task_assignees)
+        .service(router::add_task_assignee)
+        .service(router::remove_task_assignee)
+        .service(router::get_task_watchers)
+        .service(router::watch_task)
+        .service(router::unwatch_task)
+        .service(router::get_attachments)
+        .service(router::add_attachment)
+        .service(router::get_attachment)
+        .service(router::download_attachment)
+        .service(router::get_attachment_thumbnail)
+        .service(router::delete_attachment)
+        .service(router::get_checklist)
+        .service(router::add_checklist_item)
+        .service(router::reorder_checklist)
+        .service(router::toggle_checklist_item)
+        .service(router::delete_checklist_item)
+        .service(router::get_worklogs)
+        .service(router::add_worklog)
+        .service(router::delete_worklog)
+        .service(router::start_timer)
+        .service(router::stop_timer)
+        .service(router::get_timer)
+        .service(router::get_task_time)
+        .service(router::get_user_time)
+        .service(router::get_timesheet)
+        .service(router::get_sprints)
+        .service(router::add_sprint)
+        .service(router::get_sprint)
+        .service(router::update_sprint)
+        .service(router::delete_sprint)
+        .service(router::get_sprint_tasks)
+        .service(router::add_sprint_task)
+        .service(router::remove_sprint_task)
+        .service(router::close_sprint)
+        .service(router::get_project_velocity)
+        .service(router::get_sprint_burndown)
+        .service(router::get_project_burndown)
+        .service(router::get_project_cumulative_flow)
+        .service(router::get_project_flow)
+        .service(router::get_project_forecast)
+        .service(router::get_audit_log)
+        .service(router::export_audit_log)
+        .service(router::verify_audit_log)
+        .service(router::connect_events)
+        .service(router::get_events)
+        .service(router::get_my_tasks)
+        .service(router::get_notifications)
+        .service(router::mark_all_notifications_read)
+        .service(router::mark_notification_read)
+        .service(router::get_notification_preferences)
+        .service(router::update_notification_preferences)
+        .service(router::get_webhooks)
+        .service(router::add_webhook)
+        .service(router::update_webhook)
+        .service(router::delete_webhook)
+        .service(router::get_webhook_deliveries)
+        .service(router::redeliver_webhook)
     }
       )
     .bind(address)?
@@ -1,12 +1,13 @@
 use diesel::Insertable;
 use serde::{Deserialize,Serialize};
-use crate::schema::{tasks, users};
+use crate::schema::{attachment_thumbnails, attachments, audit_log, checklist_items, comment_mentions, comment_revisions, comments, email_outbox, events, labels, notification_preferences, notifications, projects, sprints, task_assignees, task_labels, task_links, task_revisions, task_series, task_status_changes, task_watchers, tasks, users, webhook_deliveries, webhooks, worklogs};
 use chrono;
 
 /// Модель сущности задания. Используется для работы ОРМ Diesel
 #[derive(Debug, Serialize, Deserialize, Queryable, Identifiable, Associations, Insertable)]
 #[table_name = "tasks"]
 #[belongs_to(User)]
+#[belongs_to(Project)]
 pub struct Task{
     pub id: String,
     pub title: String,
@@ -14,17 +15,102 @@ pub struct Task{
     pub done: bool,
     pub user_id: Option<String>, 
     pub created_at: chrono::NaiveDateTime,
-    pub updated_at: Option<chrono::NaiveDateTime>
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    pub project_id: Option<String>,
+    pub parent_id: Option<String>,
+    pub due_at: Option<chrono::NaiveDateTime>,
+    pub estimate_minutes: Option<i32>,
+    pub series_id: Option<String>,
+    pub estimate_points: Option<i32>,
+    pub sprint_id: Option<String>,
+    pub status: String
 }
 
 /// Вспомогательная модель. 
 /// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+/// Поля `project_id`, `parent_id`, `due_at`, `estimate_minutes` и `estimate_points` при изменении задачи
+/// не меняются, если не переданы: None - поле не передано, Some(None) - передан null.
 #[derive(Serialize,Deserialize)]
 pub struct NewTask{
     pub title: String,
     pub body: String,
     pub done: bool,
     pub user_id: Option<String>,
+    #[serde(default, deserialize_with = "optional_field")]
+    pub project_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "optional_field")]
+    pub parent_id: Option<Option<String>>,
+    #[serde(default, deserialize_with = "optional_field")]
+    pub due_at: Option<Option<chrono::NaiveDateTime>>,
+    #[serde(default, deserialize_with = "optional_field")]
+    pub estimate_minutes: Option<Option<i32>>,
+    #[serde(default, deserialize_with = "optional_field")]
+    pub estimate_points: Option<Option<i32>>,
+    /// Статус задачи: `todo`, `in_progress` или `done`. Если не задан, выводится из `done`.
+    #[serde(default)]
+    pub status: Option<String>,
+}
+
+/// Метод десериализации необязательного поля, отличающий непереданное поле от null.
+/// Вызывается только для переданного поля, поэтому null даёт Some(None), а отсутствие поля - значение по умолчанию None.
+fn optional_field<'de, D, T>(deserializer: D) -> Result<Option<Option<T>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de>
+{
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// Задача вместе с вычисляемыми полями, отдаваемая клиенту.
+/// `blocked` - у задачи есть незавершённые блокирующие задачи,
+/// `checklist` - прогресс чек-листа, отсутствует, если у задачи нет пунктов чек-листа,
+/// `progress` - процент выполненных потомков, отсутствует, если у задачи нет подзадач,
+/// `assignees` - исполнители задачи с ролями.
+#[derive(Debug, Serialize)]
+pub struct TaskView{
+    #[serde(flatten)]
+    pub task: Task,
+    pub blocked: bool,
+    pub checklist: Option<ChecklistProgress>,
+    pub progress: Option<f64>,
+    pub assignees: Vec<AssigneeView>,
+}
+
+/// Узел дерева задач. `progress` - процент выполненных потомков,
+/// отсутствует, если у задачи нет подзадач.
+#[derive(Debug, Serialize)]
+pub struct TaskNode{
+    #[serde(flatten)]
+    pub task: Task,
+    pub progress: Option<f64>,
+    pub children: Vec<TaskNode>,
+}
+
+/// Поведение при удалении задачи, у которой есть подзадачи.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OnParentDelete{
+    /// Удалить все подзадачи вместе с задачей.
+    Cascade,
+    /// Оставить подзадачи, сделав их задачами верхнего уровня.
+    Orphan,
+    /// Запретить удаление, пока у задачи есть подзадачи.
+    #[default]
+    Block,
+}
+
+/// Параметры фильтрации списка задач, передаваемые в строке запроса.
+/// `labels` - идентификаторы меток через запятую,
+/// `label_match` - режим сопоставления: `any` (по умолчанию) или `all`,
+/// `assignee` - идентификатор исполнителя или `me` для текущего пользователя,
+/// `assignee_role` - роль исполнителя, например `reviewer`.
+#[derive(Debug, Default, Deserialize)]
+pub struct TaskFilter{
+    pub project_id: Option<String>,
+    pub labels: Option<String>,
+    pub label_match: Option<String>,
+    pub assignee: Option<String>,
+    pub assignee_role: Option<String>,
 }
 
 /// Модель сущности пользователя. Используется для работы ОРМ Diesel
@@ -33,6 +119,8 @@ pub struct NewTask{
 pub struct User{
     pub id: String,
     pub user_name: String,
+    /// Пароль хранится в открытом виде и поэтому никогда не отдаётся клиенту
+    #[serde(skip_serializing)]
     pub password: String,
     pub email: String,
     pub role: i32,
@@ -48,4 +136,984 @@ pub struct NewUser{
     pub password: String,
     pub email: String,
     pub role: i32,
-}
\ No newline at end of file
+}
+
+/// Данные для входа пользователя. `login` - имя пользователя или адрес электронной почты.
+#[derive(Serialize,Deserialize)]
+pub struct Credentials{
+    pub login: String,
+    pub password: String,
+}
+
+/// Результат успешного входа: токен для заголовка `Authorization: Bearer <token>`,
+/// время окончания его действия и вошедший пользователь.
+#[derive(Debug, Serialize)]
+pub struct Session{
+    pub token: String,
+    pub expires_at: chrono::NaiveDateTime,
+    pub user: User,
+}
+
+/// Модель сущности проекта. Используется для работы ОРМ Diesel
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "projects"]
+pub struct Project{
+    pub id: String,
+    pub name: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+    pub storage_quota: Option<i64>
+}
+
+/// Вспомогательная модель. 
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+/// `storage_quota` - квота на вложения проекта в байтах, None - квота по умолчанию.
+#[derive(Serialize,Deserialize)]
+pub struct NewProject{
+    pub name: String,
+    #[serde(default)]
+    pub storage_quota: Option<i64>,
+}
+
+/// Модель сущности метки. Метка без `project_id` считается глобальной.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "labels"]
+#[belongs_to(Project)]
+pub struct Label{
+    pub id: String,
+    pub name: String,
+    pub color: String,
+    pub project_id: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>
+}
+
+/// Вспомогательная модель. 
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+#[derive(Serialize,Deserialize)]
+pub struct NewLabel{
+    pub name: String,
+    pub color: Option<String>,
+    #[serde(default)]
+    pub project_id: Option<String>,
+}
+
+/// Модель связи задачи и метки (таблица task_labels).
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "task_labels"]
+#[primary_key(task_id, label_id)]
+#[belongs_to(Task)]
+#[belongs_to(Label)]
+pub struct TaskLabel{
+    pub task_id: String,
+    pub label_id: String,
+}
+
+/// Метка вместе с количеством задач, к которым она привязана.
+#[derive(Debug, Serialize)]
+pub struct LabelUsage{
+    #[serde(flatten)]
+    pub label: Label,
+    pub usage: i64,
+}
+
+/// Тело запроса на слияние меток: метка из пути переносится в `into`.
+#[derive(Serialize,Deserialize)]
+pub struct LabelMerge{
+    pub into: String,
+}
+
+/// Модель связи между задачами. Для типа `blocks` задача `source_id` блокирует задачу `target_id`.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "task_links"]
+pub struct TaskLink{
+    pub id: String,
+    pub source_id: String,
+    pub target_id: String,
+    pub link_type: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Вспомогательная модель. 
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+/// `link_type` - один из типов `blocks`, `relates_to`, `duplicates`.
+#[derive(Serialize,Deserialize)]
+pub struct NewTaskLink{
+    pub target_id: String,
+    pub link_type: String,
+}
+
+/// Строка расписания проекта. Все смещения отсчитываются от начала расписания,
+/// длительность задачи берётся из оценки `estimate_minutes`.
+#[derive(Debug, Serialize)]
+pub struct ScheduleEntry{
+    pub task_id: String,
+    pub title: String,
+    pub done: bool,
+    pub duration_minutes: i64,
+    pub earliest_start: chrono::NaiveDateTime,
+    pub earliest_finish: chrono::NaiveDateTime,
+    pub latest_start: chrono::NaiveDateTime,
+    pub latest_finish: chrono::NaiveDateTime,
+    pub slack_minutes: i64,
+    pub critical: bool,
+    pub due_at: Option<chrono::NaiveDateTime>,
+    pub dependencies: Vec<String>,
+}
+
+/// Задача, срок которой невозможно соблюсти при текущих зависимостях и оценках.
+#[derive(Debug, Serialize)]
+pub struct InfeasibleTask{
+    pub task_id: String,
+    pub due_at: chrono::NaiveDateTime,
+    pub earliest_finish: chrono::NaiveDateTime,
+    pub late_by_minutes: i64,
+}
+
+/// Расписание проекта, пригодное для построения диаграммы Ганта.
+#[derive(Debug, Serialize)]
+pub struct Schedule{
+    pub project_id: String,
+    pub start: chrono::NaiveDateTime,
+    pub finish: chrono::NaiveDateTime,
+    pub tasks: Vec<ScheduleEntry>,
+    pub critical_path: Vec<String>,
+    pub infeasible: Vec<InfeasibleTask>,
+}
+
+/// Страница списка. Номер страницы начинается с 1.
+#[derive(Debug, Serialize)]
+pub struct Page<T>{
+    pub items: Vec<T>,
+    pub total: i64,
+    pub page: i64,
+    pub per_page: i64,
+}
+
+/// Параметры постраничного вывода, передаваемые в строке запроса.
+#[derive(Debug, Deserialize)]
+pub struct PageQuery{
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+impl PageQuery{
+    /// Размер страницы по умолчанию
+    pub const DEFAULT_PER_PAGE: i64 = 20;
+    /// Максимальный размер страницы
+    pub const MAX_PER_PAGE: i64 = 100;
+
+    /// Метод, возвращающий номер страницы и её размер в допустимых пределах.
+    pub fn bounds(&self) -> (i64, i64){
+        let page = self.page.unwrap_or(1).max(1);
+        let per_page = self.per_page.unwrap_or(Self::DEFAULT_PER_PAGE).clamp(1, Self::MAX_PER_PAGE);
+        (page, per_page)
+    }
+}
+
+/// Модель сущности комментария к задаче. Используется для работы ОРМ Diesel
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "comments"]
+#[belongs_to(Task)]
+#[belongs_to(User)]
+pub struct Comment{
+    pub id: String,
+    pub task_id: String,
+    pub user_id: String,
+    pub body: String,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>
+}
+
+/// Вспомогательная модель. 
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+#[derive(Serialize,Deserialize)]
+pub struct NewComment{
+    pub body: String,
+}
+
+/// Предыдущая версия текста комментария, сохраняемая при каждом изменении.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "comment_revisions"]
+#[belongs_to(Comment)]
+pub struct CommentRevision{
+    pub id: String,
+    pub comment_id: String,
+    pub body: String,
+    pub edited_at: chrono::NaiveDateTime,
+}
+
+/// Модель упоминания пользователя в комментарии (таблица comment_mentions).
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable)]
+#[table_name = "comment_mentions"]
+pub struct CommentMention{
+    pub comment_id: String,
+    pub user_id: String,
+}
+
+/// Упомянутый пользователь в ответе сервера.
+#[derive(Debug, Serialize)]
+pub struct Mention{
+    pub user_id: String,
+    pub user_name: String,
+}
+
+/// Комментарий вместе с упомянутыми в нём пользователями.
+#[derive(Debug, Serialize)]
+pub struct CommentView{
+    #[serde(flatten)]
+    pub comment: Comment,
+    pub mentions: Vec<Mention>,
+}
+
+/// Модель метаданных вложения задачи. Содержимое файла хранится в хранилище по ключу `storage_key`.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "attachments"]
+#[belongs_to(Task)]
+pub struct Attachment{
+    pub id: String,
+    pub task_id: String,
+    pub file_name: String,
+    pub size: i64,
+    pub content_type: String,
+    pub checksum: String,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub uploaded_by: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub preview_status: Option<String>,
+}
+
+/// Уменьшенная копия изображения-вложения. Метаданные исходного файла (EXIF) в неё не переносятся.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "attachment_thumbnails"]
+#[belongs_to(Attachment)]
+pub struct AttachmentThumbnail{
+    pub id: String,
+    pub attachment_id: String,
+    pub size_name: String,
+    pub width: i32,
+    pub height: i32,
+    pub content_type: String,
+    pub size: i64,
+    #[serde(skip_serializing)]
+    pub storage_key: String,
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// Вложение вместе с его уменьшенными копиями.
+#[derive(Debug, Serialize)]
+pub struct AttachmentView{
+    #[serde(flatten)]
+    pub attachment: Attachment,
+    pub thumbnails: Vec<AttachmentThumbnail>,
+}
+
+/// Загруженный во временный файл, но ещё не сохранённый в хранилище файл.
+#[derive(Debug)]
+pub struct UploadedFile{
+    pub file_name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub checksum: String,
+    pub path: std::path::PathBuf,
+}
+
+/// Модель пункта чек-листа задачи. Пункты упорядочены по полю `position`.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "checklist_items"]
+#[belongs_to(Task)]
+pub struct ChecklistItem{
+    pub id: String,
+    pub task_id: String,
+    pub position: i32,
+    pub text: String,
+    pub checked: bool,
+    pub checked_by: Option<String>,
+    pub checked_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Вспомогательная модель. 
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+#[derive(Serialize,Deserialize)]
+pub struct NewChecklistItem{
+    pub text: String,
+}
+
+/// Новый порядок пунктов чек-листа: идентификаторы всех пунктов задачи в нужном порядке.
+#[derive(Serialize,Deserialize)]
+pub struct ChecklistOrder{
+    pub item_ids: Vec<String>,
+}
+
+/// Прогресс чек-листа задачи: количество отмеченных пунктов и общее количество пунктов.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ChecklistProgress{
+    pub checked: i64,
+    pub total: i64,
+}
+
+/// Модель серии повторяющихся задач. `rrule` - правило повторения в формате iCalendar
+/// (например, `FREQ=WEEKLY;BYDAY=MO;COUNT=10`), `dtstart` - время первого повторения в UTC,
+/// `timezone` - часовой пояс IANA, в котором вычисляются повторения.
+#[derive(Debug, Clone, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "task_series"]
+pub struct TaskSeries{
+    pub id: String,
+    pub rrule: String,
+    pub timezone: String,
+    pub dtstart: chrono::NaiveDateTime,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Изменение задачи и всех её будущих повторений.
+/// Если задача ещё не повторяется, `rrule` обязателен и задача становится первой в новой серии.
+/// Если задан `rrule` или `timezone`, серия разделяется: предыдущие повторения остаются
+/// со старым правилом, а задача и следующие за ней повторения получают новое правило.
+#[derive(Serialize,Deserialize)]
+pub struct RecurrenceEdit{
+    #[serde(default)]
+    pub rrule: Option<String>,
+    #[serde(default)]
+    pub timezone: Option<String>,
+    #[serde(default)]
+    pub title: Option<String>,
+    #[serde(default)]
+    pub body: Option<String>,
+}
+
+/// Параметры предпросмотра повторений: количество следующих повторений.
+#[derive(Debug, Deserialize)]
+pub struct OccurrencesQuery{
+    pub count: Option<usize>,
+}
+
+/// Повторение задачи: время в UTC и то же время в часовом поясе серии.
+#[derive(Debug, Serialize)]
+pub struct Occurrence{
+    pub due_at: chrono::NaiveDateTime,
+    pub local: String,
+}
+
+/// Модель записи о затраченном времени. Запись без `duration_seconds` - запущенный таймер.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "worklogs"]
+#[belongs_to(Task)]
+#[belongs_to(User)]
+pub struct Worklog{
+    pub id: String,
+    pub task_id: String,
+    pub user_id: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub duration_seconds: Option<i32>,
+    pub note: String,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Вспомогательная модель. 
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+#[derive(Serialize,Deserialize)]
+pub struct NewWorklog{
+    pub started_at: chrono::NaiveDateTime,
+    pub duration_seconds: i32,
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Параметры запуска таймера.
+#[derive(Default, Serialize,Deserialize)]
+pub struct TimerStart{
+    #[serde(default)]
+    pub note: String,
+}
+
+/// Суммарное время по задаче или пользователю с разбивкой по пользователям или задачам.
+/// Учитываются только завершённые записи.
+#[derive(Debug, Serialize)]
+pub struct TimeTotal{
+    pub total_seconds: i64,
+    pub entries: Vec<TimeTotalEntry>,
+}
+
+/// Строка разбивки суммарного времени: пользователь или задача и затраченное время.
+#[derive(Debug, Serialize)]
+pub struct TimeTotalEntry{
+    pub id: String,
+    pub name: String,
+    pub seconds: i64,
+}
+
+/// Параметры отчёта по затраченному времени, передаваемые в строке запроса.
+/// `group_by` - `day` (по умолчанию), `week` или `project`, `format` - `json` (по умолчанию) или `csv`.
+/// Период `[from, to)` задаётся по времени начала записи.
+#[derive(Debug, Default, Deserialize)]
+pub struct TimesheetQuery{
+    pub user_id: Option<String>,
+    pub project_id: Option<String>,
+    pub from: Option<chrono::NaiveDateTime>,
+    pub to: Option<chrono::NaiveDateTime>,
+    pub group_by: Option<String>,
+    pub format: Option<String>,
+}
+
+/// Отчёт по затраченному времени.
+#[derive(Debug, Serialize)]
+pub struct Timesheet{
+    pub group_by: String,
+    pub rows: Vec<TimesheetRow>,
+    pub total_seconds: i64,
+}
+
+/// Строка отчёта по затраченному времени. `key` - день (`2026-10-19`),
+/// неделя ISO (`2026-W43`) или идентификатор проекта, `label` - его представление для человека.
+#[derive(Debug, Serialize)]
+pub struct TimesheetRow{
+    pub key: String,
+    pub label: String,
+    pub seconds: i64,
+    pub hours: f64,
+}
+
+/// Модель сущности спринта. `committed_points` и `completed_points` заполняются при закрытии спринта.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "sprints"]
+#[belongs_to(Project)]
+pub struct Sprint{
+    pub id: String,
+    pub project_id: String,
+    pub name: String,
+    pub start_date: chrono::NaiveDate,
+    pub end_date: chrono::NaiveDate,
+    pub goal: Option<String>,
+    pub closed_at: Option<chrono::NaiveDateTime>,
+    pub committed_points: Option<i32>,
+    pub completed_points: Option<i32>,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>
+}
+
+/// Вспомогательная модель. 
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+#[derive(Serialize,Deserialize)]
+pub struct NewSprint{
+    pub name: String,
+    pub start_date: chrono::NaiveDate,
+    pub end_date: chrono::NaiveDate,
+    #[serde(default)]
+    pub goal: Option<String>,
+}
+
+/// Задача, добавляемая в спринт.
+#[derive(Serialize,Deserialize)]
+pub struct SprintTask{
+    pub task_id: String,
+}
+
+/// Параметры закрытия спринта. Незавершённые задачи переносятся в спринт `carry_over_to`,
+/// а если он не задан - возвращаются в бэклог проекта.
+#[derive(Default, Serialize,Deserialize)]
+pub struct SprintClose{
+    #[serde(default)]
+    pub carry_over_to: Option<String>,
+}
+
+/// Результат закрытия спринта: закрытый спринт и перенесённые задачи.
+#[derive(Debug, Serialize)]
+pub struct SprintCloseResult{
+    pub sprint: Sprint,
+    pub carried_over: Vec<String>,
+}
+
+/// История скорости проекта по закрытым спринтам.
+/// `average_points` - среднее выполненных story points за последние спринты.
+#[derive(Debug, Serialize)]
+pub struct Velocity{
+    pub sprints: Vec<VelocityEntry>,
+    pub average_points: Option<f64>,
+}
+
+/// Скорость одного закрытого спринта.
+#[derive(Debug, Serialize)]
+pub struct VelocityEntry{
+    pub sprint_id: String,
+    pub name: String,
+    pub start_date: chrono::NaiveDate,
+    pub end_date: chrono::NaiveDate,
+    pub committed_points: i32,
+    pub completed_points: i32,
+}
+
+/// Модель записи истории смены статуса задачи.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "task_status_changes"]
+#[belongs_to(Task)]
+pub struct TaskStatusChange{
+    pub id: String,
+    pub task_id: String,
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub changed_at: chrono::NaiveDateTime
+}
+
+/// Модель записи истории изменений задачи.
+/// `changes` - изменённые поля в виде `{"поле": {"from": ..., "to": ...}}`,
+/// `snapshot` - состояние задачи после изменения, для удаления - перед ним.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "task_revisions"]
+pub struct TaskRevision{
+    pub id: String,
+    pub task_id: String,
+    pub action: String,
+    pub actor_id: Option<String>,
+    pub changes: serde_json::Value,
+    pub snapshot: serde_json::Value,
+    pub changed_at: chrono::NaiveDateTime
+}
+
+/// Параметры отчёта за период, передаваемые в строке запроса. Границы включаются в период.
+#[derive(Debug, Default, Deserialize)]
+pub struct ReportQuery{
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    /// Единица объёма работ для диаграмм сгорания: `points` (по умолчанию) или `tasks`.
+    pub unit: Option<String>,
+}
+
+/// Диаграмма сгорания: объём работ по дням в выбранных единицах.
+#[derive(Debug, Serialize)]
+pub struct BurnChart{
+    pub unit: String,
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    pub days: Vec<BurnPoint>,
+}
+
+/// Точка диаграммы сгорания на конец дня. `total` и `completed` образуют burnup,
+/// `remaining` и `ideal` - burndown.
+#[derive(Debug, Serialize)]
+pub struct BurnPoint{
+    pub date: chrono::NaiveDate,
+    pub total: i64,
+    pub completed: i64,
+    pub remaining: i64,
+    pub ideal: f64,
+}
+
+/// Данные накопительной диаграммы потока: количество задач в каждом статусе на конец дня.
+#[derive(Debug, Serialize)]
+pub struct CumulativeFlow{
+    pub statuses: Vec<String>,
+    pub days: Vec<CumulativeFlowDay>,
+}
+
+/// Количество задач проекта по статусам на конец дня.
+#[derive(Debug, Serialize)]
+pub struct CumulativeFlowDay{
+    pub date: chrono::NaiveDate,
+    pub counts: std::collections::BTreeMap<String, i64>,
+}
+
+/// Аналитика потока проекта за период: время выполнения задач, пропускная способность
+/// по неделям и незавершённая работа с её возрастом.
+#[derive(Debug, Serialize)]
+pub struct FlowAnalytics{
+    pub from: chrono::NaiveDate,
+    pub to: chrono::NaiveDate,
+    /// Время от создания задачи до её завершения.
+    pub lead_time: TimeDistribution,
+    /// Время от начала работы (`in_progress`) до завершения.
+    pub cycle_time: TimeDistribution,
+    pub throughput: Vec<WeeklyThroughput>,
+    pub aging_wip: Vec<AgingTask>,
+}
+
+/// Распределение длительностей в часах. Перцентили считаются методом ближайшего ранга,
+/// `histogram` - количество задач по целым дням длительности.
+#[derive(Debug, Serialize)]
+pub struct TimeDistribution{
+    pub count: usize,
+    pub mean_hours: Option<f64>,
+    pub min_hours: Option<f64>,
+    pub max_hours: Option<f64>,
+    pub percentiles: std::collections::BTreeMap<String, f64>,
+    pub histogram: Vec<HistogramBucket>,
+}
+
+/// Количество задач, выполненных за `days` полных дней.
+#[derive(Debug, Serialize)]
+pub struct HistogramBucket{
+    pub days: i64,
+    pub count: i64,
+}
+
+/// Количество задач, завершённых за неделю, начинающуюся в понедельник `week_start`.
+#[derive(Debug, Serialize)]
+pub struct WeeklyThroughput{
+    pub week_start: chrono::NaiveDate,
+    pub completed: i64,
+}
+
+/// Задача в работе и время, прошедшее с начала работы над ней.
+/// `over_p85` отмечает задачи, которые находятся в работе дольше 85-го перцентиля времени цикла.
+#[derive(Debug, Serialize)]
+pub struct AgingTask{
+    pub task_id: String,
+    pub title: String,
+    pub started_at: chrono::NaiveDateTime,
+    pub age_hours: f64,
+    pub over_p85: bool,
+}
+
+/// Параметры прогноза методом Монте-Карло, передаваемые в строке запроса.
+#[derive(Debug, Default, Deserialize)]
+pub struct ForecastQuery{
+    /// Количество оставшихся задач. По умолчанию - незавершённые задачи проекта.
+    pub remaining: Option<i64>,
+    /// Дата, для которой прогнозируется количество выполненных задач.
+    pub by: Option<chrono::NaiveDate>,
+    /// Зерно генератора случайных чисел. При одинаковом зерне и истории результат совпадает.
+    pub seed: Option<u64>,
+    pub iterations: Option<usize>,
+    /// Количество последних полных дней, по которым берётся пропускная способность.
+    pub history_days: Option<i64>,
+}
+
+/// Результат прогноза методом Монте-Карло.
+/// Ключи перцентилей означают вероятность: `p85` в `completion_dates` - дата, к которой
+/// задачи будут выполнены с вероятностью 85%, в `completed_by` - количество задач,
+/// которое будет выполнено не меньше чем с вероятностью 85%.
+#[derive(Debug, Serialize)]
+pub struct Forecast{
+    pub seed: u64,
+    pub iterations: usize,
+    pub history_from: chrono::NaiveDate,
+    pub history_to: chrono::NaiveDate,
+    pub completed_in_history: i64,
+    pub remaining: i64,
+    /// Дата выполнения оставшихся задач. None - задачи не выполняются за горизонт прогноза.
+    pub completion_dates: std::collections::BTreeMap<String, Option<chrono::NaiveDate>>,
+    pub by: Option<chrono::NaiveDate>,
+    pub completed_by: Option<std::collections::BTreeMap<String, i64>>,
+}
+
+/// Модель записи журнала аудита. `hash` - SHA-256 от содержимого записи и `prev_hash`,
+/// хэша предыдущей по `seq` записи.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "audit_log"]
+pub struct AuditEntry{
+    pub id: String,
+    pub seq: i64,
+    pub action: String,
+    pub actor_id: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub details: serde_json::Value,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub prev_hash: String,
+    pub hash: String
+}
+
+/// Событие для записи в журнал аудита.
+#[derive(Debug, Default)]
+pub struct AuditEvent{
+    pub action: String,
+    pub actor_id: Option<String>,
+    pub target_type: Option<String>,
+    pub target_id: Option<String>,
+    pub details: serde_json::Value,
+    pub ip: Option<String>,
+    pub user_agent: Option<String>,
+    pub request_id: Option<String>,
+}
+
+/// Фильтры журнала аудита, передаваемые в строке запроса. Границы периода включаются.
+#[derive(Debug, Default, Deserialize)]
+pub struct AuditQuery{
+    pub action: Option<String>,
+    pub actor_id: Option<String>,
+    pub target_id: Option<String>,
+    pub from: Option<chrono::NaiveDate>,
+    pub to: Option<chrono::NaiveDate>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+    /// Формат выгрузки: `csv` (по умолчанию) или `json`.
+    pub format: Option<String>,
+}
+
+/// Результат проверки цепочки хэшей журнала аудита.
+/// `broken_at` - номер первой записи, хэш которой не совпадает с содержимым или с предыдущей записью.
+#[derive(Debug, Serialize)]
+pub struct AuditVerification{
+    pub valid: bool,
+    pub checked: i64,
+    pub broken_at: Option<i64>,
+}
+
+/// Модель события об изменении данных, которое рассылается клиентам в реальном времени.
+/// Идентификаторы событий возрастают, клиент продолжает получение с последнего полученного.
+/// `payload` - состояние объекта после изменения (для удаления - перед ним) и изменённые поля.
+#[derive(Debug, Clone, Serialize, Queryable)]
+pub struct Event{
+    pub id: i64,
+    pub kind: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub project_id: Option<String>,
+    pub sprint_id: Option<String>,
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub payload: serde_json::Value,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Модель для создания события. Идентификатор назначается базой данных.
+#[derive(Debug, Insertable)]
+#[table_name = "events"]
+pub struct NewEvent{
+    pub kind: String,
+    pub entity_type: String,
+    pub entity_id: String,
+    pub project_id: Option<String>,
+    pub sprint_id: Option<String>,
+    pub user_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub payload: serde_json::Value,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Модель уведомления пользователя. Используется для работы ОРМ Diesel.
+/// `details` - сведения для отображения: название задачи, изменённые поля и т.п.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "notifications"]
+pub struct Notification{
+    pub id: String,
+    pub user_id: String,
+    pub kind: String,
+    pub task_id: Option<String>,
+    pub comment_id: Option<String>,
+    pub actor_id: Option<String>,
+    pub details: serde_json::Value,
+    pub read_at: Option<chrono::NaiveDateTime>,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Параметры списка уведомлений, передаваемые в строке запроса.
+/// `unread=true` оставляет только непрочитанные уведомления.
+#[derive(Debug, Default, Deserialize)]
+pub struct NotificationQuery{
+    pub unread: Option<bool>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
+
+/// Настройки пользователя, определяющие, о каких событиях создаются уведомления и как они отправляются по почте.
+/// Пока пользователь не изменял настройки, уведомления создаются обо всех событиях и сразу отправляются на русском языке.
+/// `email_mode` - `off`, `instant` (письмо о каждом назначении, упоминании и приближении срока) или
+/// `digest` (одна ежедневная сводка), `locale` - язык писем: `ru` или `en`.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, AsChangeset, Identifiable)]
+#[table_name = "notification_preferences"]
+#[primary_key(user_id)]
+pub struct NotificationPreferences{
+    pub user_id: String,
+    pub assigned: bool,
+    pub mentioned: bool,
+    pub task_changed: bool,
+    pub updated_at: chrono::NaiveDateTime,
+    pub email_mode: String,
+    pub locale: String,
+    pub due_soon: bool,
+    #[serde(skip_serializing)]
+    pub digest_sent_at: Option<chrono::NaiveDateTime>
+}
+
+/// Вспомогательная модель для изменения настроек уведомлений. Непереданные настройки не меняются.
+#[derive(Debug, Deserialize)]
+pub struct NotificationPreferencesUpdate{
+    pub assigned: Option<bool>,
+    pub mentioned: Option<bool>,
+    pub task_changed: Option<bool>,
+    pub email_mode: Option<String>,
+    pub locale: Option<String>,
+    pub due_soon: Option<bool>,
+}
+
+/// Письмо в очереди отправки. Письмо добавляется в очередь в той же транзакции, что и изменение,
+/// о котором оно сообщает, поэтому отправляются только письма о сохранённых изменениях.
+/// `status` - `pending`, `sending`, `sent` или `failed`, `dedupe_key` не даёт поставить одно письмо в очередь дважды.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "email_outbox"]
+pub struct OutboxEmail{
+    pub id: String,
+    pub user_id: Option<String>,
+    pub recipient: String,
+    pub kind: String,
+    pub subject: String,
+    pub body: String,
+    pub dedupe_key: Option<String>,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub last_error: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub sent_at: Option<chrono::NaiveDateTime>
+}
+
+/// Модель наблюдателя задачи (таблица task_watchers).
+/// Наблюдатели получают уведомления об изменениях задачи.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "task_watchers"]
+#[primary_key(task_id, user_id)]
+#[belongs_to(Task)]
+#[belongs_to(User)]
+pub struct TaskWatcher{
+    pub task_id: String,
+    pub user_id: String,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Наблюдатель задачи вместе с именем пользователя.
+#[derive(Debug, Serialize, Queryable)]
+pub struct WatcherView{
+    pub user_id: String,
+    pub user_name: String,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Модель исполнителя задачи (таблица task_assignees).
+/// `role` - `owner`, `reviewer` или `contributor`. Поле `user_id` задачи хранит основного исполнителя,
+/// который всегда является одним из исполнителей с ролью `owner`.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable, Associations)]
+#[table_name = "task_assignees"]
+#[primary_key(task_id, user_id)]
+#[belongs_to(Task)]
+#[belongs_to(User)]
+pub struct TaskAssignee{
+    pub task_id: String,
+    pub user_id: String,
+    pub role: String,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Исполнитель задачи вместе с именем пользователя.
+/// `primary` - исполнитель является основным исполнителем задачи.
+#[derive(Debug, Serialize)]
+pub struct AssigneeView{
+    pub user_id: String,
+    pub user_name: String,
+    pub role: String,
+    pub primary: bool,
+    pub created_at: chrono::NaiveDateTime
+}
+
+/// Вспомогательная модель.
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+/// Если роль не указана, исполнитель добавляется с ролью `contributor`.
+#[derive(Serialize,Deserialize)]
+pub struct NewAssignee{
+    pub user_id: String,
+    #[serde(default)]
+    pub role: Option<String>,
+}
+
+/// Параметры списка задач текущего пользователя, передаваемые в строке запроса.
+/// `timezone` - часовой пояс IANA, в котором определяется сегодняшний день (по умолчанию UTC),
+/// `days` - за сколько дней показываются недавно изменённые задачи, за которыми наблюдает пользователь.
+#[derive(Debug, Default, Deserialize)]
+pub struct MyTasksQuery{
+    pub timezone: Option<String>,
+    pub days: Option<i64>,
+}
+
+/// Количество задач в группах списка задач текущего пользователя.
+#[derive(Debug, Serialize)]
+pub struct MyTaskCounts{
+    pub overdue: usize,
+    pub due_today: usize,
+    pub upcoming: usize,
+    pub no_date: usize,
+    pub watching: usize,
+}
+
+/// Незавершённые задачи, в которых пользователь является исполнителем, сгруппированные по сроку,
+/// и недавно изменённые задачи, за которыми он наблюдает, не будучи исполнителем.
+#[derive(Debug, Serialize)]
+pub struct MyTasks{
+    pub overdue: Vec<TaskView>,
+    pub due_today: Vec<TaskView>,
+    pub upcoming: Vec<TaskView>,
+    pub no_date: Vec<TaskView>,
+    pub watching: Vec<TaskView>,
+    pub counts: MyTaskCounts,
+}
+
+/// Подписка проекта на события (таблица webhooks). События отправляются POST-запросом на `url`,
+/// время отправки и тело запроса подписываются ключом `secret`, который не отдаётся клиенту.
+/// `event_types` - типы событий, например `task.created`, или `*` для всех событий.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "webhooks"]
+pub struct Webhook{
+    pub id: String,
+    pub project_id: String,
+    pub url: String,
+    #[serde(skip_serializing)]
+    pub secret: String,
+    pub event_types: Vec<String>,
+    pub active: bool,
+    pub created_at: chrono::NaiveDateTime,
+    pub updated_at: Option<chrono::NaiveDateTime>
+}
+
+/// Вспомогательная модель.
+/// Используется в качестве шаблона для десериализации данных, отправленных с бэкенда.
+#[derive(Serialize,Deserialize)]
+pub struct NewWebhook{
+    pub url: String,
+    pub secret: String,
+    pub event_types: Vec<String>,
+    #[serde(default)]
+    pub active: Option<bool>,
+}
+
+/// Изменяемые поля подписки. Неуказанные поля не меняются.
+#[derive(Debug, Default, Deserialize)]
+pub struct WebhookUpdate{
+    pub url: Option<String>,
+    pub secret: Option<String>,
+    pub event_types: Option<Vec<String>>,
+    pub active: Option<bool>,
+}
+
+/// Отправка события по подписке (таблица webhook_deliveries).
+/// `status` - `pending`, `sending`, `delivered` или `failed`, `response_status` и `response_body` - ответ на последнюю попытку,
+/// `redelivery_of` - исходная отправка, если отправка создана повторно по запросу.
+#[derive(Debug, Serialize, Deserialize, Queryable, Insertable, Identifiable)]
+#[table_name = "webhook_deliveries"]
+pub struct WebhookDelivery{
+    pub id: String,
+    pub webhook_id: String,
+    pub event_id: i64,
+    pub event_type: String,
+    pub payload: serde_json::Value,
+    pub status: String,
+    pub attempts: i32,
+    pub next_attempt_at: chrono::NaiveDateTime,
+    pub response_status: Option<i32>,
+    pub response_body: Option<String>,
+    pub last_error: Option<String>,
+    pub redelivery_of: Option<String>,
+    pub created_at: chrono::NaiveDateTime,
+    pub delivered_at: Option<chrono::NaiveDateTime>
+}
+
+/// Параметры журнала отправок, передаваемые в строке запроса.
+/// `status` оставляет только отправки в указанном состоянии.
+#[derive(Debug, Default, Deserialize)]
+pub struct DeliveryQuery{
+    pub status: Option<String>,
+    pub page: Option<i64>,
+    pub per_page: Option<i64>,
+}
@@ -0,0 +1,98 @@
+use postgres::fallible_iterator::FallibleIterator;
+use postgres::{Client, NoTls};
+use std::time::{Duration, Instant};
+
+use super::EventError;
+
+/// Канал, в который база данных сообщает о новых событиях
+pub const EVENTS_CHANNEL: &str = "events";
+
+/// Интервал опроса базы данных, пока нет подключения для получения уведомлений
+const DISCONNECTED_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Время ответа, за которое подключение считается рабочим
+const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Начальная задержка перед повторным подключением
+const MIN_RECONNECT_DELAY: Duration = Duration::from_secs(1);
+
+/// Максимальная задержка перед повторным подключением
+const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// Отдельное подключение к базе данных, ожидающее уведомлений о новых событиях.
+/// Уведомления отправляются при завершении транзакции, записавшей событие, поэтому
+/// событие, записанное любым экземпляром сервера, становится видно всем экземплярам.
+/// При потере подключения повторное подключение выполняется с нарастающей задержкой,
+/// а до его восстановления база данных опрашивается с коротким интервалом.
+pub struct Listener{
+    url: String,
+    client: Option<Client>,
+    reconnect_at: Instant,
+    reconnect_delay: Duration,
+}
+
+impl Listener{
+    /// Метод, создающий слушателя. Подключение выполняется при первом ожидании.
+    /// # Arguments
+    ///
+    /// * `url`         - URL базы данных.
+    pub fn new(url: String) -> Self{
+        Listener{ url, client: None, reconnect_at: Instant::now(), reconnect_delay: MIN_RECONNECT_DELAY }
+    }
+
+    /// Метод, ожидающий уведомления о новом событии не дольше указанного времени.
+    /// Без подключения ожидание сокращается до интервала опроса, а сразу после подключения
+    /// ожидание не выполняется, чтобы загрузить события, записанные без него.
+    /// # Arguments
+    ///
+    /// * `timeout`     - максимальное время ожидания.
+    pub fn wait(&mut self, timeout: Duration){
+        if self.client.is_none() && Instant::now() >= self.reconnect_at {
+            match self.connect() {
+                Ok(client) => {
+                    log::info!("Listening for events on channel {}", EVENTS_CHANNEL);
+                    self.client = Some(client);
+                    self.reconnect_delay = MIN_RECONNECT_DELAY;
+                    return;
+                },
+                Err(err) => self.disconnect(err),
+            }
+        }
+        let result = match self.client.as_mut() {
+            Some(client) => receive(client, timeout),
+            None => {
+                std::thread::sleep(timeout.min(DISCONNECTED_POLL_INTERVAL));
+                return;
+            },
+        };
+        if let Err(err) = result {
+            self.disconnect(Box::new(err));
+        }
+    }
+
+    /// Метод, подключающийся к базе данных и подписывающийся на канал событий.
+    fn connect(&self) -> Result<Client, EventError>{
+        let mut client = Client::connect(&self.url, NoTls)?;
+        client.batch_execute(&format!("LISTEN {}", EVENTS_CHANNEL))?;
+        Ok(client)
+    }
+
+    /// Метод, закрывающий потерянное подключение и откладывающий повторное.
+    fn disconnect(&mut self, err: EventError){
+        log::warn!("Event notifications unavailable, retrying in {:?}: {}", self.reconnect_delay, err);
+        self.client = None;
+        self.reconnect_at = Instant::now() + self.reconnect_delay;
+        self.reconnect_delay = (self.reconnect_delay * 2).min(MAX_RECONNECT_DELAY);
+    }
+}
+
+/// Метод, ожидающий уведомлений на подключении не дольше указанного времени.
+fn receive(client: &mut Client, timeout: Duration) -> Result<(), postgres::Error>{
+    let received = client.notifications().timeout_iter(timeout).next()?;
+    match received {
+        // Уведомления о нескольких событиях обрабатываются одним чтением базы данных
+        Some(_) => client.notifications().iter().count().map(|_| ()),
+        // Без уведомлений подключение могло оборваться незаметно
+        None => client.is_valid(HEALTH_CHECK_TIMEOUT),
+    }
+}
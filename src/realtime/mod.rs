@@ -0,0 +1,158 @@
+mod listener;
+pub mod sse;
+pub mod ws;
+
+use std::sync::atomic::{AtomicI64, Ordering};
+use std::sync::Arc;
+use actix_web::web;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast;
+
+use crate::controllers::events;
+use crate::database::DbPool;
+use crate::models::Event;
+use listener::Listener;
+
+/// Тип ошибок, возникающих при рассылке событий
+pub type EventError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Публичный тип разделяемого источника событий
+pub type SharedHub = Arc<EventHub>;
+
+/// Интервал, с которым новые события проверяются без уведомлений на случай их потери
+const FALLBACK_POLL_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Интервал повторной проверки, пока ожидается завершение транзакции с пропущенным идентификатором
+const GAP_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Количество событий, загружаемых за один запрос к базе данных
+const POLL_BATCH: i64 = 500;
+
+/// Время, в течение которого ожидается завершение транзакции с пропущенным идентификатором события.
+/// Идентификаторы назначаются при вставке, и транзакция с меньшим идентификатором может завершиться позже.
+const GAP_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Количество событий, которые могут ожидать отправки медленному подписчику
+const CHANNEL_CAPACITY: usize = 1024;
+
+/// Срок хранения событий в базе данных
+const RETENTION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// Интервал удаления устаревших событий
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+
+/// Источник событий для подписчиков в реальном времени.
+/// События читаются из базы данных по возрастанию идентификатора после уведомления о новых событиях
+/// и рассылаются всем подписчикам, каждый подписчик отбирает интересующие его события сам.
+/// Так событие, записанное любым экземпляром сервера, доходит до подписчиков всех экземпляров.
+pub struct EventHub{
+    sender: broadcast::Sender<Arc<Event>>,
+    position: AtomicI64,
+}
+
+impl EventHub{
+    /// Метод, подписывающий на события, разосланные после вызова.
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<Event>>{
+        self.sender.subscribe()
+    }
+
+    /// Метод, возвращающий идентификатор последнего разосланного события.
+    pub fn position(&self) -> i64{
+        self.position.load(Ordering::SeqCst)
+    }
+
+    /// Метод, загружающий из базы данных и рассылающий новые события.
+    /// Возвращает true, если рассылка остановлена в ожидании события с пропущенным идентификатором.
+    fn poll(&self, pool: &DbPool) -> Result<bool, EventError>{
+        let conn = pool.get()?;
+        loop {
+            let batch = events::get_events_after(self.position(), i64::MAX, POLL_BATCH, &conn)?;
+            let complete = (batch.len() as i64) < POLL_BATCH;
+            let now = chrono::Utc::now().naive_utc();
+            for event in batch {
+                let waited = (now - event.created_at).to_std().unwrap_or_default();
+                if event.id > self.position() + 1 && waited < GAP_TIMEOUT {
+                    return Ok(true);
+                }
+                self.position.store(event.id, Ordering::SeqCst);
+                // Ошибка означает, что подписчиков нет
+                let _ = self.sender.send(Arc::new(event));
+            }
+            if complete {
+                return Ok(false);
+            }
+        }
+    }
+
+    /// Метод, удаляющий события старше срока хранения.
+    fn prune(&self, pool: &DbPool) -> Result<(), EventError>{
+        let conn = pool.get()?;
+        let before = chrono::Utc::now().naive_utc() - chrono::Duration::from_std(RETENTION)?;
+        let deleted = events::prune_events(before, &conn)?;
+        if deleted > 0 {
+            log::info!("Pruned {} events", deleted);
+        }
+        Ok(())
+    }
+
+    /// Метод, в цикле рассылающий новые события. Выполняется в отдельном потоке.
+    fn run(&self, pool: DbPool, mut listener: Listener){
+        let mut pruned_at: Option<Instant> = None;
+        loop {
+            let pending = self.poll(&pool).unwrap_or_else(|err| {
+                log::error!("Failed to dispatch events: {}", err);
+                false
+            });
+            if pruned_at.is_none_or(|pruned_at| pruned_at.elapsed() >= PRUNE_INTERVAL) {
+                if let Err(err) = self.prune(&pool) {
+                    log::error!("Failed to prune events: {}", err);
+                }
+                pruned_at = Some(Instant::now());
+            }
+            listener.wait(if pending { GAP_POLL_INTERVAL } else { FALLBACK_POLL_INTERVAL });
+        }
+    }
+}
+
+/// Метод, загружающий события, пропущенные подписчиком после указанного, до последнего разосланного.
+/// # Arguments
+///
+/// * `hub`         - источник событий.
+/// * `pool`        - пул базы данных.
+/// * `after`       - идентификатор последнего полученного подписчиком события.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо идентификатор последнего
+/// разосланного события и пропущенные события. Если пропущенные события получить нельзя, вместо них возвращается None.
+pub async fn load_missed(hub: &EventHub, pool: &DbPool, after: i64) -> Result<(i64, Option<Vec<Event>>), EventError>{
+    let up_to = hub.position();
+    let pool = pool.clone();
+    let missed = web::block(move || {
+        let conn = pool.get()?;
+        events::get_missed_events(after, up_to, &conn)
+    })
+    .await??;
+    Ok((up_to, missed))
+}
+
+/// Метод инициализации источника событий.
+/// Рассылка начинается с событий, записанных после запуска.
+/// Уведомления о новых событиях получаются по URL базы данных из .env файла.
+pub fn init_hub(pool: DbPool) -> SharedHub{
+    let listener = Listener::new(std::env::var("DATABASE_URL").expect("DATABASE_URL"));
+    let position = pool
+        .get()
+        .map_err(EventError::from)
+        .and_then(|conn| events::get_last_event_id(&conn))
+        .expect("Failed to load last event id");
+    let (sender, _) = broadcast::channel(CHANNEL_CAPACITY);
+    let hub = Arc::new(EventHub{ sender, position: AtomicI64::new(position) });
+
+    let dispatcher = hub.clone();
+    std::thread::Builder::new()
+        .name("event-dispatcher".to_string())
+        .spawn(move || dispatcher.run(pool, listener))
+        .expect("Failed to start event dispatcher");
+    hub
+}
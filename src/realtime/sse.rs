@@ -0,0 +1,171 @@
+//! Поток событий в формате Server-Sent Events.
+//!
+//! Каждое событие отправляется с полями `id` (идентификатор события), `event` (тип, например
+//! `task.updated`) и `data` (событие в JSON). Если пропущенные после `Last-Event-ID` события
+//! получить нельзя, отправляется событие `reset`, после которого данные нужно загрузить заново.
+
+use actix_web::web::Bytes;
+use actix_web::HttpResponse;
+use std::collections::{HashSet, VecDeque};
+use std::time::Duration;
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use std::sync::Arc;
+
+use crate::database::DbPool;
+use crate::models::Event;
+use super::{EventError, SharedHub};
+
+/// Интервал, с которым клиенту отправляется пульс
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Задержка перед переподключением, которую сервер предлагает клиенту, в миллисекундах
+const RETRY_MS: u64 = 3_000;
+
+/// Фильтр событий потока. Пустой список не ограничивает события.
+#[derive(Debug, Default)]
+pub struct EventFilter{
+    projects: HashSet<String>,
+    users: HashSet<String>,
+}
+
+impl EventFilter{
+    /// Метод, создающий фильтр из списков идентификаторов через запятую.
+    /// # Arguments
+    ///
+    /// * `projects`    - идентификаторы проектов.
+    /// * `users`       - идентификаторы пользователей: исполнителей задач или изменённых пользователей.
+    pub fn new(projects: Option<&str>, users: Option<&str>) -> Self{
+        let split = |list: Option<&str>| list
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(str::to_string)
+            .collect();
+        EventFilter{ projects: split(projects), users: split(users) }
+    }
+
+    /// Метод, проверяющий, проходит ли событие фильтр.
+    fn matches(&self, event: &Event) -> bool{
+        let allowed = |ids: &HashSet<String>, id: &Option<String>| ids.is_empty()
+            || id.as_ref().is_some_and(|id| ids.contains(id));
+        allowed(&self.projects, &event.project_id) && allowed(&self.users, &event.user_id)
+    }
+}
+
+/// Состояние потока одного клиента
+struct EventStream{
+    hub: SharedHub,
+    pool: DbPool,
+    receiver: Receiver<Arc<Event>>,
+    filter: EventFilter,
+    heartbeat: actix_web::rt::time::Interval,
+    /// Сообщения, ожидающие отправки
+    backlog: VecDeque<Bytes>,
+    /// Идентификатор последнего события, обработанного потоком
+    last_event_id: i64,
+}
+
+impl EventStream{
+    /// Метод, ставящий событие в очередь отправки, если оно ещё не обработано и проходит фильтр.
+    fn deliver(&mut self, event: &Event) -> Result<(), EventError>{
+        if event.id <= self.last_event_id {
+            return Ok(());
+        }
+        self.last_event_id = event.id;
+        if self.filter.matches(event) {
+            let data = serde_json::to_string(event)?;
+            self.backlog.push_back(Bytes::from(format!("id: {}\nevent: {}\ndata: {}\n\n", event.id, event.kind, data)));
+        }
+        Ok(())
+    }
+
+    /// Метод, ставящий в очередь события, пропущенные после указанного, или событие `reset`.
+    async fn resume(&mut self, after: i64) -> Result<(), EventError>{
+        let (up_to, missed) = super::load_missed(&self.hub, &self.pool, after).await?;
+        match missed {
+            Some(missed) => {
+                self.last_event_id = after.min(self.last_event_id);
+                for event in &missed {
+                    self.deliver(event)?;
+                }
+                self.backlog.push_back(Bytes::from(format!("id: {}\n\n", self.last_event_id.max(up_to))));
+            },
+            None => self.backlog.push_back(Bytes::from(format!(
+                "id: {}\nevent: reset\ndata: {{\"last_event_id\":{}}}\n\n", up_to, up_to
+            ))),
+        }
+        self.last_event_id = self.last_event_id.max(up_to);
+        Ok(())
+    }
+
+    /// Метод, возвращающий следующую часть потока. None завершает поток.
+    async fn next(&mut self) -> Option<Bytes>{
+        loop {
+            if let Some(chunk) = self.backlog.pop_front() {
+                return Some(chunk);
+            }
+            let result = tokio::select! {
+                _ = self.heartbeat.tick() => {
+                    // Пульс сообщает последнее обработанное событие, чтобы продолжение
+                    // не устаревало, когда подходящие под фильтр события редки
+                    return Some(Bytes::from(format!(": ping\nid: {}\n\n", self.last_event_id)));
+                },
+                event = self.receiver.recv() => match event {
+                    Ok(event) => self.deliver(&event),
+                    // Поток не успевал получать события, пропущенные загружаются из базы данных
+                    Err(RecvError::Lagged(_)) => {
+                        let after = self.last_event_id;
+                        self.resume(after).await
+                    },
+                    Err(RecvError::Closed) => return None,
+                },
+            };
+            if let Err(err) = result {
+                log::warn!("Closing event stream: {}", err);
+                return None;
+            }
+        }
+    }
+}
+
+/// Метод, открывающий поток событий.
+/// # Arguments
+///
+/// * `hub`             - источник событий.
+/// * `pool`            - пул базы данных для получения пропущенных событий.
+/// * `filter`          - фильтр событий.
+/// * `last_event_id`   - идентификатор последнего полученного клиентом события, если клиент переподключается.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо потоковый ответ `text/event-stream`.
+pub async fn open(hub: SharedHub, pool: DbPool, filter: EventFilter, last_event_id: Option<i64>) -> Result<HttpResponse, EventError>{
+    // Подписка оформляется до чтения позиции, чтобы не потерять события между ними
+    let receiver = hub.subscribe();
+    let position = hub.position();
+    let mut heartbeat = actix_web::rt::time::interval(HEARTBEAT_INTERVAL);
+    heartbeat.reset();
+    let mut stream = EventStream{
+        hub,
+        pool,
+        receiver,
+        filter,
+        heartbeat,
+        backlog: VecDeque::from([Bytes::from(format!("retry: {}\n\n", RETRY_MS))]),
+        last_event_id: position,
+    };
+    match last_event_id {
+        Some(after) => stream.resume(after).await?,
+        None => stream.backlog.push_back(Bytes::from(format!("id: {}\n\n", position))),
+    }
+
+    let body = futures_util::stream::unfold(stream, |mut stream| async move {
+        stream.next().await.map(|chunk| (Ok::<_, actix_web::Error>(chunk), stream))
+    });
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .insert_header(("X-Accel-Buffering", "no"))
+        .streaming(body))
+}
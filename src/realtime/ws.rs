@@ -0,0 +1,224 @@
+//! Подписка на события через WebSocket.
+//!
+//! Клиент отправляет JSON-сообщения с полем `type`:
+//! * `{"type": "subscribe", "topic": "project" | "board" | "task", "id": "..."}` - подписка на события
+//!   проекта, доски (спринта) или задачи;
+//! * `{"type": "unsubscribe", "topic": ..., "id": ...}` - отмена подписки;
+//! * `{"type": "resume", "last_event_id": 42}` - получение событий по текущим подпискам,
+//!   пропущенных после указанного, например после переподключения;
+//! * `{"type": "ping"}` - проверка соединения, сервер отвечает `{"type": "pong"}`.
+//!
+//! Сервер отправляет `welcome` с идентификатором последнего события при подключении,
+//! `subscribed`/`unsubscribed`, `event` для каждого события, `reset`, если пропущенные события
+//! получить нельзя и данные нужно загрузить заново, и `error` для некорректных сообщений.
+
+use actix_web::{web, HttpRequest, HttpResponse};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use uuid::Uuid;
+
+use crate::controllers::events;
+use crate::database::DbPool;
+use crate::models::Event;
+use super::{EventError, SharedHub};
+
+/// Интервал, с которым сервер отправляет клиенту ping
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(15);
+
+/// Время без сообщений от клиента, после которого соединение закрывается
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(45);
+
+/// Объект подписки. Доской считается спринт.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum Topic{
+    Project,
+    Board,
+    Task,
+}
+
+/// Сообщение клиента
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ClientMessage{
+    Subscribe{ topic: Topic, id: Uuid },
+    Unsubscribe{ topic: Topic, id: Uuid },
+    Resume{ last_event_id: i64 },
+    Ping,
+}
+
+/// Сообщение сервера
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ServerMessage<'a>{
+    Welcome{ user_id: &'a str, last_event_id: i64 },
+    Subscribed{ topic: Topic, id: Uuid },
+    Unsubscribed{ topic: Topic, id: Uuid },
+    Event(&'a Event),
+    Reset{ last_event_id: i64 },
+    Pong,
+    Error{ message: String },
+}
+
+/// Подписки соединения
+#[derive(Debug, Default)]
+struct Subscriptions{
+    topics: HashSet<(Topic, String)>,
+}
+
+impl Subscriptions{
+    /// Метод, проверяющий, относится ли событие к одной из подписок.
+    fn matches(&self, event: &Event) -> bool{
+        let has = |topic: Topic, id: &Option<String>| id
+            .as_ref()
+            .is_some_and(|id| self.topics.contains(&(topic, id.clone())));
+        has(Topic::Project, &event.project_id)
+            || has(Topic::Board, &event.sprint_id)
+            || (event.entity_type == events::ENTITY_TASK && self.topics.contains(&(Topic::Task, event.entity_id.clone())))
+    }
+}
+
+/// Соединение с клиентом
+struct Connection{
+    session: Session,
+    hub: SharedHub,
+    pool: DbPool,
+    subscriptions: Subscriptions,
+    /// Идентификатор последнего события, обработанного соединением
+    last_event_id: i64,
+}
+
+impl Connection{
+    /// Метод, отправляющий сообщение клиенту.
+    async fn send(&mut self, message: &ServerMessage<'_>) -> Result<(), EventError>{
+        let text = serde_json::to_string(message)?;
+        self.session.text(text).await?;
+        Ok(())
+    }
+
+    /// Метод, отправляющий клиенту событие, если оно ещё не обработано и относится к подпискам.
+    async fn deliver(&mut self, event: &Event) -> Result<(), EventError>{
+        if event.id <= self.last_event_id {
+            return Ok(());
+        }
+        self.last_event_id = event.id;
+        if self.subscriptions.matches(event) {
+            self.send(&ServerMessage::Event(event)).await?;
+        }
+        Ok(())
+    }
+
+    /// Метод, отправляющий клиенту события по подпискам, записанные после указанного.
+    /// Если пропущенные события получить нельзя, клиенту отправляется `reset`.
+    async fn resume(&mut self, after: i64) -> Result<(), EventError>{
+        let (up_to, missed) = super::load_missed(&self.hub, &self.pool, after).await?;
+        match missed {
+            Some(missed) => {
+                self.last_event_id = after.min(self.last_event_id);
+                for event in &missed {
+                    self.deliver(event).await?;
+                }
+            },
+            None => self.send(&ServerMessage::Reset{ last_event_id: up_to }).await?,
+        }
+        self.last_event_id = self.last_event_id.max(up_to);
+        Ok(())
+    }
+
+    /// Метод, обрабатывающий текстовое сообщение клиента.
+    async fn handle(&mut self, text: &str) -> Result<(), EventError>{
+        let message = match serde_json::from_str::<ClientMessage>(text) {
+            Ok(message) => message,
+            Err(err) => return self.send(&ServerMessage::Error{ message: format!("Invalid message: {}", err) }).await,
+        };
+        match message {
+            ClientMessage::Subscribe{ topic, id } => {
+                self.subscriptions.topics.insert((topic, id.to_string()));
+                self.send(&ServerMessage::Subscribed{ topic, id }).await
+            },
+            ClientMessage::Unsubscribe{ topic, id } => {
+                self.subscriptions.topics.remove(&(topic, id.to_string()));
+                self.send(&ServerMessage::Unsubscribed{ topic, id }).await
+            },
+            ClientMessage::Resume{ last_event_id } => self.resume(last_event_id).await,
+            ClientMessage::Ping => self.send(&ServerMessage::Pong).await,
+        }
+    }
+}
+
+/// Метод, устанавливающий WebSocket-соединение с проверенным пользователем.
+/// Обмен сообщениями выполняется в отдельной задаче до закрытия соединения.
+/// # Arguments
+///
+/// * `req`         - запрос на подключение.
+/// * `body`        - поток данных соединения.
+/// * `hub`         - источник событий.
+/// * `pool`        - пул базы данных для получения пропущенных событий.
+/// * `user_id`     - идентификатор подключившегося пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо ответ на подключение.
+pub fn connect(req: &HttpRequest, body: web::Payload, hub: SharedHub, pool: DbPool, user_id: String) -> Result<HttpResponse, actix_web::Error>{
+    let (response, session, stream) = actix_ws::handle(req, body)?;
+    // Подписка оформляется до чтения позиции, чтобы не потерять события между ними
+    let receiver = hub.subscribe();
+    let last_event_id = hub.position();
+    let connection = Connection{ session, hub, pool, subscriptions: Subscriptions::default(), last_event_id };
+    actix_web::rt::spawn(run(connection, stream, receiver, user_id));
+    Ok(response)
+}
+
+/// Метод, обрабатывающий сообщения клиента, события и проверку соединения до его закрытия.
+async fn run(mut connection: Connection, mut stream: MessageStream, mut receiver: Receiver<Arc<Event>>, user_id: String){
+    let mut heartbeat = actix_web::rt::time::interval(HEARTBEAT_INTERVAL);
+    let mut last_seen = Instant::now();
+    let welcome = ServerMessage::Welcome{ user_id: &user_id, last_event_id: connection.last_event_id };
+    if connection.send(&welcome).await.is_err() {
+        return;
+    }
+
+    let reason = loop {
+        let result = tokio::select! {
+            _ = heartbeat.tick() => {
+                if last_seen.elapsed() > CLIENT_TIMEOUT {
+                    break Some(CloseReason{ code: CloseCode::Away, description: Some("Heartbeat timeout".to_string()) });
+                }
+                connection.session.ping(b"").await.map_err(EventError::from)
+            },
+            message = stream.next() => {
+                last_seen = Instant::now();
+                match message {
+                    Some(Ok(Message::Text(text))) => connection.handle(&text).await,
+                    Some(Ok(Message::Ping(bytes))) => connection.session.pong(&bytes).await.map_err(EventError::from),
+                    Some(Ok(Message::Close(reason))) => break reason,
+                    Some(Ok(_)) => Ok(()),
+                    Some(Err(err)) => {
+                        log::warn!("WebSocket protocol error for user {}: {}", user_id, err);
+                        break Some(CloseCode::Protocol.into());
+                    },
+                    None => break None,
+                }
+            },
+            event = receiver.recv() => match event {
+                Ok(event) => connection.deliver(&event).await,
+                // Соединение не успевало получать события, пропущенные загружаются из базы данных
+                Err(RecvError::Lagged(_)) => {
+                    let after = connection.last_event_id;
+                    connection.resume(after).await
+                },
+                Err(RecvError::Closed) => break Some(CloseCode::Restart.into()),
+            },
+        };
+        if let Err(err) = result {
+            log::warn!("Closing WebSocket for user {}: {}", user_id, err);
+            break None;
+        }
+    };
+    let _ = connection.session.close(reason).await;
+}
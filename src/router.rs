@@ -1,25 +1,55 @@
-use crate::{database::DbPool, models::NewTask, models::NewUser};
-use actix_web::{HttpResponse, Error, web, get, post, delete, put};
-use crate::controllers;
+use crate::{database::DbPool, models, models::NewTask, models::NewUser};
+use crate::models::{
+    AuditQuery, ChecklistOrder, Credentials, DeliveryQuery, ForecastQuery, LabelMerge, MyTasksQuery, NewAssignee, NewChecklistItem, NewComment, NewLabel, NewProject, NewTaskLink, OnParentDelete,
+    NewSprint, NewWebhook, NewWorklog, NotificationPreferencesUpdate, NotificationQuery, OccurrencesQuery, PageQuery, RecurrenceEdit, ReportQuery, SprintClose, SprintTask, TaskFilter,
+    TimerStart, TimesheetQuery, UploadedFile, WebhookUpdate
+};
+use crate::auth::{Actor, RequestMeta};
+use crate::realtime::{self, SharedHub};
+use crate::storage::SharedStorage;
+use actix_multipart::Multipart;
+use actix_web::{HttpRequest, HttpResponse, Error, web, get, post, delete, put};
+use actix_web::http::header::{self, ContentDisposition, DispositionParam, DispositionType};
+use crate::controllers::{self, ClientError};
+use futures_util::{Stream, TryStreamExt};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::io::{Read, Write};
 use uuid::Uuid;
 
+/// Метод, преобразующий ошибку контроллера в ответ сервера.
+/// Ошибки клиента отдаются с кодом 4xx, остальные ошибки - с кодом 500.
+fn map_error(err: Box<dyn std::error::Error + Send + Sync>) -> Error{
+    match err.downcast_ref::<ClientError>() {
+        Some(ClientError::BadRequest(message)) => actix_web::error::ErrorBadRequest(message.clone()),
+        Some(ClientError::Unauthorized(message)) => actix_web::error::ErrorUnauthorized(message.clone()),
+        Some(ClientError::Forbidden(message)) => actix_web::error::ErrorForbidden(message.clone()),
+        Some(ClientError::Conflict(message)) => actix_web::error::ErrorConflict(message.clone()),
+        Some(ClientError::PayloadTooLarge(message)) => actix_web::error::ErrorPayloadTooLarge(message.clone()),
+        None => actix_web::error::ErrorInternalServerError(err),
+    }
+}
+
 /// Метод, обрабатывающий GET запрос.
 /// # Arguments
 ///
-/// * `pool` - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `pool`   - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`  - Пользователь, выполняющий запрос, если он указан. Нужен для фильтра `assignee=me`.
+/// * `filter` - Параметры фильтрации: проект, метки (`labels=a,b`) и режим их сопоставления (`label_match=any|all`),
+///              исполнитель (`assignee`) и его роль (`assignee_role`).
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор заданий.
 
 #[get("/")]
-async fn get_tasks(pool: web::Data<DbPool>) -> Result<HttpResponse, Error>{
+async fn get_tasks(pool: web::Data<DbPool>, actor: Option<Actor>, filter: web::Query<TaskFilter>) -> Result<HttpResponse, Error>{
     let tasks = web::block(move ||{
         let conn = pool.get()?;
-        controllers::tasks::get_tasks(&conn)
+        controllers::tasks::get_tasks(&filter, actor.as_ref().map(|actor| actor.user_id.as_str()), &conn)
     })
     .await?
-    .map_err(actix_web::error::ErrorInternalServerError)?;
+    .map_err(map_error)?;
     if let Some(tasks) = tasks {
         Ok(HttpResponse::Ok().json(tasks))
     } else {
@@ -44,7 +74,7 @@ async fn get_task(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<
     let task_uid = task_uid.into_inner();
     let task = web::block(move || {
         let conn = pool.get()?;
-        controllers::tasks::get_task(&task_uid, &conn)
+        controllers::tasks::get_task_view(&task_uid, &conn)
     })
     .await?
     .map_err(actix_web::error::ErrorInternalServerError)?;
@@ -60,6 +90,7 @@ async fn get_task(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<
 /// # Arguments
 ///
 /// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задачи.
 /// * `new_task`    - Структура данных типа new_task, необходимая для создания объекта сущности task.
 ///
 /// # Return
@@ -67,36 +98,54 @@ async fn get_task(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<
 /// Возвращает Результат с ответом, содержащим либо ошибку, объект задачи.
 
 #[post("/task")]
-async fn add_task(pool: web::Data<DbPool>, new_task: web::Json<NewTask>) -> Result<HttpResponse, Error>{
+async fn add_task(pool: web::Data<DbPool>, actor: Option<Actor>, new_task: web::Json<NewTask>) -> Result<HttpResponse, Error>{
+    let actor = actor.map(|actor| actor.user_id);
     let task = web::block(move || {
         let conn = pool.get()?;
-        controllers::tasks::create_task(&new_task.0, &conn)
+        controllers::tasks::create_task(&new_task.0, actor.as_deref(), &conn)
     })
     .await?
-    .map_err(actix_web::error::ErrorInternalServerError)?;
+    .map_err(map_error)?;
 
     Ok(HttpResponse::Ok().json(task))
 }
 
+/// Параметры строки запроса для удаления задачи.
+#[derive(Deserialize)]
+struct DeleteTaskQuery{
+    children: Option<OnParentDelete>,
+}
+
 /// Метод, обрабатывающий DELETE запрос.
 /// # Arguments
 ///
 /// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `storage`     - Хранилище содержимого файлов. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задачи.
 /// * `task_uid`    - Уникальный идентификатор задачи, требуемой для удаления из базы данных.
+/// * `query`       - Поведение по отношению к подзадачам: `children=cascade|orphan|block` (по умолчанию `block`).
 ///
 /// # Return
 ///
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении объекта сущности задачи.
 
 #[delete("/task/{task_uid}")]
-async fn delete_task(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>)-> Result<HttpResponse, Error>{
+async fn delete_task(
+    pool: web::Data<DbPool>,
+    storage: web::Data<SharedStorage>,
+    actor: Option<Actor>,
+    task_uid: web::Path<Uuid>,
+    query: web::Query<DeleteTaskQuery>
+)-> Result<HttpResponse, Error>{
     let task_uid = task_uid.into_inner();
+    let on_delete = query.children.unwrap_or_default();
+    let actor = actor.map(|actor| actor.user_id);
     let result = web::block(move || {
         let conn = pool.get()?;
-        controllers::tasks::delete_task(&task_uid, &conn)
+        controllers::tasks::delete_task(&task_uid, on_delete, actor.as_deref(), storage.as_ref().as_ref(), &conn)
     })
     .await?
-    .map_err(actix_web::error::ErrorInternalServerError)?;
+    .map_err(map_error)?;
     if result == true{
         Ok(HttpResponse::Ok().body(format!("Task {} deleted", task_uid.to_string())))
     } else {
@@ -109,6 +158,7 @@ async fn delete_task(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>)-> Resul
 /// # Arguments
 ///
 /// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задачи.
 /// * `task_uid`    - Уникальный идентификатор задачи, требуемой для извлечения из базы данных.
 /// * `new_task`    - Структура данных типа new_task, необходимая для создания объекта сущности task.
 ///
@@ -119,17 +169,75 @@ async fn delete_task(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>)-> Resul
 #[put("/task/{task_uid}")]
 async fn update_task(
     pool: web::Data<DbPool>,
+    actor: Option<Actor>,
     new_task: web::Json<NewTask>,
     task_uid: web::Path<Uuid>
 )-> Result<HttpResponse, Error>{
     let task_uid = task_uid.into_inner();
+    let actor = actor.map(|actor| actor.user_id);
     let task = web::block(move || {
         let conn = pool.get()?;
-        controllers::tasks::update_task(&task_uid,&new_task.0, &conn)
+        controllers::tasks::update_task(&task_uid,&new_task.0, actor.as_deref(), &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(task) = task{
+        Ok(HttpResponse::Ok().json(task))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор родительской задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор непосредственных подзадач.
+
+#[get("/task/{task_uid}/children")]
+async fn get_task_children(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let children = web::block(move || {
+        let conn = pool.get()?;
+        controllers::tasks::get_children(&task_uid, &conn)
     })
     .await?
     .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(HttpResponse::Ok().json(task))
+    if let Some(children) = children{
+        Ok(HttpResponse::Ok().json(children))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор корневой задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо дерево подзадач с процентом выполнения.
+
+#[get("/task/{task_uid}/tree")]
+async fn get_task_tree(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let tree = web::block(move || {
+        let conn = pool.get()?;
+        controllers::tasks::get_task_tree(&task_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(tree) = tree{
+        Ok(HttpResponse::Ok().json(tree))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
 }
 
 /// Метод, обрабатывающий GET запрос.
@@ -188,6 +296,8 @@ async fn get_user(pool: web::Data<DbPool>, user_uid: web::Path<Uuid>) -> Result<
 /// # Arguments
 ///
 /// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос, если передан токен. Роль нового пользователя задаёт только администратор.
+/// * `meta`        - Сведения о запросе для журнала аудита.
 /// * `new_user`    - Структура данных типа new_user, необходимая для создания объекта сущности пользователя.
 ///
 /// # Return
@@ -195,13 +305,14 @@ async fn get_user(pool: web::Data<DbPool>, user_uid: web::Path<Uuid>) -> Result<
 /// Возвращает Результат с ответом, содержащим либо ошибку, объект пользователя.
 
 #[post("/user")]
-async fn add_user(pool: web::Data<DbPool>, new_user: web::Json<NewUser>) -> Result<HttpResponse, Error>{
+async fn add_user(pool: web::Data<DbPool>, actor: Option<Actor>, meta: RequestMeta, new_user: web::Json<NewUser>) -> Result<HttpResponse, Error>{
+    let actor = actor.map(|actor| actor.user_id);
     let task = web::block(move || {
         let conn = pool.get()?;
-        controllers::users::create_user(&new_user.0, &conn)
+        controllers::users::create_user(&new_user.0, actor.as_deref(), &meta, &conn)
     })
     .await?
-    .map_err(actix_web::error::ErrorInternalServerError)?;
+    .map_err(map_error)?;
 
     Ok(HttpResponse::Ok().json(task))
 }
@@ -210,6 +321,8 @@ async fn add_user(pool: web::Data<DbPool>, new_user: web::Json<NewUser>) -> Resu
 /// # Arguments
 ///
 /// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос. Чужие учётные записи удаляет только администратор.
+/// * `meta`        - Сведения о запросе для журнала аудита.
 /// * `user_uid`    - Уникальный идентификатор пользователя, требуемой для удаления из базы данных.
 ///
 /// # Return
@@ -217,14 +330,22 @@ async fn add_user(pool: web::Data<DbPool>, new_user: web::Json<NewUser>) -> Resu
 /// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении объекта сущности пользователя.
 
 #[delete("/user/{user_uid}")]
-async fn delete_user(pool: web::Data<DbPool>, user_uid: web::Path<Uuid>)-> Result<HttpResponse, Error>{
+async fn delete_user(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    meta: RequestMeta,
+    user_uid: web::Path<Uuid>
+)-> Result<HttpResponse, Error>{
     let user_uid = user_uid.into_inner();
     let result = web::block(move || {
         let conn = pool.get()?;
-        controllers::users::delete_user(&user_uid, &conn)
+        if actor.user_id != user_uid.to_string() {
+            controllers::users::require_admin(&actor.user_id, &conn)?;
+        }
+        controllers::users::delete_user(&user_uid, Some(&actor.user_id), &meta, &conn)
     })
     .await?
-    .map_err(actix_web::error::ErrorInternalServerError)?;
+    .map_err(map_error)?;
     if result{
         Ok(HttpResponse::Ok().body(format!("User {} deleted", user_uid.to_string())))
     } else {
@@ -236,6 +357,8 @@ async fn delete_user(pool: web::Data<DbPool>, user_uid: web::Path<Uuid>)-> Resul
 /// # Arguments
 ///
 /// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос. Чужие учётные записи и роли изменяет только администратор.
+/// * `meta`        - Сведения о запросе для журнала аудита.
 /// * `user_uid`    - Уникальный идентификатор пользователя, требуемой для извлечения из базы данных.
 /// * `new_user`    - Структура данных типа new_user, необходимая для создания объекта сущности пользователя.
 ///
@@ -246,15 +369,2786 @@ async fn delete_user(pool: web::Data<DbPool>, user_uid: web::Path<Uuid>)-> Resul
 #[put("/user/{user_uid}")]
 async fn update_user(
     pool: web::Data<DbPool>,
+    actor: Actor,
+    meta: RequestMeta,
     new_user: web::Json<NewUser>,
     user_uid: web::Path<Uuid>
 )-> Result<HttpResponse, Error>{
     let user_uid = user_uid.into_inner();
     let user = web::block(move || {
         let conn = pool.get()?;
-        controllers::users::update_user(&user_uid,&new_user.0, &conn)
+        controllers::users::update_user(&user_uid,&new_user.0, &actor.user_id, &meta, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    match user {
+        Some(user) => Ok(HttpResponse::Ok().json(user)),
+        None => Ok(HttpResponse::NotFound().body(format!("User {} not found", user_uid))),
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool` - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор проектов.
+
+#[get("/projects")]
+async fn get_projects(pool: web::Data<DbPool>) -> Result<HttpResponse, Error>{
+    let projects = web::block(move ||{
+        let conn = pool.get()?;
+        controllers::projects::get_projects(&conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(projects))
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid`    - Уникальный идентификатор проекта, требуемого для извлечения из базы данных.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект проекта.
+
+#[get("/project/{project_uid}")]
+async fn get_project(pool: web::Data<DbPool>, project_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let project = web::block(move || {
+        let conn = pool.get()?;
+        controllers::projects::get_project(&project_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(project) = project{
+        Ok(HttpResponse::Ok().json(project))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `new_project`    - Структура данных типа new_project, необходимая для создания объекта сущности проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект проекта.
+
+#[post("/project")]
+async fn add_project(pool: web::Data<DbPool>, new_project: web::Json<NewProject>) -> Result<HttpResponse, Error>{
+    let project = web::block(move || {
+        let conn = pool.get()?;
+        controllers::projects::create_project(&new_project.0, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    Ok(HttpResponse::Ok().json(project))
+}
+
+/// Метод, обрабатывающий DELETE запрос.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid`    - Уникальный идентификатор проекта, требуемого для удаления из базы данных.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении объекта сущности проекта.
+
+#[delete("/project/{project_uid}")]
+async fn delete_project(pool: web::Data<DbPool>, project_uid: web::Path<Uuid>)-> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::projects::delete_project(&project_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Project {} deleted", project_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий PUT запрос.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid`    - Уникальный идентификатор проекта, требуемого для извлечения из базы данных.
+/// * `new_project`    - Структура данных типа new_project, необходимая для изменения объекта сущности проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект проекта.
+
+#[put("/project/{project_uid}")]
+async fn update_project(
+    pool: web::Data<DbPool>,
+    new_project: web::Json<NewProject>,
+    project_uid: web::Path<Uuid>
+)-> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let project = web::block(move || {
+        let conn = pool.get()?;
+        controllers::projects::update_project(&project_uid, &new_project.0, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(project))
+}
+
+/// Параметры строки запроса для расписания проекта.
+#[derive(Deserialize)]
+struct ScheduleQuery{
+    start: Option<chrono::NaiveDateTime>,
+}
+
+/// Метод, обрабатывающий GET запрос. Рассчитывает расписание проекта и критический путь.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid`    - Уникальный идентификатор проекта.
+/// * `query`          - Необязательный момент начала расписания `start`. По умолчанию - текущее время.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо расписание проекта.
+
+#[get("/project/{project_uid}/schedule")]
+async fn get_project_schedule(
+    pool: web::Data<DbPool>,
+    project_uid: web::Path<Uuid>,
+    query: web::Query<ScheduleQuery>
+) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let schedule = web::block(move || {
+        let conn = pool.get()?;
+        controllers::schedule::get_schedule(&project_uid, query.start, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(schedule) = schedule{
+        Ok(HttpResponse::Ok().json(schedule))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Параметры строки запроса для списка меток.
+#[derive(Deserialize)]
+struct LabelsQuery{
+    project_id: Option<String>,
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `query`   - Необязательный идентификатор проекта. Если указан, возвращаются метки проекта и глобальные метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор меток с количеством использований.
+
+#[get("/labels")]
+async fn get_labels(pool: web::Data<DbPool>, query: web::Query<LabelsQuery>) -> Result<HttpResponse, Error>{
+    let labels = web::block(move ||{
+        let conn = pool.get()?;
+        controllers::labels::get_labels(query.project_id.as_deref(), &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok().json(labels))
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`         - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `label_uid`    - Уникальный идентификатор метки, требуемой для извлечения из базы данных.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект метки.
+
+#[get("/label/{label_uid}")]
+async fn get_label(pool: web::Data<DbPool>, label_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let label_uid = label_uid.into_inner();
+    let label = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::get_label(&label_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(label) = label{
+        Ok(HttpResponse::Ok().json(label))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Label {} not found", label_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос.
+/// # Arguments
+///
+/// * `pool`         - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `new_label`    - Структура данных типа new_label, необходимая для создания объекта сущности метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект метки.
+
+#[post("/label")]
+async fn add_label(pool: web::Data<DbPool>, new_label: web::Json<NewLabel>) -> Result<HttpResponse, Error>{
+    let label = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::create_label(&new_label.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+
+    Ok(HttpResponse::Ok().json(label))
+}
+
+/// Метод, обрабатывающий PUT запрос. Переименовывает метку и изменяет её цвет.
+/// # Arguments
+///
+/// * `pool`         - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `label_uid`    - Уникальный идентификатор изменяемой метки.
+/// * `new_label`    - Структура данных типа new_label с новым именем и цветом метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект метки.
+
+#[put("/label/{label_uid}")]
+async fn update_label(
+    pool: web::Data<DbPool>,
+    new_label: web::Json<NewLabel>,
+    label_uid: web::Path<Uuid>
+)-> Result<HttpResponse, Error>{
+    let label_uid = label_uid.into_inner();
+    let label = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::update_label(&label_uid, &new_label.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(label) = label{
+        Ok(HttpResponse::Ok().json(label))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Label {} not found", label_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Сливает метку из пути с меткой `into`.
+/// # Arguments
+///
+/// * `pool`         - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `label_uid`    - Уникальный идентификатор сливаемой метки. После слияния метка удаляется.
+/// * `merge`        - Структура данных типа label_merge с идентификатором целевой метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, целевую метку.
+
+#[post("/label/{label_uid}/merge")]
+async fn merge_labels(
+    pool: web::Data<DbPool>,
+    merge: web::Json<LabelMerge>,
+    label_uid: web::Path<Uuid>
+)-> Result<HttpResponse, Error>{
+    let label_uid = label_uid.into_inner();
+    let target_uid = Uuid::parse_str(&merge.into)
+        .map_err(actix_web::error::ErrorBadRequest)?;
+    let label = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::merge_labels(&label_uid, &target_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(label) = label{
+        Ok(HttpResponse::Ok().json(label))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Label {} or {} not found", label_uid, target_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос.
+/// # Arguments
+///
+/// * `pool`         - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `label_uid`    - Уникальный идентификатор метки, требуемой для удаления из базы данных.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении объекта сущности метки.
+
+#[delete("/label/{label_uid}")]
+async fn delete_label(pool: web::Data<DbPool>, label_uid: web::Path<Uuid>)-> Result<HttpResponse, Error>{
+    let label_uid = label_uid.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::delete_label(&label_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Label {} deleted", label_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Label {} not found", label_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор меток задачи.
+
+#[get("/task/{task_uid}/labels")]
+async fn get_task_labels(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let labels = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::get_task_labels(&task_uid, &conn)
     })
     .await?
     .map_err(actix_web::error::ErrorInternalServerError)?;
-    Ok(HttpResponse::Ok().json(user))
-}
\ No newline at end of file
+    Ok(HttpResponse::Ok().json(labels))
+}
+
+/// Метод, обрабатывающий POST запрос. Назначает метку задаче.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `path`    - Уникальные идентификаторы задачи и метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор меток задачи.
+
+#[post("/task/{task_uid}/labels/{label_uid}")]
+async fn add_task_label(pool: web::Data<DbPool>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, label_uid) = path.into_inner();
+    let labels = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::add_task_label(&task_uid, &label_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(labels) = labels{
+        Ok(HttpResponse::Ok().json(labels))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} or label {} not found", task_uid, label_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос. Снимает метку с задачи.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `path`    - Уникальные идентификаторы задачи и метки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном снятии метки.
+
+#[delete("/task/{task_uid}/labels/{label_uid}")]
+async fn remove_task_label(pool: web::Data<DbPool>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, label_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::labels::remove_task_label(&task_uid, &label_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Label {} removed from task {}", label_uid, task_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Label {} is not set on task {}", label_uid, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор входящих и исходящих связей задачи.
+
+#[get("/task/{task_uid}/links")]
+async fn get_task_links(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let links = web::block(move || {
+        let conn = pool.get()?;
+        controllers::links::get_task_links(&task_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(links) = links{
+        Ok(HttpResponse::Ok().json(links))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Создаёт связь задачи из пути с задачей `target_id`.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор исходной задачи.
+/// * `new_link`    - Структура данных типа new_task_link, необходимая для создания объекта связи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект связи.
+
+#[post("/task/{task_uid}/links")]
+async fn add_task_link(
+    pool: web::Data<DbPool>,
+    new_link: web::Json<NewTaskLink>,
+    task_uid: web::Path<Uuid>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let link = web::block(move || {
+        let conn = pool.get()?;
+        controllers::links::create_task_link(&task_uid, &new_link.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(link) = link{
+        Ok(HttpResponse::Ok().json(link))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `path`    - Уникальные идентификаторы задачи и удаляемой связи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении связи.
+
+#[delete("/task/{task_uid}/links/{link_uid}")]
+async fn delete_task_link(pool: web::Data<DbPool>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, link_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::links::delete_task_link(&task_uid, &link_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Link {} deleted", link_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Link {} not found for task {}", link_uid, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+/// * `page`        - Параметры постраничного вывода `page` и `per_page`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу комментариев задачи.
+
+#[get("/task/{task_uid}/comments")]
+async fn get_comments(
+    pool: web::Data<DbPool>,
+    task_uid: web::Path<Uuid>,
+    page: web::Query<PageQuery>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let comments = web::block(move || {
+        let conn = pool.get()?;
+        controllers::comments::get_comments(&task_uid, &page, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(comments) = comments{
+        Ok(HttpResponse::Ok().json(comments))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `path`    - Уникальные идентификаторы задачи и комментария.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект комментария.
+
+#[get("/task/{task_uid}/comments/{comment_uid}")]
+async fn get_comment(pool: web::Data<DbPool>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, comment_uid) = path.into_inner();
+    let comment = web::block(move || {
+        let conn = pool.get()?;
+        controllers::comments::get_comment(&task_uid, &comment_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(comment) = comment{
+        Ok(HttpResponse::Ok().json(comment))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Comment {} not found", comment_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Автором комментария становится текущий пользователь.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`          - Пользователь, выполняющий запрос.
+/// * `task_uid`       - Уникальный идентификатор задачи.
+/// * `new_comment`    - Структура данных типа new_comment, необходимая для создания объекта комментария.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект комментария.
+
+#[post("/task/{task_uid}/comments")]
+async fn add_comment(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    task_uid: web::Path<Uuid>,
+    new_comment: web::Json<NewComment>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let comment = web::block(move || {
+        let conn = pool.get()?;
+        controllers::comments::create_comment(&task_uid, &actor.user_id, &new_comment.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(comment) = comment{
+        Ok(HttpResponse::Ok().json(comment))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий PUT запрос. Изменять комментарий может только его автор.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`          - Пользователь, выполняющий запрос.
+/// * `path`           - Уникальные идентификаторы задачи и комментария.
+/// * `new_comment`    - Структура данных типа new_comment с новым текстом комментария.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект комментария.
+
+#[put("/task/{task_uid}/comments/{comment_uid}")]
+async fn update_comment(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    path: web::Path<(Uuid, Uuid)>,
+    new_comment: web::Json<NewComment>
+) -> Result<HttpResponse, Error>{
+    let (task_uid, comment_uid) = path.into_inner();
+    let comment = web::block(move || {
+        let conn = pool.get()?;
+        controllers::comments::update_comment(&task_uid, &comment_uid, &actor.user_id, &new_comment.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(comment) = comment{
+        Ok(HttpResponse::Ok().json(comment))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Comment {} not found", comment_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос. Удалять комментарий может только его автор.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос.
+/// * `path`    - Уникальные идентификаторы задачи и комментария.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении комментария.
+
+#[delete("/task/{task_uid}/comments/{comment_uid}")]
+async fn delete_comment(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    path: web::Path<(Uuid, Uuid)>
+) -> Result<HttpResponse, Error>{
+    let (task_uid, comment_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::comments::delete_comment(&task_uid, &comment_uid, &actor.user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Comment {} deleted", comment_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Comment {} not found", comment_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `path`    - Уникальные идентификаторы задачи и комментария.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор предыдущих версий комментария.
+
+#[get("/task/{task_uid}/comments/{comment_uid}/history")]
+async fn get_comment_history(pool: web::Data<DbPool>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, comment_uid) = path.into_inner();
+    let history = web::block(move || {
+        let conn = pool.get()?;
+        controllers::comments::get_comment_history(&task_uid, &comment_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(history) = history{
+        Ok(HttpResponse::Ok().json(history))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Comment {} not found", comment_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор исполнителей задачи.
+
+#[get("/task/{task_uid}/assignees")]
+async fn get_task_assignees(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let assignees = web::block(move || {
+        let conn = pool.get()?;
+        controllers::assignees::get_assignees(&task_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(assignees) = assignees{
+        Ok(HttpResponse::Ok().json(assignees))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Добавляет исполнителя задачи или изменяет его роль.
+/// # Arguments
+///
+/// * `pool`            - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`           - Пользователь, выполняющий запрос, если он указан.
+/// * `task_uid`        - Уникальный идентификатор задачи.
+/// * `new_assignee`    - Структура данных типа NewAssignee с пользователем и ролью.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор исполнителей задачи.
+
+#[post("/task/{task_uid}/assignees")]
+async fn add_task_assignee(
+    pool: web::Data<DbPool>,
+    actor: Option<Actor>,
+    task_uid: web::Path<Uuid>,
+    new_assignee: web::Json<NewAssignee>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let assignees = web::block(move || {
+        let conn = pool.get()?;
+        let actor = actor.as_ref().map(|actor| actor.user_id.as_str());
+        controllers::assignees::add_assignee(&task_uid, &new_assignee.0, actor, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(assignees) = assignees{
+        Ok(HttpResponse::Ok().json(assignees))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос. Удаляет исполнителя задачи.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос, если он указан.
+/// * `path`    - Уникальные идентификаторы задачи и пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении исполнителя.
+
+#[delete("/task/{task_uid}/assignees/{user_uid}")]
+async fn remove_task_assignee(pool: web::Data<DbPool>, actor: Option<Actor>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, user_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        let actor = actor.as_ref().map(|actor| actor.user_id.as_str());
+        controllers::assignees::remove_assignee(&task_uid, &user_uid, actor, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("User {} removed from assignees of task {}", user_uid, task_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("User {} is not assigned to task {}", user_uid, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор наблюдателей задачи.
+
+#[get("/task/{task_uid}/watchers")]
+async fn get_task_watchers(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let watchers = web::block(move || {
+        let conn = pool.get()?;
+        controllers::watchers::get_watchers(&task_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(watchers) = watchers{
+        Ok(HttpResponse::Ok().json(watchers))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Подписывает текущего пользователя на изменения задачи.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор наблюдателей задачи.
+
+#[post("/task/{task_uid}/watchers")]
+async fn watch_task(pool: web::Data<DbPool>, actor: Actor, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let watchers = web::block(move || {
+        let conn = pool.get()?;
+        controllers::watchers::watch(&task_uid, &actor.user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(watchers) = watchers{
+        Ok(HttpResponse::Ok().json(watchers))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос. Отписывает текущего пользователя от изменений задачи.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешной отписке.
+
+#[delete("/task/{task_uid}/watchers")]
+async fn unwatch_task(pool: web::Data<DbPool>, actor: Actor, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let user_id = actor.user_id.clone();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::watchers::unwatch(&task_uid, &user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("User {} no longer watches task {}", actor.user_id, task_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("User {} does not watch task {}", actor.user_id, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор метаданных вложений задачи.
+
+#[get("/task/{task_uid}/attachments")]
+async fn get_attachments(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let attachments = web::block(move || {
+        let conn = pool.get()?;
+        controllers::attachments::get_attachments(&task_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(attachments) = attachments{
+        Ok(HttpResponse::Ok().json(attachments))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `path`    - Уникальные идентификаторы задачи и вложения.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо метаданные вложения.
+
+#[get("/task/{task_uid}/attachments/{attachment_uid}")]
+async fn get_attachment(pool: web::Data<DbPool>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, attachment_uid) = path.into_inner();
+    let attachment = web::block(move || {
+        let conn = pool.get()?;
+        controllers::attachments::get_attachment_view(&task_uid, &attachment_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(attachment) = attachment{
+        Ok(HttpResponse::Ok().json(attachment))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Attachment {} not found", attachment_uid)))
+    }
+}
+
+/// Временный файл загрузки, удаляемый при выходе из области видимости.
+struct TempFile(std::path::PathBuf);
+
+impl Drop for TempFile{
+    fn drop(&mut self){
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// Метод, обрабатывающий POST запрос с телом multipart/form-data.
+/// Файл передаётся в поле `file` и до сохранения в хранилище записывается во временный файл.
+/// Для изображений после ответа в фоне генерируются уменьшенные копии.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `storage`     - Хранилище содержимого файлов.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+/// * `payload`     - Тело запроса.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо метаданные вложения.
+
+#[post("/task/{task_uid}/attachments")]
+async fn add_attachment(
+    pool: web::Data<DbPool>,
+    storage: web::Data<SharedStorage>,
+    actor: Actor,
+    task_uid: web::Path<Uuid>,
+    mut payload: Multipart
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let max_size = controllers::attachments::max_file_size();
+    let mut uploaded = None;
+    // Временный файл удаляется при любом выходе из обработчика, в том числе при обрыве загрузки
+    let mut temp_file: Option<TempFile> = None;
+    while let Some(mut field) = payload.try_next().await? {
+        if field.name() != "file" {
+            continue;
+        }
+        let file_name = field
+            .content_disposition()
+            .get_filename()
+            .and_then(|name| name.rsplit(['/', '\\']).next())
+            .filter(|name| !name.is_empty())
+            .unwrap_or("file")
+            .to_string();
+        let content_type = field
+            .content_type()
+            .map(|mime| mime.to_string())
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let path = std::env::temp_dir().join(format!("upload-{}", Uuid::new_v4()));
+        let file_path = path.clone();
+        let mut file = web::block(move || std::fs::File::create(file_path)).await??;
+        temp_file = Some(TempFile(path.clone()));
+        let mut hasher = Sha256::new();
+        let mut size: i64 = 0;
+        while let Some(chunk) = field.try_next().await? {
+            size += chunk.len() as i64;
+            if size > max_size {
+                return Ok(HttpResponse::PayloadTooLarge().body(format!("File is larger than {} bytes", max_size)));
+            }
+            hasher.update(&chunk);
+            file = web::block(move || file.write_all(&chunk).map(|_| file)).await??;
+        }
+        uploaded = Some(UploadedFile{
+            file_name,
+            content_type,
+            size,
+            checksum: hex::encode(hasher.finalize()),
+            path,
+        });
+        break;
+    }
+    let uploaded = match uploaded {
+        Some(uploaded) => uploaded,
+        None => return Ok(HttpResponse::BadRequest().body("Missing multipart field file")),
+    };
+
+    let (block_pool, block_storage) = (pool.clone(), storage.clone());
+    let attachment = web::block(move || {
+        let conn = block_pool.get()?;
+        controllers::attachments::create_attachment(&task_uid, &actor.user_id, &uploaded, block_storage.as_ref().as_ref(), &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    drop(temp_file);
+    if let Some(attachment) = attachment{
+        if attachment.preview_status.is_some() {
+            let attachment_id = attachment.id.clone();
+            actix_web::rt::task::spawn_blocking(move || {
+                let result = controllers::thumbnails::generate_thumbnails(&attachment_id, &pool, storage.as_ref().as_ref());
+                if let Err(err) = result {
+                    log::error!("Thumbnail generation for attachment {} failed: {}", attachment_id, err);
+                }
+            });
+        }
+        Ok(HttpResponse::Ok().json(attachment))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Отдаёт содержимое вложения потоком,
+/// поддерживает заголовок Range с одним диапазоном байт.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `storage`     - Хранилище содержимого файлов.
+/// * `path`        - Уникальные идентификаторы задачи и вложения.
+/// * `req`         - Запрос, из которого извлекается заголовок Range.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо содержимое файла.
+
+#[get("/task/{task_uid}/attachments/{attachment_uid}/content")]
+async fn download_attachment(
+    pool: web::Data<DbPool>,
+    storage: web::Data<SharedStorage>,
+    path: web::Path<(Uuid, Uuid)>,
+    req: HttpRequest
+) -> Result<HttpResponse, Error>{
+    let (task_uid, attachment_uid) = path.into_inner();
+    let attachment = web::block(move || {
+        let conn = pool.get()?;
+        controllers::attachments::get_attachment(&task_uid, &attachment_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    let attachment = match attachment {
+        Some(attachment) => attachment,
+        None => return Ok(HttpResponse::NotFound().body(format!("Attachment {} not found", attachment_uid))),
+    };
+
+    let size = attachment.size as u64;
+    let range = match req.headers().get(header::RANGE).and_then(|value| value.to_str().ok()) {
+        Some(value) => match parse_range(value, size) {
+            Ok(range) => range,
+            Err(()) => {
+                return Ok(HttpResponse::RangeNotSatisfiable()
+                    .insert_header((header::CONTENT_RANGE, format!("bytes */{}", size)))
+                    .finish());
+            },
+        },
+        None => None,
+    };
+    let key = attachment.storage_key.clone();
+    let reader = web::block(move || storage.open(&key, range))
+        .await?
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+
+    let mut response = match range {
+        Some((start, end)) => {
+            let mut response = HttpResponse::PartialContent();
+            response.insert_header((header::CONTENT_RANGE, format!("bytes {}-{}/{}", start, end, size)));
+            response
+        },
+        None => HttpResponse::Ok(),
+    };
+    let length = range.map_or(size, |(start, end)| end - start + 1);
+    Ok(response
+        .insert_header((header::CONTENT_TYPE, attachment.content_type.clone()))
+        .insert_header((header::ACCEPT_RANGES, "bytes"))
+        .insert_header(ContentDisposition{
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(attachment.file_name.clone())],
+        })
+        .no_chunking(length)
+        .streaming(read_stream(reader)))
+}
+
+/// Метод, обрабатывающий GET запрос. Отдаёт уменьшенную копию изображения-вложения.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `storage`     - Хранилище содержимого файлов.
+/// * `path`        - Уникальные идентификаторы задачи и вложения, имя размера (`small`, `medium`, `large`).
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо содержимое уменьшенной копии.
+
+#[get("/task/{task_uid}/attachments/{attachment_uid}/thumbnails/{size_name}")]
+async fn get_attachment_thumbnail(
+    pool: web::Data<DbPool>,
+    storage: web::Data<SharedStorage>,
+    path: web::Path<(Uuid, Uuid, String)>
+) -> Result<HttpResponse, Error>{
+    let (task_uid, attachment_uid, size_name) = path.into_inner();
+    let thumbnail = web::block(move || {
+        let conn = pool.get()?;
+        controllers::thumbnails::get_thumbnail(&task_uid, &attachment_uid, &size_name, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    let thumbnail = match thumbnail {
+        Some(thumbnail) => thumbnail,
+        None => return Ok(HttpResponse::NotFound().body(format!("Thumbnail of attachment {} not found", attachment_uid))),
+    };
+    let key = thumbnail.storage_key.clone();
+    let reader = web::block(move || storage.open(&key, None))
+        .await?
+        .map_err(actix_web::error::ErrorInternalServerError)?;
+    Ok(HttpResponse::Ok()
+        .insert_header((header::CONTENT_TYPE, thumbnail.content_type.clone()))
+        .no_chunking(thumbnail.size as u64)
+        .streaming(read_stream(reader)))
+}
+
+/// Метод, обрабатывающий DELETE запрос. Удаляет метаданные и содержимое вложения.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `storage`     - Хранилище содержимого файлов.
+/// * `path`        - Уникальные идентификаторы задачи и вложения.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении вложения.
+
+#[delete("/task/{task_uid}/attachments/{attachment_uid}")]
+async fn delete_attachment(
+    pool: web::Data<DbPool>,
+    storage: web::Data<SharedStorage>,
+    path: web::Path<(Uuid, Uuid)>
+) -> Result<HttpResponse, Error>{
+    let (task_uid, attachment_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::attachments::delete_attachment(&task_uid, &attachment_uid, storage.as_ref().as_ref(), &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Attachment {} deleted", attachment_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Attachment {} not found", attachment_uid)))
+    }
+}
+
+/// Метод разбора заголовка Range. Поддерживается один диапазон в байтах.
+/// # Return
+///
+/// Возвращает включительный диапазон, None для игнорируемого заголовка
+/// или ошибку для диапазона, выходящего за пределы файла.
+fn parse_range(value: &str, size: u64) -> Result<Option<(u64, u64)>, ()>{
+    let spec = match value.trim().strip_prefix("bytes=") {
+        Some(spec) if !spec.contains(',') => spec.trim(),
+        _ => return Ok(None),
+    };
+    let (start, end) = match spec.split_once('-') {
+        Some(parts) => parts,
+        None => return Ok(None),
+    };
+    let range = match (start.parse::<u64>().ok(), end.parse::<u64>().ok()) {
+        (Some(start), Some(end)) if start <= end => (start, end.min(size.saturating_sub(1))),
+        (Some(start), None) if end.is_empty() => (start, size.saturating_sub(1)),
+        (None, Some(suffix)) if start.is_empty() && suffix > 0 => (size.saturating_sub(suffix), size.saturating_sub(1)),
+        _ => return Ok(None),
+    };
+    if size == 0 || range.0 >= size {
+        return Err(());
+    }
+    Ok(Some(range))
+}
+
+/// Метод, превращающий блокирующий поток чтения в асинхронный поток фрагментов тела ответа.
+/// Чтение выполняется в отдельном потоке, фрагменты передаются через ограниченный канал.
+fn read_stream(mut reader: Box<dyn Read + Send>) -> impl Stream<Item = Result<web::Bytes, std::io::Error>>{
+    let (sender, receiver) = tokio::sync::mpsc::channel(4);
+    actix_web::rt::task::spawn_blocking(move || {
+        let mut buffer = vec![0u8; 64 * 1024];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read) => {
+                    if sender.blocking_send(Ok(web::Bytes::copy_from_slice(&buffer[..read]))).is_err() {
+                        break;
+                    }
+                },
+                Err(err) => {
+                    let _ = sender.blocking_send(Err(err));
+                    break;
+                },
+            }
+        }
+    });
+    futures_util::stream::unfold(receiver, |mut receiver| async move {
+        receiver.recv().await.map(|item| (item, receiver))
+    })
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор пунктов чек-листа задачи.
+
+#[get("/task/{task_uid}/checklist")]
+async fn get_checklist(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let items = web::block(move || {
+        let conn = pool.get()?;
+        controllers::checklists::get_checklist(&task_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(items) = items{
+        Ok(HttpResponse::Ok().json(items))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Добавляет пункт в конец чек-листа задачи.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+/// * `new_item`    - Структура данных типа new_checklist_item, необходимая для создания пункта чек-листа.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект пункта чек-листа.
+
+#[post("/task/{task_uid}/checklist")]
+async fn add_checklist_item(
+    pool: web::Data<DbPool>,
+    task_uid: web::Path<Uuid>,
+    new_item: web::Json<NewChecklistItem>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let item = web::block(move || {
+        let conn = pool.get()?;
+        controllers::checklists::create_checklist_item(&task_uid, &new_item.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(item) = item{
+        Ok(HttpResponse::Ok().json(item))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий PUT запрос. Задаёт новый порядок пунктов чек-листа.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+/// * `order`       - Идентификаторы всех пунктов чек-листа в новом порядке.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор пунктов в новом порядке.
+
+#[put("/task/{task_uid}/checklist/order")]
+async fn reorder_checklist(
+    pool: web::Data<DbPool>,
+    task_uid: web::Path<Uuid>,
+    order: web::Json<ChecklistOrder>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let items = web::block(move || {
+        let conn = pool.get()?;
+        controllers::checklists::reorder_checklist(&task_uid, &order.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(items) = items{
+        Ok(HttpResponse::Ok().json(items))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Отмечает пункт чек-листа или снимает отметку.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос.
+/// * `path`    - Уникальные идентификаторы задачи и пункта чек-листа.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, измененный пункт чек-листа.
+
+#[post("/task/{task_uid}/checklist/{item_uid}/toggle")]
+async fn toggle_checklist_item(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    path: web::Path<(Uuid, Uuid)>
+) -> Result<HttpResponse, Error>{
+    let (task_uid, item_uid) = path.into_inner();
+    let item = web::block(move || {
+        let conn = pool.get()?;
+        controllers::checklists::toggle_checklist_item(&task_uid, &item_uid, &actor.user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(item) = item{
+        Ok(HttpResponse::Ok().json(item))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Checklist item {} not found for task {}", item_uid, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `path`    - Уникальные идентификаторы задачи и удаляемого пункта чек-листа.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении пункта.
+
+#[delete("/task/{task_uid}/checklist/{item_uid}")]
+async fn delete_checklist_item(pool: web::Data<DbPool>, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, item_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::checklists::delete_checklist_item(&task_uid, &item_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Checklist item {} deleted", item_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Checklist item {} not found for task {}", item_uid, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает следующие повторения задачи после её срока.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор повторяющейся задачи.
+/// * `query`       - Количество повторений `count` (по умолчанию 5, не более 100).
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор повторений.
+
+#[get("/task/{task_uid}/occurrences")]
+async fn get_task_occurrences(
+    pool: web::Data<DbPool>,
+    task_uid: web::Path<Uuid>,
+    query: web::Query<OccurrencesQuery>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let occurrences = web::block(move || {
+        let conn = pool.get()?;
+        controllers::recurrence::get_occurrences(&task_uid, query.count, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(occurrences) = occurrences{
+        Ok(HttpResponse::Ok().json(occurrences))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий PUT запрос. Изменяет правило повторения и текст задачи
+/// вместе со всеми её будущими повторениями.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задачи.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+/// * `edit`        - Структура данных типа recurrence_edit с изменениями серии.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо серию повторений задачи.
+
+#[put("/task/{task_uid}/recurrence")]
+async fn update_task_recurrence(
+    pool: web::Data<DbPool>,
+    actor: Option<Actor>,
+    task_uid: web::Path<Uuid>,
+    edit: web::Json<RecurrenceEdit>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let actor = actor.map(|actor| actor.user_id);
+    let series = web::block(move || {
+        let conn = pool.get()?;
+        controllers::recurrence::update_recurrence(&task_uid, &edit.0, actor.as_deref(), &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(series) = series{
+        Ok(HttpResponse::Ok().json(series))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор записей о затраченном времени.
+
+#[get("/task/{task_uid}/worklogs")]
+async fn get_worklogs(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let worklogs = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::get_worklogs(&task_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(worklogs) = worklogs{
+        Ok(HttpResponse::Ok().json(worklogs))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Добавляет запись о затраченном времени вручную.
+/// # Arguments
+///
+/// * `pool`           - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`          - Пользователь, выполняющий запрос.
+/// * `task_uid`       - Уникальный идентификатор задачи.
+/// * `new_worklog`    - Структура данных типа new_worklog, необходимая для создания записи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект записи.
+
+#[post("/task/{task_uid}/worklogs")]
+async fn add_worklog(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    task_uid: web::Path<Uuid>,
+    new_worklog: web::Json<NewWorklog>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let worklog = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::create_worklog(&task_uid, &actor.user_id, &new_worklog.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(worklog) = worklog{
+        Ok(HttpResponse::Ok().json(worklog))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос.
+/// * `path`    - Уникальные идентификаторы задачи и удаляемой записи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении записи.
+
+#[delete("/task/{task_uid}/worklogs/{worklog_uid}")]
+async fn delete_worklog(pool: web::Data<DbPool>, actor: Actor, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (task_uid, worklog_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::delete_worklog(&task_uid, &worklog_uid, &actor.user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Worklog {} deleted", worklog_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Worklog {} not found for task {}", worklog_uid, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Запускает таймер текущего пользователя по задаче.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+/// * `start`       - Необязательная заметка к записи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо запись запущенного таймера.
+
+#[post("/task/{task_uid}/timer/start")]
+async fn start_timer(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    task_uid: web::Path<Uuid>,
+    start: Option<web::Json<TimerStart>>
+) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let start = start.map(|start| start.into_inner()).unwrap_or_default();
+    let worklog = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::start_timer(&task_uid, &actor.user_id, &start, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(worklog) = worklog{
+        Ok(HttpResponse::Ok().json(worklog))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Останавливает запущенный таймер текущего пользователя.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо завершённую запись о затраченном времени.
+
+#[post("/timer/stop")]
+async fn stop_timer(pool: web::Data<DbPool>, actor: Actor) -> Result<HttpResponse, Error>{
+    let worklog = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::stop_timer(&actor.user_id, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(worklog) = worklog{
+        Ok(HttpResponse::Ok().json(worklog))
+    } else {
+        Ok(HttpResponse::NotFound().body("No running timer"))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо запущенный таймер текущего пользователя.
+
+#[get("/timer")]
+async fn get_timer(pool: web::Data<DbPool>, actor: Actor) -> Result<HttpResponse, Error>{
+    let worklog = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::get_running_timer(&actor.user_id, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(worklog) = worklog{
+        Ok(HttpResponse::Ok().json(worklog))
+    } else {
+        Ok(HttpResponse::NotFound().body("No running timer"))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо суммарное время по задаче с разбивкой по пользователям.
+
+#[get("/task/{task_uid}/time")]
+async fn get_task_time(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let total = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::get_task_total(&task_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(total) = total{
+        Ok(HttpResponse::Ok().json(total))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `user_uid`    - Уникальный идентификатор пользователя.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо суммарное время пользователя с разбивкой по задачам.
+
+#[get("/user/{user_uid}/time")]
+async fn get_user_time(pool: web::Data<DbPool>, user_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let user_uid = user_uid.into_inner();
+    let total = web::block(move || {
+        let conn = pool.get()?;
+        controllers::worklogs::get_user_total(&user_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(total) = total{
+        Ok(HttpResponse::Ok().json(total))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("User {} not found", user_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Формирует отчёт по затраченному времени.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос, если передан токен.
+/// * `meta`    - Сведения о запросе. Выгрузка в CSV записывается в журнал аудита.
+/// * `query`   - Фильтры `user_id`, `project_id`, `from`, `to`, группировка `group_by=day|week|project`
+///               и формат `format=json|csv`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо отчёт в формате JSON или CSV.
+
+#[get("/timesheet")]
+async fn get_timesheet(
+    pool: web::Data<DbPool>,
+    actor: Option<Actor>,
+    meta: RequestMeta,
+    query: web::Query<TimesheetQuery>
+) -> Result<HttpResponse, Error>{
+    let query = query.into_inner();
+    let csv = match query.format.as_deref() {
+        None | Some("json") => false,
+        Some("csv") => true,
+        Some(format) => return Ok(HttpResponse::BadRequest().body(format!("Unknown format {}", format))),
+    };
+    let actor = actor.map(|actor| actor.user_id);
+    let timesheet = web::block(move || {
+        let conn = pool.get()?;
+        if csv {
+            let event = controllers::audit::event(controllers::audit::ACTION_EXPORT, actor.as_deref(), &meta);
+            controllers::worklogs::export_timesheet(&query, event, &conn)
+        } else {
+            controllers::worklogs::get_timesheet(&query, &conn)
+        }
+    })
+    .await?
+    .map_err(map_error)?;
+    if csv {
+        Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(ContentDisposition{
+                disposition: DispositionType::Attachment,
+                parameters: vec![DispositionParam::Filename("timesheet.csv".to_string())],
+            })
+            .body(controllers::worklogs::timesheet_to_csv(&timesheet)))
+    } else {
+        Ok(HttpResponse::Ok().json(timesheet))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`            - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid`     - Уникальный идентификатор проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор спринтов проекта.
+
+#[get("/project/{project_uid}/sprints")]
+async fn get_sprints(pool: web::Data<DbPool>, project_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let sprints = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::get_sprints(&project_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(sprints) = sprints{
+        Ok(HttpResponse::Ok().json(sprints))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос.
+/// # Arguments
+///
+/// * `pool`            - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid`     - Уникальный идентификатор проекта.
+/// * `new_sprint`      - Структура данных типа new_sprint, необходимая для создания объекта спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект спринта.
+
+#[post("/project/{project_uid}/sprints")]
+async fn add_sprint(
+    pool: web::Data<DbPool>,
+    project_uid: web::Path<Uuid>,
+    new_sprint: web::Json<NewSprint>
+) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let sprint = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::create_sprint(&project_uid, &new_sprint.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(sprint) = sprint{
+        Ok(HttpResponse::Ok().json(sprint))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`          - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `sprint_uid`    - Уникальный идентификатор спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, объект спринта.
+
+#[get("/sprint/{sprint_uid}")]
+async fn get_sprint(pool: web::Data<DbPool>, sprint_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let sprint_uid = sprint_uid.into_inner();
+    let sprint = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::get_sprint(&sprint_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(sprint) = sprint{
+        Ok(HttpResponse::Ok().json(sprint))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Sprint {} not found", sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий PUT запрос.
+/// # Arguments
+///
+/// * `pool`          - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `sprint_uid`    - Уникальный идентификатор спринта.
+/// * `new_sprint`    - Структура данных типа new_sprint с новыми данными спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, измененный объект спринта.
+
+#[put("/sprint/{sprint_uid}")]
+async fn update_sprint(
+    pool: web::Data<DbPool>,
+    sprint_uid: web::Path<Uuid>,
+    new_sprint: web::Json<NewSprint>
+) -> Result<HttpResponse, Error>{
+    let sprint_uid = sprint_uid.into_inner();
+    let sprint = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::update_sprint(&sprint_uid, &new_sprint.0, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(sprint) = sprint{
+        Ok(HttpResponse::Ok().json(sprint))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Sprint {} not found", sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос. Задачи удаляемого спринта возвращаются в бэклог.
+/// # Arguments
+///
+/// * `pool`          - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `sprint_uid`    - Уникальный идентификатор спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении спринта.
+
+#[delete("/sprint/{sprint_uid}")]
+async fn delete_sprint(pool: web::Data<DbPool>, sprint_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let sprint_uid = sprint_uid.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::delete_sprint(&sprint_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Sprint {} deleted", sprint_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Sprint {} not found", sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`          - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `sprint_uid`    - Уникальный идентификатор спринта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор задач спринта.
+
+#[get("/sprint/{sprint_uid}/tasks")]
+async fn get_sprint_tasks(pool: web::Data<DbPool>, sprint_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let sprint_uid = sprint_uid.into_inner();
+    let tasks = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::get_sprint_tasks(&sprint_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(tasks) = tasks{
+        Ok(HttpResponse::Ok().json(tasks))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Sprint {} not found", sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Добавляет задачу в спринт.
+/// # Arguments
+///
+/// * `pool`          - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`         - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задачи.
+/// * `sprint_uid`    - Уникальный идентификатор спринта.
+/// * `sprint_task`   - Идентификатор добавляемой задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, измененный объект задачи.
+
+#[post("/sprint/{sprint_uid}/tasks")]
+async fn add_sprint_task(
+    pool: web::Data<DbPool>,
+    actor: Option<Actor>,
+    sprint_uid: web::Path<Uuid>,
+    sprint_task: web::Json<SprintTask>
+) -> Result<HttpResponse, Error>{
+    let sprint_uid = sprint_uid.into_inner();
+    let actor = actor.map(|actor| actor.user_id);
+    let task = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::add_sprint_task(&sprint_uid, &sprint_task.0, actor.as_deref(), &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(task) = task{
+        Ok(HttpResponse::Ok().json(task))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Sprint {} not found", sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос. Возвращает задачу из спринта в бэклог.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задачи.
+/// * `path`    - Уникальные идентификаторы спринта и задачи.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении задачи из спринта.
+
+#[delete("/sprint/{sprint_uid}/tasks/{task_uid}")]
+async fn remove_sprint_task(
+    pool: web::Data<DbPool>,
+    actor: Option<Actor>,
+    path: web::Path<(Uuid, Uuid)>
+) -> Result<HttpResponse, Error>{
+    let (sprint_uid, task_uid) = path.into_inner();
+    let actor = actor.map(|actor| actor.user_id);
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::remove_sprint_task(&sprint_uid, &task_uid, actor.as_deref(), &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Task {} removed from sprint {}", task_uid, sprint_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} is not in sprint {}", task_uid, sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Закрывает спринт и переносит незавершённые задачи.
+/// # Arguments
+///
+/// * `pool`          - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`         - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задач.
+/// * `sprint_uid`    - Уникальный идентификатор спринта.
+/// * `close`         - Спринт `carry_over_to` для незавершённых задач. Без него задачи возвращаются в бэклог.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо закрытый спринт и перенесённые задачи.
+
+#[post("/sprint/{sprint_uid}/close")]
+async fn close_sprint(
+    pool: web::Data<DbPool>,
+    actor: Option<Actor>,
+    sprint_uid: web::Path<Uuid>,
+    close: Option<web::Json<SprintClose>>
+) -> Result<HttpResponse, Error>{
+    let sprint_uid = sprint_uid.into_inner();
+    let close = close.map(|close| close.into_inner()).unwrap_or_default();
+    let actor = actor.map(|actor| actor.user_id);
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::close_sprint(&sprint_uid, &close, actor.as_deref(), &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(result) = result{
+        Ok(HttpResponse::Ok().json(result))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Sprint {} not found", sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос.
+/// # Arguments
+///
+/// * `pool`            - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid`     - Уникальный идентификатор проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо историю скорости проекта по закрытым спринтам.
+
+#[get("/project/{project_uid}/velocity")]
+async fn get_project_velocity(pool: web::Data<DbPool>, project_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let velocity = web::block(move || {
+        let conn = pool.get()?;
+        controllers::sprints::get_velocity(&project_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(velocity) = velocity{
+        Ok(HttpResponse::Ok().json(velocity))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает диаграмму сгорания спринта
+/// от даты его начала до даты окончания.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `sprint_uid`  - Уникальный идентификатор спринта.
+/// * `query`       - Единица объёма работ `unit`: `points` (по умолчанию) или `tasks`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо диаграмму сгорания.
+
+#[get("/sprint/{sprint_uid}/burndown")]
+async fn get_sprint_burndown(
+    pool: web::Data<DbPool>,
+    sprint_uid: web::Path<Uuid>,
+    query: web::Query<ReportQuery>
+) -> Result<HttpResponse, Error>{
+    let sprint_uid = sprint_uid.into_inner();
+    let chart = web::block(move || {
+        let conn = pool.get()?;
+        controllers::reports::get_sprint_burndown(&sprint_uid, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(chart) = chart{
+        Ok(HttpResponse::Ok().json(chart))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Sprint {} not found", sprint_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает диаграмму сгорания проекта за период.
+/// Поле `completed` каждого дня даёт диаграмму выполнения (burnup).
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid` - Уникальный идентификатор проекта.
+/// * `query`       - Период `from`/`to` (по умолчанию последние 30 дней) и единица объёма `unit`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо диаграмму сгорания.
+
+#[get("/project/{project_uid}/burndown")]
+async fn get_project_burndown(
+    pool: web::Data<DbPool>,
+    project_uid: web::Path<Uuid>,
+    query: web::Query<ReportQuery>
+) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let chart = web::block(move || {
+        let conn = pool.get()?;
+        controllers::reports::get_project_burndown(&project_uid, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(chart) = chart{
+        Ok(HttpResponse::Ok().json(chart))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает накопительную диаграмму потока проекта:
+/// количество задач в каждом статусе на конец каждого дня периода.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid` - Уникальный идентификатор проекта.
+/// * `query`       - Период `from`/`to` (по умолчанию последние 30 дней).
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо данные диаграммы по дням.
+
+#[get("/project/{project_uid}/cumulative-flow")]
+async fn get_project_cumulative_flow(
+    pool: web::Data<DbPool>,
+    project_uid: web::Path<Uuid>,
+    query: web::Query<ReportQuery>
+) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let flow = web::block(move || {
+        let conn = pool.get()?;
+        controllers::reports::get_cumulative_flow(&project_uid, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(flow) = flow{
+        Ok(HttpResponse::Ok().json(flow))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает аналитику потока проекта: распределения
+/// времени выполнения и времени цикла с перцентилями, пропускную способность по неделям
+/// и возраст задач, находящихся в работе.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid` - Уникальный идентификатор проекта.
+/// * `query`       - Период завершения задач `from`/`to` (по умолчанию последние 30 дней).
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо аналитику потока.
+
+#[get("/project/{project_uid}/analytics/flow")]
+async fn get_project_flow(
+    pool: web::Data<DbPool>,
+    project_uid: web::Path<Uuid>,
+    query: web::Query<ReportQuery>
+) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let analytics = web::block(move || {
+        let conn = pool.get()?;
+        controllers::reports::get_flow_analytics(&project_uid, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(analytics) = analytics{
+        Ok(HttpResponse::Ok().json(analytics))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Прогнозирует методом Монте-Карло дату выполнения
+/// оставшихся задач проекта и количество задач, выполненных к дате `by`.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `project_uid` - Уникальный идентификатор проекта.
+/// * `query`       - Параметры прогноза: `remaining`, `by`, `seed`, `iterations`, `history_days`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо прогноз с перцентилями.
+
+#[get("/project/{project_uid}/forecast")]
+async fn get_project_forecast(
+    pool: web::Data<DbPool>,
+    project_uid: web::Path<Uuid>,
+    query: web::Query<ForecastQuery>
+) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let forecast = web::block(move || {
+        let conn = pool.get()?;
+        controllers::forecasts::get_forecast(&project_uid, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(forecast) = forecast{
+        Ok(HttpResponse::Ok().json(forecast))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает историю изменений задачи: для каждого
+/// создания, изменения и удаления - изменённые поля, автора и время.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `task_uid`    - Уникальный идентификатор задачи, в том числе удалённой.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор записей истории.
+
+#[get("/task/{task_uid}/history")]
+async fn get_task_history(pool: web::Data<DbPool>, task_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let task_uid = task_uid.into_inner();
+    let history = web::block(move || {
+        let conn = pool.get()?;
+        controllers::history::get_task_history(&task_uid, &conn)
+    })
+    .await?
+    .map_err(actix_web::error::ErrorInternalServerError)?;
+    if let Some(history) = history{
+        Ok(HttpResponse::Ok().json(history))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Task {} not found", task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Возвращает задачу к состоянию после указанной записи истории.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос, если передан токен. Записывается в историю задачи.
+/// * `path`        - Уникальные идентификаторы задачи и записи истории.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект задачи.
+
+#[post("/task/{task_uid}/history/{revision_uid}/revert")]
+async fn revert_task(
+    pool: web::Data<DbPool>,
+    actor: Option<Actor>,
+    path: web::Path<(Uuid, Uuid)>
+) -> Result<HttpResponse, Error>{
+    let (task_uid, revision_uid) = path.into_inner();
+    let actor = actor.map(|actor| actor.user_id);
+    let task = web::block(move || {
+        let conn = pool.get()?;
+        controllers::history::revert_task(&task_uid, &revision_uid, actor.as_deref(), &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(task) = task{
+        Ok(HttpResponse::Ok().json(task))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Revision {} of task {} not found", revision_uid, task_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Проверяет имя пользователя или адрес электронной почты и пароль.
+/// Успешный вход и неудачные попытки записываются в журнал аудита.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `meta`        - Сведения о запросе для журнала аудита.
+/// * `credentials` - Данные для входа `login` и `password`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку 401, либо токен `token`, время окончания его действия
+/// `expires_at` и объект пользователя `user`. Токен передаётся в заголовке `Authorization: Bearer <token>`.
+
+#[post("/login")]
+async fn login(pool: web::Data<DbPool>, meta: RequestMeta, credentials: web::Json<Credentials>) -> Result<HttpResponse, Error>{
+    let session = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::login(&credentials, &meta, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(session))
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает страницу журнала аудита, начиная с последних записей.
+/// Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `query`       - Фильтры `action`, `actor_id`, `target_id`, `from`, `to` и параметры страницы `page`, `per_page`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу записей журнала.
+
+#[get("/admin/audit")]
+async fn get_audit_log(pool: web::Data<DbPool>, actor: Actor, query: web::Query<AuditQuery>) -> Result<HttpResponse, Error>{
+    let page = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::audit::get_audit_log(&query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// Метод, обрабатывающий GET запрос. Выгружает журнал аудита по порядку цепочки хэшей.
+/// Выгрузка записывается в журнал. Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `meta`        - Сведения о запросе для журнала аудита.
+/// * `query`       - Фильтры `action`, `actor_id`, `target_id`, `from`, `to` и формат `format=csv|json`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо записи журнала в формате CSV или JSON.
+
+#[get("/admin/audit/export")]
+async fn export_audit_log(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    meta: RequestMeta,
+    query: web::Query<AuditQuery>
+) -> Result<HttpResponse, Error>{
+    let query = query.into_inner();
+    let csv = match query.format.as_deref() {
+        None | Some("csv") => true,
+        Some("json") => false,
+        Some(format) => return Ok(HttpResponse::BadRequest().body(format!("Unknown format {}", format))),
+    };
+    let entries = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        let event = models::AuditEvent{
+            target_type: Some("audit_log".to_string()),
+            details: serde_json::json!({
+                "action": query.action,
+                "actor_id": query.actor_id,
+                "target_id": query.target_id,
+                "from": query.from,
+                "to": query.to,
+            }),
+            ..controllers::audit::event(controllers::audit::ACTION_EXPORT, Some(&actor.user_id), &meta)
+        };
+        controllers::audit::export_audit_log(&query, event, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if csv {
+        Ok(HttpResponse::Ok()
+            .content_type("text/csv; charset=utf-8")
+            .insert_header(ContentDisposition{
+                disposition: DispositionType::Attachment,
+                parameters: vec![DispositionParam::Filename("audit.csv".to_string())],
+            })
+            .body(controllers::audit::audit_to_csv(&entries)))
+    } else {
+        Ok(HttpResponse::Ok().json(entries))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Проверяет целостность цепочки хэшей журнала аудита.
+/// Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо результат проверки.
+
+#[get("/admin/audit/verify")]
+async fn verify_audit_log(pool: web::Data<DbPool>, actor: Actor) -> Result<HttpResponse, Error>{
+    let verification = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::audit::verify_audit_log(&conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(verification))
+}
+
+/// Параметры подключения к потоку событий.
+/// Браузер не может передать заголовок при открытии WebSocket или EventSource,
+/// поэтому токен, выданный при входе, передаётся и в строке запроса.
+#[derive(Deserialize)]
+struct StreamQuery{
+    token: Option<String>,
+}
+
+/// Метод, проверяющий пользователя, подключающегося к потоку событий.
+/// Пользователь берётся из токена в заголовке Authorization, а если заголовок не передан - из токена в строке запроса.
+async fn authenticate_stream(pool: &web::Data<DbPool>, actor: Option<Actor>, token: Option<String>) -> Result<models::User, Error>{
+    let user_id = actor
+        .map(|actor| actor.user_id)
+        .or_else(|| token.as_deref().and_then(crate::auth::verify_token))
+        .ok_or_else(|| actix_web::error::ErrorUnauthorized("Missing or invalid token"))?;
+    let pool = pool.clone();
+    let user = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::authenticate(&user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(user)
+}
+
+/// Метод, обрабатывающий GET запрос. Открывает WebSocket-соединение для получения событий
+/// об изменении задач в реальном времени.
+/// # Arguments
+///
+/// * `req`         - Запрос на подключение.
+/// * `body`        - Поток данных соединения.
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `hub`         - Источник событий. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь из токена в заголовке Authorization, если он передан.
+/// * `query`       - Токен `token`, если заголовок не передан.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку 401, либо ответ на подключение.
+
+#[get("/ws")]
+async fn connect_events(
+    req: HttpRequest,
+    body: web::Payload,
+    pool: web::Data<DbPool>,
+    hub: web::Data<SharedHub>,
+    actor: Option<Actor>,
+    query: web::Query<StreamQuery>
+) -> Result<HttpResponse, Error>{
+    let user = authenticate_stream(&pool, actor, query.into_inner().token).await?;
+    realtime::ws::connect(&req, body, hub.get_ref().clone(), pool.get_ref().clone(), user.id)
+}
+
+/// Параметры потока событий: токен `token`, если не передан заголовок Authorization,
+/// фильтры `projects` и `users` - идентификаторы через запятую,
+/// `last_event_id` - если клиент не может передать заголовок Last-Event-ID.
+#[derive(Deserialize)]
+struct EventsQuery{
+    token: Option<String>,
+    projects: Option<String>,
+    users: Option<String>,
+    last_event_id: Option<i64>,
+}
+
+/// Метод, обрабатывающий GET запрос. Открывает поток событий об изменении задач и пользователей
+/// в формате Server-Sent Events. Переподключившийся клиент получает пропущенные события после Last-Event-ID.
+/// # Arguments
+///
+/// * `req`         - Запрос на подключение.
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `hub`         - Источник событий. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь из токена в заголовке Authorization, если он передан.
+/// * `query`       - Пользователь, фильтры `projects`, `users` и `last_event_id`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо поток событий.
+
+#[get("/events")]
+async fn get_events(
+    req: HttpRequest,
+    pool: web::Data<DbPool>,
+    hub: web::Data<SharedHub>,
+    actor: Option<Actor>,
+    query: web::Query<EventsQuery>
+) -> Result<HttpResponse, Error>{
+    let query = query.into_inner();
+    authenticate_stream(&pool, actor, query.token).await?;
+    let last_event_id = match req.headers().get("Last-Event-ID") {
+        Some(value) => Some(value
+            .to_str()
+            .ok()
+            .and_then(|value| value.trim().parse::<i64>().ok())
+            .ok_or_else(|| actix_web::error::ErrorBadRequest("Invalid Last-Event-ID header"))?),
+        None => query.last_event_id,
+    };
+    let filter = realtime::sse::EventFilter::new(query.projects.as_deref(), query.users.as_deref());
+    realtime::sse::open(hub.get_ref().clone(), pool.get_ref().clone(), filter, last_event_id)
+        .await
+        .map_err(map_error)
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает задачи текущего пользователя, сгруппированные по сроку,
+/// и недавно изменённые задачи, за которыми он наблюдает.
+/// # Arguments
+///
+/// * `pool`    - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`   - Пользователь, выполняющий запрос.
+/// * `query`   - Часовой пояс пользователя (`timezone`) и период для недавно изменённых задач в днях (`days`).
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сгруппированные задачи с их количеством.
+
+#[get("/me/tasks")]
+async fn get_my_tasks(pool: web::Data<DbPool>, actor: Actor, query: web::Query<MyTasksQuery>) -> Result<HttpResponse, Error>{
+    let my_tasks = web::block(move || {
+        let conn = pool.get()?;
+        controllers::tasks::get_my_tasks(&actor.user_id, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(my_tasks))
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает страницу уведомлений текущего пользователя, начиная с последних.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `query`       - Фильтр `unread=true` и параметры страницы `page`, `per_page`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу уведомлений.
+
+#[get("/me/notifications")]
+async fn get_notifications(pool: web::Data<DbPool>, actor: Actor, query: web::Query<NotificationQuery>) -> Result<HttpResponse, Error>{
+    let page = web::block(move || {
+        let conn = pool.get()?;
+        controllers::notifications::get_notifications(&actor.user_id, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(page))
+}
+
+/// Метод, обрабатывающий POST запрос. Отмечает уведомление текущего пользователя прочитанным.
+/// # Arguments
+///
+/// * `pool`                - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`               - Пользователь, выполняющий запрос.
+/// * `notification_uid`    - Уникальный идентификатор уведомления.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект уведомления.
+
+#[post("/me/notifications/{notification_uid}/read")]
+async fn mark_notification_read(pool: web::Data<DbPool>, actor: Actor, notification_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let notification_uid = notification_uid.into_inner();
+    let notification = web::block(move || {
+        let conn = pool.get()?;
+        controllers::notifications::mark_read(&actor.user_id, &notification_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(notification) = notification {
+        Ok(HttpResponse::Ok().json(notification))
+    } else {
+        let res = HttpResponse::NotFound().body(format!("No notification found with uid: {}", notification_uid));
+        Ok(res)
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Отмечает прочитанными все уведомления текущего пользователя.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо количество отмеченных уведомлений.
+
+#[post("/me/notifications/read-all")]
+async fn mark_all_notifications_read(pool: web::Data<DbPool>, actor: Actor) -> Result<HttpResponse, Error>{
+    let updated = web::block(move || {
+        let conn = pool.get()?;
+        controllers::notifications::mark_all_read(&actor.user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "updated": updated })))
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает настройки уведомлений текущего пользователя.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо настройки уведомлений.
+
+#[get("/me/notification-preferences")]
+async fn get_notification_preferences(pool: web::Data<DbPool>, actor: Actor) -> Result<HttpResponse, Error>{
+    let preferences = web::block(move || {
+        let conn = pool.get()?;
+        controllers::notifications::get_preferences(&actor.user_id, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+/// Метод, обрабатывающий PUT запрос. Изменяет настройки уведомлений текущего пользователя.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `update`      - Настройки `assigned`, `mentioned`, `task_changed`. Непереданные настройки не меняются.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо новые настройки уведомлений.
+
+#[put("/me/notification-preferences")]
+async fn update_notification_preferences(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    update: web::Json<NotificationPreferencesUpdate>
+) -> Result<HttpResponse, Error>{
+    let preferences = web::block(move || {
+        let conn = pool.get()?;
+        controllers::notifications::update_preferences(&actor.user_id, &update, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    Ok(HttpResponse::Ok().json(preferences))
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает подписки проекта на события. Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`            - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`           - Пользователь, выполняющий запрос.
+/// * `project_uid`     - Уникальный идентификатор проекта.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо вектор подписок проекта.
+
+#[get("/project/{project_uid}/webhooks")]
+async fn get_webhooks(pool: web::Data<DbPool>, actor: Actor, project_uid: web::Path<Uuid>) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let webhooks = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::webhooks::get_webhooks(&project_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(webhooks) = webhooks{
+        Ok(HttpResponse::Ok().json(webhooks))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Создаёт подписку проекта на события. Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`            - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`           - Пользователь, выполняющий запрос.
+/// * `project_uid`     - Уникальный идентификатор проекта.
+/// * `new_webhook`     - Адрес `url`, ключ подписи `secret`, типы событий `event_types` и флаг `active`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо объект подписки.
+
+#[post("/project/{project_uid}/webhooks")]
+async fn add_webhook(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    project_uid: web::Path<Uuid>,
+    new_webhook: web::Json<NewWebhook>
+) -> Result<HttpResponse, Error>{
+    let project_uid = project_uid.into_inner();
+    let webhook = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::webhooks::create_webhook(&project_uid, &new_webhook, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(webhook) = webhook{
+        Ok(HttpResponse::Ok().json(webhook))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Project {} not found", project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий PUT запрос. Изменяет подписку проекта. Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `path`        - Уникальные идентификаторы проекта и подписки.
+/// * `update`      - Поля `url`, `secret`, `event_types`, `active`. Непереданные поля не меняются.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо измененный объект подписки.
+
+#[put("/project/{project_uid}/webhooks/{webhook_uid}")]
+async fn update_webhook(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    path: web::Path<(Uuid, Uuid)>,
+    update: web::Json<WebhookUpdate>
+) -> Result<HttpResponse, Error>{
+    let (project_uid, webhook_uid) = path.into_inner();
+    let webhook = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::webhooks::update_webhook(&project_uid, &webhook_uid, &update, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(webhook) = webhook{
+        Ok(HttpResponse::Ok().json(webhook))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Webhook {} not found in project {}", webhook_uid, project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий DELETE запрос. Удаляет подписку проекта вместе с журналом отправок.
+/// Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `path`        - Уникальные идентификаторы проекта и подписки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо сообщение об успешном удалении подписки.
+
+#[delete("/project/{project_uid}/webhooks/{webhook_uid}")]
+async fn delete_webhook(pool: web::Data<DbPool>, actor: Actor, path: web::Path<(Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (project_uid, webhook_uid) = path.into_inner();
+    let result = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::webhooks::delete_webhook(&project_uid, &webhook_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if result{
+        Ok(HttpResponse::Ok().body(format!("Webhook {} deleted", webhook_uid)))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Webhook {} not found in project {}", webhook_uid, project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий GET запрос. Возвращает журнал отправок подписки, начиная с последних,
+/// с кодами ответов получателя. Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `path`        - Уникальные идентификаторы проекта и подписки.
+/// * `query`       - Фильтр `status` и параметры страницы `page`, `per_page`.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо страницу отправок.
+
+#[get("/project/{project_uid}/webhooks/{webhook_uid}/deliveries")]
+async fn get_webhook_deliveries(
+    pool: web::Data<DbPool>,
+    actor: Actor,
+    path: web::Path<(Uuid, Uuid)>,
+    query: web::Query<DeliveryQuery>
+) -> Result<HttpResponse, Error>{
+    let (project_uid, webhook_uid) = path.into_inner();
+    let deliveries = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::webhooks::get_deliveries(&project_uid, &webhook_uid, &query, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(deliveries) = deliveries{
+        Ok(HttpResponse::Ok().json(deliveries))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Webhook {} not found in project {}", webhook_uid, project_uid)))
+    }
+}
+
+/// Метод, обрабатывающий POST запрос. Повторно ставит событие отправки в очередь. Доступен только администраторам.
+/// # Arguments
+///
+/// * `pool`        - Пул базы данных. Данный аргумент обрабатывается фреймворком Actix.
+/// * `actor`       - Пользователь, выполняющий запрос.
+/// * `path`        - Уникальные идентификаторы проекта, подписки и исходной отправки.
+///
+/// # Return
+///
+/// Возвращает Результат с ответом, содержащим либо ошибку, либо новую отправку.
+
+#[post("/project/{project_uid}/webhooks/{webhook_uid}/deliveries/{delivery_uid}/redeliver")]
+async fn redeliver_webhook(pool: web::Data<DbPool>, actor: Actor, path: web::Path<(Uuid, Uuid, Uuid)>) -> Result<HttpResponse, Error>{
+    let (project_uid, webhook_uid, delivery_uid) = path.into_inner();
+    let delivery = web::block(move || {
+        let conn = pool.get()?;
+        controllers::users::require_admin(&actor.user_id, &conn)?;
+        controllers::webhooks::redeliver(&project_uid, &webhook_uid, &delivery_uid, &conn)
+    })
+    .await?
+    .map_err(map_error)?;
+    if let Some(delivery) = delivery{
+        Ok(HttpResponse::Ok().json(delivery))
+    } else {
+        Ok(HttpResponse::NotFound().body(format!("Delivery {} not found for webhook {}", delivery_uid, webhook_uid)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_single_byte_ranges(){
+        assert_eq!(parse_range("bytes=0-99", 1000), Ok(Some((0, 99))));
+        assert_eq!(parse_range("bytes=500-", 1000), Ok(Some((500, 999))));
+        assert_eq!(parse_range("bytes=-200", 1000), Ok(Some((800, 999))));
+        assert_eq!(parse_range(" bytes= 0-9 ", 1000), Ok(Some((0, 9))));
+    }
+
+    #[test]
+    fn clamps_ranges_to_file_size(){
+        assert_eq!(parse_range("bytes=900-5000", 1000), Ok(Some((900, 999))));
+        assert_eq!(parse_range("bytes=-2000", 1000), Ok(Some((0, 999))));
+        assert_eq!(parse_range("bytes=999-999", 1000), Ok(Some((999, 999))));
+    }
+
+    #[test]
+    fn rejects_ranges_beyond_the_file(){
+        assert_eq!(parse_range("bytes=1000-", 1000), Err(()));
+        assert_eq!(parse_range("bytes=1000-1200", 1000), Err(()));
+        assert_eq!(parse_range("bytes=0-0", 0), Err(()));
+        assert_eq!(parse_range("bytes=-10", 0), Err(()));
+    }
+
+    #[test]
+    fn ignores_unsupported_or_malformed_ranges(){
+        for value in ["bytes=0-1,5-6", "items=0-1", "bytes=abc", "bytes=5-2", "bytes=-0", "bytes=-", "bytes=0x1-2", ""] {
+            assert_eq!(parse_range(value, 1000), Ok(None), "{} must be ignored", value);
+        }
+    }
+}
@@ -1,4 +1,3 @@
-
 /// Макрос для работы с таблицей tasks
 table! {
     tasks (id) {
@@ -9,6 +8,14 @@ table! {
         user_id -> Nullable<Varchar>,
         created_at -> Timestamp,
         updated_at -> Nullable<Timestamp>,
+        project_id -> Nullable<Varchar>,
+        parent_id -> Nullable<Varchar>,
+        due_at -> Nullable<Timestamp>,
+        estimate_minutes -> Nullable<Int4>,
+        series_id -> Nullable<Varchar>,
+        estimate_points -> Nullable<Int4>,
+        sprint_id -> Nullable<Varchar>,
+        status -> Varchar,
     }
 }
 
@@ -25,9 +32,381 @@ table! {
     }
 }
 
+/// Макрос для работы с таблицей projects
+table! {
+    projects (id) {
+        id -> Varchar,
+        name -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+        storage_quota -> Nullable<Int8>,
+    }
+}
+
+/// Макрос для работы с таблицей labels
+table! {
+    labels (id) {
+        id -> Varchar,
+        name -> Varchar,
+        color -> Varchar,
+        project_id -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+/// Макрос для работы с таблицей task_labels
+table! {
+    task_labels (task_id, label_id) {
+        task_id -> Varchar,
+        label_id -> Varchar,
+    }
+}
+
+/// Макрос для работы с таблицей task_links
+table! {
+    task_links (id) {
+        id -> Varchar,
+        source_id -> Varchar,
+        target_id -> Varchar,
+        link_type -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей comments
+table! {
+    comments (id) {
+        id -> Varchar,
+        task_id -> Varchar,
+        user_id -> Varchar,
+        body -> Varchar,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+/// Макрос для работы с таблицей comment_revisions
+table! {
+    comment_revisions (id) {
+        id -> Varchar,
+        comment_id -> Varchar,
+        body -> Varchar,
+        edited_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей comment_mentions
+table! {
+    comment_mentions (comment_id, user_id) {
+        comment_id -> Varchar,
+        user_id -> Varchar,
+    }
+}
+
+/// Макрос для работы с таблицей attachments
+table! {
+    attachments (id) {
+        id -> Varchar,
+        task_id -> Varchar,
+        file_name -> Varchar,
+        size -> Int8,
+        content_type -> Varchar,
+        checksum -> Varchar,
+        storage_key -> Varchar,
+        uploaded_by -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        preview_status -> Nullable<Varchar>,
+    }
+}
+
+/// Макрос для работы с таблицей attachment_thumbnails
+table! {
+    attachment_thumbnails (id) {
+        id -> Varchar,
+        attachment_id -> Varchar,
+        size_name -> Varchar,
+        width -> Int4,
+        height -> Int4,
+        content_type -> Varchar,
+        size -> Int8,
+        storage_key -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей checklist_items
+table! {
+    checklist_items (id) {
+        id -> Varchar,
+        task_id -> Varchar,
+        position -> Int4,
+        text -> Varchar,
+        checked -> Bool,
+        checked_by -> Nullable<Varchar>,
+        checked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей task_series
+table! {
+    task_series (id) {
+        id -> Varchar,
+        rrule -> Varchar,
+        timezone -> Varchar,
+        dtstart -> Timestamp,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей worklogs
+table! {
+    worklogs (id) {
+        id -> Varchar,
+        task_id -> Varchar,
+        user_id -> Varchar,
+        started_at -> Timestamp,
+        duration_seconds -> Nullable<Int4>,
+        note -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей sprints
+table! {
+    sprints (id) {
+        id -> Varchar,
+        project_id -> Varchar,
+        name -> Varchar,
+        start_date -> Date,
+        end_date -> Date,
+        goal -> Nullable<Varchar>,
+        closed_at -> Nullable<Timestamp>,
+        committed_points -> Nullable<Int4>,
+        completed_points -> Nullable<Int4>,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+/// Макрос для работы с таблицей task_status_changes
+table! {
+    task_status_changes (id) {
+        id -> Varchar,
+        task_id -> Varchar,
+        from_status -> Nullable<Varchar>,
+        to_status -> Varchar,
+        changed_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей task_revisions
+table! {
+    task_revisions (id) {
+        id -> Varchar,
+        task_id -> Varchar,
+        action -> Varchar,
+        actor_id -> Nullable<Varchar>,
+        changes -> Jsonb,
+        snapshot -> Jsonb,
+        changed_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей audit_log
+table! {
+    audit_log (id) {
+        id -> Varchar,
+        seq -> Int8,
+        action -> Varchar,
+        actor_id -> Nullable<Varchar>,
+        target_type -> Nullable<Varchar>,
+        target_id -> Nullable<Varchar>,
+        details -> Jsonb,
+        ip -> Nullable<Varchar>,
+        user_agent -> Nullable<Varchar>,
+        request_id -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        prev_hash -> Varchar,
+        hash -> Varchar,
+    }
+}
+
+/// Макрос для работы с таблицей events
+table! {
+    events (id) {
+        id -> Int8,
+        kind -> Varchar,
+        entity_type -> Varchar,
+        entity_id -> Varchar,
+        project_id -> Nullable<Varchar>,
+        sprint_id -> Nullable<Varchar>,
+        user_id -> Nullable<Varchar>,
+        actor_id -> Nullable<Varchar>,
+        payload -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей notifications
+table! {
+    notifications (id) {
+        id -> Varchar,
+        user_id -> Varchar,
+        kind -> Varchar,
+        task_id -> Nullable<Varchar>,
+        comment_id -> Nullable<Varchar>,
+        actor_id -> Nullable<Varchar>,
+        details -> Jsonb,
+        read_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей notification_preferences
+table! {
+    notification_preferences (user_id) {
+        user_id -> Varchar,
+        assigned -> Bool,
+        mentioned -> Bool,
+        task_changed -> Bool,
+        updated_at -> Timestamp,
+        email_mode -> Varchar,
+        locale -> Varchar,
+        due_soon -> Bool,
+        digest_sent_at -> Nullable<Timestamp>,
+    }
+}
+
+/// Макрос для работы с таблицей task_watchers
+table! {
+    task_watchers (task_id, user_id) {
+        task_id -> Varchar,
+        user_id -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей task_assignees
+table! {
+    task_assignees (task_id, user_id) {
+        task_id -> Varchar,
+        user_id -> Varchar,
+        role -> Varchar,
+        created_at -> Timestamp,
+    }
+}
+
+/// Макрос для работы с таблицей webhooks
+table! {
+    webhooks (id) {
+        id -> Varchar,
+        project_id -> Varchar,
+        url -> Varchar,
+        secret -> Varchar,
+        event_types -> Array<Text>,
+        active -> Bool,
+        created_at -> Timestamp,
+        updated_at -> Nullable<Timestamp>,
+    }
+}
+
+/// Макрос для работы с таблицей webhook_deliveries
+table! {
+    webhook_deliveries (id) {
+        id -> Varchar,
+        webhook_id -> Varchar,
+        event_id -> Int8,
+        event_type -> Varchar,
+        payload -> Jsonb,
+        status -> Varchar,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        response_status -> Nullable<Int4>,
+        response_body -> Nullable<Text>,
+        last_error -> Nullable<Varchar>,
+        redelivery_of -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        delivered_at -> Nullable<Timestamp>,
+    }
+}
+
+/// Макрос для работы с таблицей email_outbox
+table! {
+    email_outbox (id) {
+        id -> Varchar,
+        user_id -> Nullable<Varchar>,
+        recipient -> Varchar,
+        kind -> Varchar,
+        subject -> Varchar,
+        body -> Text,
+        dedupe_key -> Nullable<Varchar>,
+        status -> Varchar,
+        attempts -> Int4,
+        next_attempt_at -> Timestamp,
+        last_error -> Nullable<Varchar>,
+        created_at -> Timestamp,
+        sent_at -> Nullable<Timestamp>,
+    }
+}
+
 joinable!(tasks -> users (user_id));
+joinable!(tasks -> projects (project_id));
+joinable!(tasks -> task_series (series_id));
+joinable!(tasks -> sprints (sprint_id));
+joinable!(sprints -> projects (project_id));
+joinable!(task_status_changes -> tasks (task_id));
+joinable!(labels -> projects (project_id));
+joinable!(task_labels -> tasks (task_id));
+joinable!(task_labels -> labels (label_id));
+joinable!(attachment_thumbnails -> attachments (attachment_id));
+joinable!(attachments -> tasks (task_id));
+joinable!(attachments -> users (uploaded_by));
+joinable!(checklist_items -> tasks (task_id));
+joinable!(checklist_items -> users (checked_by));
+joinable!(comments -> tasks (task_id));
+joinable!(comments -> users (user_id));
+joinable!(comment_revisions -> comments (comment_id));
+joinable!(comment_mentions -> comments (comment_id));
+joinable!(comment_mentions -> users (user_id));
+joinable!(worklogs -> tasks (task_id));
+joinable!(worklogs -> users (user_id));
+joinable!(notifications -> users (user_id));
+joinable!(notification_preferences -> users (user_id));
+joinable!(email_outbox -> users (user_id));
+joinable!(task_watchers -> tasks (task_id));
+joinable!(task_watchers -> users (user_id));
+joinable!(task_assignees -> tasks (task_id));
+joinable!(task_assignees -> users (user_id));
+joinable!(webhooks -> projects (project_id));
+joinable!(webhook_deliveries -> webhooks (webhook_id));
 
 allow_tables_to_appear_in_same_query!(
+    attachment_thumbnails,
+    attachments,
+    audit_log,
+    checklist_items,
+    comment_mentions,
+    comment_revisions,
+    comments,
+    email_outbox,
+    events,
+    labels,
+    notification_preferences,
+    notifications,
+    projects,
+    sprints,
+    task_assignees,
+    task_labels,
+    task_links,
+    task_revisions,
+    task_series,
+    task_status_changes,
+    task_watchers,
     tasks,
     users,
+    webhook_deliveries,
+    webhooks,
+    worklogs,
 );
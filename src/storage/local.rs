@@ -0,0 +1,61 @@
+use std::fs::{self, File};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+
+use super::{Storage, StorageError};
+
+/// Хранилище файлов в локальной файловой системе.
+/// Ключ объекта используется как относительный путь внутри корневого каталога.
+pub struct LocalStorage{
+    root: PathBuf,
+}
+
+impl LocalStorage{
+    /// Метод, создающий хранилище и его корневой каталог.
+    pub fn new(root: impl Into<PathBuf>) -> io::Result<Self>{
+        let root = root.into();
+        fs::create_dir_all(&root)?;
+        Ok(LocalStorage{ root })
+    }
+
+    /// Метод, возвращающий путь к объекту. Ключи с переходом в родительский каталог запрещены.
+    fn path(&self, key: &str) -> Result<PathBuf, StorageError>{
+        if key.split('/').any(|part| part.is_empty() || part == "." || part == "..") {
+            return Err(format!("Invalid storage key {}", key).into());
+        }
+        Ok(self.root.join(key))
+    }
+}
+
+impl Storage for LocalStorage{
+    fn put(&self, key: &str, source: &Path, _size: u64, _checksum: &str) -> Result<(), StorageError>{
+        let path = self.path(key)?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        // Переименование не работает между файловыми системами, поэтому при ошибке файл копируется
+        if fs::rename(source, &path).is_err() {
+            fs::copy(source, &path)?;
+        }
+        Ok(())
+    }
+
+    fn open(&self, key: &str, range: Option<(u64, u64)>) -> Result<Box<dyn Read + Send>, StorageError>{
+        let mut file = File::open(self.path(key)?)?;
+        match range {
+            Some((start, end)) => {
+                file.seek(SeekFrom::Start(start))?;
+                Ok(Box::new(file.take(end - start + 1)))
+            },
+            None => Ok(Box::new(file)),
+        }
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StorageError>{
+        match fs::remove_file(self.path(key)?) {
+            Ok(()) => Ok(()),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
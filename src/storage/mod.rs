@@ -0,0 +1,52 @@
+mod local;
+mod s3;
+
+use std::io::Read;
+use std::path::Path;
+use std::sync::Arc;
+
+pub use local::LocalStorage;
+pub use s3::S3Storage;
+
+/// Тип ошибок, возникающих при работе с хранилищем файлов
+pub type StorageError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Публичный тип разделяемого хранилища файлов
+pub type SharedStorage = Arc<dyn Storage>;
+
+/// Хранилище содержимого файлов. Метаданные файлов хранятся в базе данных,
+/// хранилище отвечает только за содержимое, адресуемое ключом.
+pub trait Storage: Send + Sync{
+    /// Метод, сохраняющий содержимое файла по ключу.
+    /// # Arguments
+    ///
+    /// * `key`         - ключ объекта в хранилище.
+    /// * `source`      - путь к временному файлу с содержимым.
+    /// * `size`        - размер содержимого в байтах.
+    /// * `checksum`    - SHA-256 содержимого в шестнадцатеричном виде.
+    fn put(&self, key: &str, source: &Path, size: u64, checksum: &str) -> Result<(), StorageError>;
+
+    /// Метод, открывающий содержимое файла на чтение.
+    /// # Arguments
+    ///
+    /// * `key`         - ключ объекта в хранилище.
+    /// * `range`       - включительный диапазон байт. None - весь файл.
+    fn open(&self, key: &str, range: Option<(u64, u64)>) -> Result<Box<dyn Read + Send>, StorageError>;
+
+    /// Метод, удаляющий содержимое файла. Отсутствие объекта ошибкой не считается.
+    fn delete(&self, key: &str) -> Result<(), StorageError>;
+}
+
+/// Метод инициализации хранилища файлов.
+/// Тип хранилища задаётся переменной окружения STORAGE_BACKEND (`local` по умолчанию или `s3`).
+pub fn init_storage() -> SharedStorage{
+    let backend = std::env::var("STORAGE_BACKEND").unwrap_or_else(|_| "local".to_string());
+    match backend.as_str() {
+        "local" => {
+            let root = std::env::var("STORAGE_PATH").unwrap_or_else(|_| "storage".to_string());
+            Arc::new(LocalStorage::new(root).expect("Failed to create storage directory"))
+        },
+        "s3" => Arc::new(S3Storage::from_env()),
+        other => panic!("Unknown STORAGE_BACKEND {}", other),
+    }
+}
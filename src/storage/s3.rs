@@ -0,0 +1,116 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+
+use hmac::{Hmac, Mac};
+use sha2::{Digest, Sha256};
+
+use super::{Storage, StorageError};
+
+/// Хранилище файлов, совместимое с Amazon S3 (в том числе MinIO).
+/// Запросы подписываются по схеме AWS Signature Version 4, бакет адресуется в пути URL.
+pub struct S3Storage{
+    endpoint: String,
+    bucket: String,
+    region: String,
+    access_key: String,
+    secret_key: String,
+}
+
+impl S3Storage{
+    /// Метод, создающий хранилище по переменным окружения
+    /// S3_ENDPOINT, S3_BUCKET, S3_REGION (по умолчанию `us-east-1`), S3_ACCESS_KEY и S3_SECRET_KEY.
+    pub fn from_env() -> Self{
+        S3Storage{
+            endpoint: std::env::var("S3_ENDPOINT").expect("S3_ENDPOINT").trim_end_matches('/').to_string(),
+            bucket: std::env::var("S3_BUCKET").expect("S3_BUCKET"),
+            region: std::env::var("S3_REGION").unwrap_or_else(|_| "us-east-1".to_string()),
+            access_key: std::env::var("S3_ACCESS_KEY").expect("S3_ACCESS_KEY"),
+            secret_key: std::env::var("S3_SECRET_KEY").expect("S3_SECRET_KEY"),
+        }
+    }
+
+    /// Метод, формирующий подписанный запрос к объекту.
+    fn request(&self, method: &str, key: &str, payload_hash: &str) -> ureq::Request{
+        let path = format!("/{}/{}", uri_encode(&self.bucket), key.split('/').map(uri_encode).collect::<Vec<_>>().join("/"));
+        let host = self.endpoint
+            .split("://")
+            .nth(1)
+            .unwrap_or(&self.endpoint)
+            .to_string();
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+
+        let signed_headers = "host;x-amz-content-sha256;x-amz-date";
+        let canonical_request = format!(
+            "{}\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            method, path, host, payload_hash, amz_date, signed_headers, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date, scope, hex::encode(Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key_bytes = hmac(format!("AWS4{}", self.secret_key).as_bytes(), date.as_bytes());
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key_bytes = hmac(&key_bytes, part.as_bytes());
+        }
+        let signature = hex::encode(hmac(&key_bytes, string_to_sign.as_bytes()));
+        let authorization = format!(
+            "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+            self.access_key, scope, signed_headers, signature
+        );
+
+        ureq::request(method, &format!("{}{}", self.endpoint, path))
+            .set("x-amz-content-sha256", payload_hash)
+            .set("x-amz-date", &amz_date)
+            .set("Authorization", &authorization)
+    }
+}
+
+impl Storage for S3Storage{
+    fn put(&self, key: &str, source: &Path, size: u64, checksum: &str) -> Result<(), StorageError>{
+        let file = File::open(source)?;
+        self.request("PUT", key, checksum)
+            .set("Content-Length", &size.to_string())
+            .send(file)?;
+        Ok(())
+    }
+
+    fn open(&self, key: &str, range: Option<(u64, u64)>) -> Result<Box<dyn Read + Send>, StorageError>{
+        let mut request = self.request("GET", key, EMPTY_PAYLOAD_HASH);
+        if let Some((start, end)) = range {
+            request = request.set("Range", &format!("bytes={}-{}", start, end));
+        }
+        Ok(request.call()?.into_reader())
+    }
+
+    fn delete(&self, key: &str) -> Result<(), StorageError>{
+        match self.request("DELETE", key, EMPTY_PAYLOAD_HASH).call() {
+            Ok(_) | Err(ureq::Error::Status(404, _)) => Ok(()),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+/// SHA-256 пустого тела запроса
+const EMPTY_PAYLOAD_HASH: &str = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+
+/// Метод вычисления HMAC-SHA256.
+fn hmac(key: &[u8], data: &[u8]) -> Vec<u8>{
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts keys of any size");
+    mac.update(data);
+    mac.finalize().into_bytes().to_vec()
+}
+
+/// Метод кодирования сегмента пути по правилам AWS (все символы, кроме незарезервированных).
+fn uri_encode(segment: &str) -> String{
+    segment
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,277 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::io::{self, Read};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::time::Duration;
+
+use crate::controllers::webhooks::{self, AttemptResult};
+use crate::database::DbPool;
+use crate::models::{Webhook, WebhookDelivery};
+
+/// Тип ошибок, возникающих при отправке событий
+pub type SendError = Box<dyn std::error::Error + Send + Sync>;
+
+/// Интервал проверки очереди отправок
+const SEND_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Количество отправок, выполняемых за одну проверку очереди
+const SEND_BATCH: i64 = 20;
+
+/// Время ожидания ответа получателя
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Максимальный размер читаемого ответа получателя в байтах
+const MAX_RESPONSE_BYTES: u64 = 4096;
+
+/// Отправитель событий по подпискам проектов
+struct Sender{
+    agent: ureq::Agent,
+}
+
+impl Sender{
+    /// Метод, создающий отправителя. Перенаправления не выполняются: подписка должна указывать конечный адрес.
+    /// Адрес получателя проверяется при каждом соединении, поэтому имя, которое после создания подписки
+    /// стало указывать на внутреннюю сеть, не позволяет отправить на него запрос.
+    fn new() -> Self{
+        let agent = ureq::AgentBuilder::new()
+            .timeout(REQUEST_TIMEOUT)
+            .redirects(0)
+            .resolver(resolve)
+            .build();
+        Sender{ agent }
+    }
+
+    /// Метод, отправляющий событие POST-запросом.
+    /// Время отправки в секундах Unix передаётся в заголовке `X-Webhook-Timestamp`.
+    /// Строка `<timestamp>.<body>` подписывается HMAC-SHA256 с ключом подписки, подпись передаётся в заголовке
+    /// `X-Webhook-Signature-256` в виде `sha256=<hex>`. Получатель вычисляет подпись тем же способом,
+    /// сравнивает её за постоянное время и отклоняет запросы со слишком старым временем отправки,
+    /// поэтому перехваченный запрос нельзя повторить позже.
+    fn send(&self, webhook: &Webhook, delivery: &WebhookDelivery) -> AttemptResult{
+        let body = delivery.payload.to_string();
+        let timestamp = chrono::Utc::now().timestamp().to_string();
+        let response = self.agent
+            .post(&webhook.url)
+            .set("Content-Type", "application/json")
+            .set("User-Agent", "back-webhooks")
+            .set("X-Webhook-Id", &webhook.id)
+            .set("X-Webhook-Delivery", &delivery.id)
+            .set("X-Webhook-Event", &delivery.event_type)
+            .set("X-Webhook-Timestamp", &timestamp)
+            .set("X-Webhook-Signature-256", &format!("sha256={}", sign(&webhook.secret, &timestamp, &body)))
+            .send_string(&body);
+        match response {
+            Ok(response) | Err(ureq::Error::Status(_, response)) => {
+                let status = response.status() as i32;
+                let mut text = String::new();
+                let body = response
+                    .into_reader()
+                    .take(MAX_RESPONSE_BYTES)
+                    .read_to_string(&mut text)
+                    .map(|_| text);
+                AttemptResult{ status: Some(status), body: body.ok(), error: None }
+            },
+            Err(err) => AttemptResult{ error: Some(err.to_string()), ..Default::default() },
+        }
+    }
+
+    /// Метод, в цикле выполняющий отправки из очереди. Выполняется в отдельном потоке.
+    fn run(&self, pool: DbPool){
+        loop {
+            let result = pool
+                .get()
+                .map_err(SendError::from)
+                .and_then(|conn| webhooks::send_pending(SEND_BATCH, |webhook, delivery| self.send(webhook, delivery), &conn));
+            match result {
+                // Полная партия означает, что в очереди могут остаться отправки
+                Ok(processed) if processed as i64 == SEND_BATCH => continue,
+                Ok(_) => {},
+                Err(err) => log::error!("Failed to send webhooks: {}", err),
+            }
+            std::thread::sleep(SEND_INTERVAL);
+        }
+    }
+}
+
+/// Метод, вычисляющий подпись строки `<timestamp>.<body>` ключом подписки.
+fn sign(secret: &str, timestamp: &str, body: &str) -> String{
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(timestamp.as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    hex::encode(mac.finalize().into_bytes())
+}
+
+/// Метод, определяющий адреса получателя. Адреса внутренней сети отбрасываются,
+/// если они не разрешены переменной окружения WEBHOOKS_ALLOW_PRIVATE_HOSTS.
+fn resolve(netloc: &str) -> io::Result<Vec<SocketAddr>>{
+    let addresses: Vec<SocketAddr> = netloc.to_socket_addrs()?.collect();
+    if webhooks::private_hosts_allowed() {
+        return Ok(addresses);
+    }
+    let public: Vec<SocketAddr> = addresses
+        .into_iter()
+        .filter(|address| webhooks::is_public_address(&address.ip()))
+        .collect();
+    if public.is_empty() {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} does not resolve to a public address", netloc),
+        ));
+    }
+    Ok(public)
+}
+
+/// Метод инициализации отправки событий по подпискам проектов.
+pub fn init_webhooks(pool: DbPool){
+    let sender = Sender::new();
+    std::thread::Builder::new()
+        .name("webhook-sender".to_string())
+        .spawn(move || sender.run(pool))
+        .expect("Failed to start webhook sender");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+
+    #[test]
+    fn signs_timestamp_and_body(){
+        assert_eq!(
+            sign("s3cret", "1700000000", r#"{"event":"task.created"}"#),
+            "a117179a43a06042c6d857a94703dfefa1bf48c88d041636f8e5199d04a4d308"
+        );
+        assert_ne!(sign("s3cret", "1700000001", r#"{"event":"task.created"}"#), sign("s3cret", "1700000000", r#"{"event":"task.created"}"#));
+    }
+
+    #[test]
+    fn resolver_drops_private_addresses(){
+        if webhooks::private_hosts_allowed() {
+            return;
+        }
+        assert!(resolve("127.0.0.1:80").is_err());
+        assert!(resolve("[::1]:80").is_err());
+        assert_eq!(resolve("8.8.8.8:443").unwrap(), vec!["8.8.8.8:443".parse::<SocketAddr>().unwrap()]);
+    }
+
+    /// Заголовки и тело запроса, принятого локальным получателем
+    type Received = mpsc::Receiver<(Vec<(String, String)>, String)>;
+
+    /// Метод, принимающий один запрос на локальном порту и отвечающий на него указанной строкой статуса.
+    /// Возвращает адрес получателя и канал, в который передаются заголовки и тело принятого запроса.
+    fn receiver(status_line: &'static str) -> (String, Received){
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (sender, received) = mpsc::channel();
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut headers = Vec::new();
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end();
+                if line.is_empty() {
+                    break;
+                }
+                if let Some((name, value)) = line.split_once(':') {
+                    headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+                }
+            }
+            let length: usize = headers
+                .iter()
+                .find(|(name, _)| name == "content-length")
+                .map(|(_, value)| value.parse().unwrap())
+                .unwrap_or(0);
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let mut stream = stream;
+            write!(stream, "{}\r\nContent-Length: 4\r\nConnection: close\r\n\r\nboom", status_line).unwrap();
+            sender.send((headers, String::from_utf8(body).unwrap())).unwrap();
+        });
+        (url, received)
+    }
+
+    fn webhook(url: &str) -> Webhook{
+        let now = chrono::Utc::now().naive_utc();
+        Webhook{
+            id: "webhook".to_string(),
+            project_id: "project".to_string(),
+            url: url.to_string(),
+            secret: "s3cret".to_string(),
+            event_types: vec!["*".to_string()],
+            active: true,
+            created_at: now,
+            updated_at: None,
+        }
+    }
+
+    fn delivery() -> WebhookDelivery{
+        let now = chrono::Utc::now().naive_utc();
+        WebhookDelivery{
+            id: "delivery".to_string(),
+            webhook_id: "webhook".to_string(),
+            event_id: 1,
+            event_type: "task.created".to_string(),
+            payload: serde_json::json!({ "event": "task.created" }),
+            status: webhooks::STATUS_SENDING.to_string(),
+            attempts: 0,
+            next_attempt_at: now,
+            response_status: None,
+            response_body: None,
+            last_error: None,
+            redelivery_of: None,
+            created_at: now,
+            delivered_at: None,
+        }
+    }
+
+    /// Отправитель без проверки адресов, чтобы запрос дошёл до локального получателя.
+    fn local_sender() -> Sender{
+        Sender{ agent: ureq::AgentBuilder::new().timeout(REQUEST_TIMEOUT).redirects(0).build() }
+    }
+
+    #[test]
+    fn sends_signed_request(){
+        let (url, received) = receiver("HTTP/1.1 200 OK");
+        let result = local_sender().send(&webhook(&url), &delivery());
+        let (headers, body) = received.recv().unwrap();
+        let header = |name: &str| headers.iter().find(|(header, _)| header == name).map(|(_, value)| value.clone()).unwrap();
+
+        assert_eq!(result.status, Some(200));
+        assert_eq!(result.body.as_deref(), Some("boom"));
+        assert_eq!(result.error, None);
+        assert_eq!(body, r#"{"event":"task.created"}"#);
+        assert_eq!(header("x-webhook-event"), "task.created");
+        assert_eq!(header("x-webhook-delivery"), "delivery");
+        let timestamp = header("x-webhook-timestamp");
+        assert!((chrono::Utc::now().timestamp() - timestamp.parse::<i64>().unwrap()).abs() < 60);
+        assert_eq!(header("x-webhook-signature-256"), format!("sha256={}", sign("s3cret", &timestamp, &body)));
+    }
+
+    #[test]
+    fn error_status_is_reported_with_body(){
+        let (url, _received) = receiver("HTTP/1.1 500 Internal Server Error");
+        let result = local_sender().send(&webhook(&url), &delivery());
+
+        assert_eq!(result.status, Some(500));
+        assert_eq!(result.body.as_deref(), Some("boom"));
+        assert_eq!(result.error, None);
+    }
+
+    #[test]
+    fn default_sender_refuses_local_receiver(){
+        if webhooks::private_hosts_allowed() {
+            return;
+        }
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let result = Sender::new().send(&webhook(&url), &delivery());
+
+        assert_eq!(result.status, None);
+        assert!(result.error.unwrap().contains("does not resolve to a public address"));
+    }
+}